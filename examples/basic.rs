@@ -1,6 +1,8 @@
 extern crate uni_app;
 extern crate uni_gl;
 
+use uni_gl::GlBackend;
+
 fn main() {
     // create the game window (native) or canvas (web)
     let app = uni_app::App::new(uni_app::AppConfig {
@@ -0,0 +1,600 @@
+use capabilities::Capabilities;
+use common::*;
+use error::GlError;
+use extensions::GlExtensions;
+use glenum::*;
+use pipeline::PipelineState;
+use tracking::{LiveObject, ObjectRef};
+
+/// The OpenGL API shared by every uni-gl backend.
+///
+/// Each target provides its own [`GLContext`] implementing this trait, so engine code
+/// written against it compiles (or fails to compile) the same way on native and web.
+/// The associated types are the raw object handles wrapped by [`WebGLBuffer`], [`WebGLShader`]...
+///
+/// This doc is not intended to cover all OpenGL API in depth.
+/// Check [https://www.khronos.org/opengl/](https://www.khronos.org/opengl/) for more information.
+pub trait GlBackend {
+    /// raw buffer handle
+    type Buffer;
+    /// raw shader handle
+    type Shader;
+    /// raw program handle
+    type Program;
+    /// raw texture handle
+    type Texture;
+    /// raw vertex array object handle
+    type VertexArray;
+    /// raw uniform location handle
+    type UniformLocation;
+    /// raw framebuffer handle
+    type FrameBuffer;
+    /// raw renderbuffer handle
+    type Renderbuffer;
+    /// raw query handle
+    type Query;
+
+    /// return the error flag raised by the last calls, if any
+    fn get_error(&self) -> Result<(), GlError>;
+
+    /// whether the context is lost, every object created with it being invalid
+    fn is_context_lost(&self) -> bool;
+
+    /// give a name to an object, shown by [`GlBackend::live_objects`] and the leak report.
+    ///
+    /// With [`GlExtensions::debug_output`], it also becomes the object label shown by GPU debuggers.
+    fn set_object_name<'a, O: Into<ObjectRef<'a, Self>>>(&self, object: O, name: &str)
+    where
+        Self: Sized + 'a;
+
+    /// every object created and not deleted yet, with its creation site and estimated size.
+    ///
+    /// Objects still alive when the context is dropped are reported as leaks.
+    fn live_objects(&self) -> Vec<LiveObject>;
+
+    /// version, extensions and limits of the context, gathered when it was created
+    fn capabilities(&self) -> &Capabilities;
+
+    /// names of the extensions supported by the context, sorted
+    fn get_supported_extensions(&self) -> Vec<String>;
+
+    /// optional features of the context, detected and enabled when it was created
+    fn extensions(&self) -> &GlExtensions;
+
+    /// create a new OpenGL buffer
+    #[track_caller]
+    fn create_buffer(&self) -> Result<WebGLBuffer<Self::Buffer>, GlError>;
+
+    /// delete an existing buffer
+    fn delete_buffer(&self, buffer: &WebGLBuffer<Self::Buffer>);
+
+    /// bind a buffer to current state.
+    fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<Self::Buffer>);
+
+    /// fills a buffer with data.
+    ///
+    /// kind : see [`GlBackend::bind_buffer`].
+    fn buffer_data(&self, kind: BufferKind, data: &[u8], draw: DrawMode) -> Result<(), GlError>;
+
+    /// update a subset of a buffer
+    ///
+    /// kind : see [`GlBackend::bind_buffer`].
+    ///
+    /// offset : offset in the buffer where data replacement will begin
+    fn buffer_sub_data(&self, kind: BufferKind, offset: u32, data: &[u8]);
+
+    /// this buffer is not bound to the current state anymore.
+    fn unbind_buffer(&self, kind: BufferKind);
+
+    /// create a new shader.
+    #[track_caller]
+    fn create_shader(&self, kind: ShaderKind) -> Result<WebGLShader<Self::Shader>, GlError>;
+
+    /// set or replace the source code in a shader
+    fn shader_source(&self, shader: &WebGLShader<Self::Shader>, source: &str);
+
+    /// set the source code of a shader to `header` (e.g. a `#version` line and defines) followed by `source`.
+    ///
+    /// Lines reported by [`GlError::shader_diagnostics`] are then lines of `source`.
+    fn shader_source_with_header(
+        &self,
+        shader: &WebGLShader<Self::Shader>,
+        header: &str,
+        source: &str,
+    );
+
+    /// compile a shader
+    ///
+    /// returns [`GlError::ShaderCompile`] with the driver info log if compilation failed
+    fn compile_shader(&self, shader: &WebGLShader<Self::Shader>) -> Result<(), GlError>;
+
+    /// delete a shader
+    fn delete_shader(&self, shader: &WebGLShader<Self::Shader>);
+
+    /// create a program
+    #[track_caller]
+    fn create_program(&self) -> Result<WebGLProgram<Self::Program>, GlError>;
+
+    /// link a program
+    ///
+    /// returns [`GlError::ProgramLink`] with the driver info log if linking failed
+    fn link_program(&self, program: &WebGLProgram<Self::Program>) -> Result<(), GlError>;
+
+    /// delete a program
+    fn delete_program(&self, program: &WebGLProgram<Self::Program>);
+
+    /// bind a program to the current state.
+    fn use_program(&self, program: &WebGLProgram<Self::Program>);
+
+    /// attach a shader to a program. A program must have two shaders : vertex and fragment shader.
+    fn attach_shader(
+        &self,
+        program: &WebGLProgram<Self::Program>,
+        shader: &WebGLShader<Self::Shader>,
+    );
+
+    /// associate a generic vertex attribute index with a named attribute
+    fn bind_attrib_location(&self, program: &WebGLProgram<Self::Program>, name: &str, loc: u32);
+
+    /// return the location of an attribute variable
+    fn get_attrib_location(&self, program: &WebGLProgram<Self::Program>, name: &str)
+        -> Option<u32>;
+
+    /// return the location of a uniform variable.
+    ///
+    /// Native and web contexts cache locations per program, filled with every active uniform
+    /// when the program is linked, so this is cheap enough to call each frame.
+    fn get_uniform_location(
+        &self,
+        program: &WebGLProgram<Self::Program>,
+        name: &str,
+    ) -> Option<WebGLUniformLocation<Self::UniformLocation>>;
+
+    /// define an array of generic vertex attribute data
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        size: AttributeSize,
+        kind: DataType,
+        normalized: bool,
+        stride: u32,
+        offset: u32,
+    );
+
+    /// enable a generic vertex attribute array
+    fn enable_vertex_attrib_array(&self, location: u32);
+
+    /// advance a generic vertex attribute once every `divisor` instances instead of once per vertex
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32) -> Result<(), GlError>;
+
+    /// specify clear values for the color buffers
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+
+    /// enable GL capabilities.
+    ///
+    /// flag should be one of [`Flag`]
+    fn enable(&self, flag: i32);
+
+    /// disable GL capabilities.
+    ///
+    /// flag should be one of [`Flag`]
+    fn disable(&self, flag: i32);
+
+    /// specify whether front- or back-facing polygons can be culled
+    fn cull_face(&self, flag: Culling);
+
+    /// enable or disable writing into the depth buffer
+    fn depth_mask(&self, b: bool);
+
+    /// specify the value used for depth buffer comparisons
+    fn depth_func(&self, d: DepthTest);
+
+    /// specify the clear value for the depth buffer
+    fn clear_depth(&self, value: f32);
+
+    /// clear buffers to preset values
+    fn clear(&self, bit: BufferBit);
+
+    /// set the viewport
+    fn viewport(&self, x: i32, y: i32, width: u32, height: u32);
+
+    /// render primitives from indexed array data
+    fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32);
+
+    /// render primitives from array data
+    fn draw_arrays(&self, mode: Primitives, count: usize);
+
+    /// render `instances` instances of primitives from indexed array data.
+    ///
+    /// Fails when instancing is unsupported or GL raises an error. An incomplete framebuffer is reported
+    /// through the [`ErrorPolicy`](::ErrorPolicy) as for [`GlBackend::draw_elements`].
+    fn draw_elements_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+        instances: usize,
+    ) -> Result<(), GlError>;
+
+    /// render `instances` instances of primitives from array data
+    fn draw_arrays_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        instances: usize,
+    ) -> Result<(), GlError>;
+
+    /// read a block of pixels from the frame buffer
+    #[allow(clippy::too_many_arguments)]
+    fn read_pixels(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        kind: PixelType,
+        data: &mut [u8],
+    ) -> Result<(), GlError>;
+
+    /// set pixel storage modes
+    fn pixel_storei(&self, storage: PixelStorageMode, value: i32);
+
+    /// specify a two-dimensional texture image
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: &[u8],
+    ) -> Result<(), GlError>;
+
+    /// update a part of a two-dimensional texture subimage
+    #[allow(clippy::too_many_arguments)]
+    fn tex_sub_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        xoffset: u16,
+        yoffset: u16,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: &[u8],
+    );
+
+    /// specify a two-dimensional texture image in a compressed format
+    fn compressed_tex_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        compression: TextureCompression,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> Result<(), GlError>;
+
+    /// return informations about current program
+    fn get_program_parameter(
+        &self,
+        program: &WebGLProgram<Self::Program>,
+        pname: ShaderParameter,
+    ) -> i32;
+
+    /// return informations about an active uniform of a linked program.
+    ///
+    /// index : from 0 to the [`ShaderParameter::ActiveUniforms`] program parameter.
+    /// `None` if the index is out of range or the uniform type is not a known [`UniformType`].
+    fn get_active_uniform(
+        &self,
+        program: &WebGLProgram<Self::Program>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo>;
+
+    /// return informations about an active attribute of a linked program.
+    ///
+    /// index : from 0 to the [`ShaderParameter::ActiveAttributes`] program parameter.
+    /// `None` if the index is out of range or the attribute type is not a known [`UniformType`].
+    fn get_active_attrib(
+        &self,
+        program: &WebGLProgram<Self::Program>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo>;
+
+    /// return an integer state value or limit, like [`Parameter::MaxTextureSize`].
+    ///
+    /// Object bindings return the object name natively and its tracking id on web, 0 when nothing is bound.
+    /// The scalar getters report `InvalidEnum` and return 0 for states with several values.
+    fn get_parameter_i32(&self, pname: Parameter) -> i32;
+
+    /// return a floating point state value, like [`Parameter::LineWidth`]
+    fn get_parameter_f32(&self, pname: Parameter) -> f32;
+
+    /// return a boolean state value, like [`Parameter::DepthWritemask`]
+    fn get_parameter_bool(&self, pname: Parameter) -> bool;
+
+    /// return an integer state array, like [`Parameter::Viewport`] or [`Parameter::ScissorBox`].
+    ///
+    /// Arrays of 2 values, like [`Parameter::MaxViewportDims`], are padded with zeros.
+    /// [`Parameter::CompressedTextureFormats`] reports `InvalidEnum`, see [`GlBackend::get_compressed_texture_formats`].
+    fn get_parameter_i32x4(&self, pname: Parameter) -> [i32; 4];
+
+    /// return a floating point state array, like [`Parameter::ColorClearValue`] or [`Parameter::DepthRange`].
+    ///
+    /// Arrays of 2 values are padded with zeros.
+    fn get_parameter_f32x4(&self, pname: Parameter) -> [f32; 4];
+
+    /// return a string describing the context : [`Parameter::Vendor`], [`Parameter::Renderer`],
+    /// [`Parameter::Version`] or [`Parameter::ShadingLanguageVersion`]
+    fn get_parameter_string(&self, pname: Parameter) -> String;
+
+    /// return the compressed texture formats accepted by [`GlBackend::compressed_tex_image2d`]
+    fn get_compressed_texture_formats(&self) -> Vec<u32>;
+
+    /// create a new texture object
+    #[track_caller]
+    fn create_texture(&self) -> Result<WebGLTexture<Self::Texture>, GlError>;
+
+    /// destroy a texture object
+    fn delete_texture(&self, texture: &WebGLTexture<Self::Texture>);
+
+    /// generate mipmaps for current 2D texture
+    fn generate_mipmap(&self);
+
+    /// generate mipmaps for current cube map texture
+    fn generate_mipmap_cube(&self);
+
+    /// select active texture unit
+    fn active_texture(&self, active: u32);
+
+    /// bind a named 2D texture to a texturing target
+    fn bind_texture(&self, texture: &WebGLTexture<Self::Texture>);
+
+    /// current 2D texture is not bound to current state anymore
+    fn unbind_texture(&self);
+
+    /// bind a named cube map texture to a texturing target
+    fn bind_texture_cube(&self, texture: &WebGLTexture<Self::Texture>);
+
+    /// current cube map texture is not bound to current state anymore
+    fn unbind_texture_cube(&self);
+
+    /// set the RGB alpha blend equation
+    fn blend_equation(&self, eq: BlendEquation);
+
+    /// specify pixel arithmetic for RGB and alpha components separately
+    fn blend_func(&self, b1: BlendMode, b2: BlendMode);
+
+    /// set the blend color
+    fn blend_color(&self, r: f32, g: f32, b: f32, a: f32);
+
+    /// set the front and back function and reference value for stencil testing
+    fn stencil_func(&self, func: StencilTest, reference: i32, mask: u32);
+
+    /// set the front and back stencil test actions
+    ///
+    /// fail : action when the stencil test fails,
+    /// depth_fail : when the stencil test passes and the depth test fails,
+    /// pass : when both tests pass
+    fn stencil_op(&self, fail: StencilAction, depth_fail: StencilAction, pass: StencilAction);
+
+    /// control the front and back writing of individual bits in the stencil planes
+    fn stencil_mask(&self, mask: u32);
+
+    /// set the function and reference value for stencil testing of front and/or back faces
+    ///
+    /// face : [`Culling::Front`], [`Culling::Back`] or [`Culling::FrontAndBack`]
+    fn stencil_func_separate(&self, face: Culling, func: StencilTest, reference: i32, mask: u32);
+
+    /// set the stencil test actions of front and/or back faces, see [`GlBackend::stencil_op`]
+    ///
+    /// face : [`Culling::Front`], [`Culling::Back`] or [`Culling::FrontAndBack`]
+    fn stencil_op_separate(
+        &self,
+        face: Culling,
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    );
+
+    /// control the writing of individual bits in the stencil planes of front and/or back faces
+    ///
+    /// face : [`Culling::Front`], [`Culling::Back`] or [`Culling::FrontAndBack`]
+    fn stencil_mask_separate(&self, face: Culling, mask: u32);
+
+    /// specify the clear value for the stencil buffer
+    fn clear_stencil(&self, value: i32);
+
+    /// enable or disable writing of frame buffer color components
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool);
+
+    /// set the scale and units used to calculate depth values,
+    /// when [`Flag::PolygonOffsetFill`] is enabled
+    fn polygon_offset(&self, factor: f32, units: f32);
+
+    /// set the scissor box, limiting drawing when [`Flag::ScissorTest`] is enabled
+    fn scissor(&self, x: i32, y: i32, width: u32, height: u32);
+
+    /// specify the winding of front-facing polygons, counter clockwise by default
+    fn front_face(&self, mode: FrontFaceDirection);
+
+    /// set the width of rasterized lines, only 1.0 being guaranteed to be supported
+    fn line_width(&self, width: f32);
+
+    /// give a preference for an implementation-specific behavior
+    ///
+    /// target : [`Hint::GenerateMipmapHint`]
+    ///
+    /// mode : [`Hint::DontCare`], [`Hint::Fastest`] or [`Hint::Nicest`]
+    fn hint(&self, target: Hint, mode: Hint);
+
+    /// map normalized device depths to window depths, between 0.0 and 1.0
+    fn depth_range(&self, near: f32, far: f32);
+
+    /// set the coverage used when [`Flag::SampleCoverage`] is enabled
+    fn sample_coverage(&self, value: f32, invert: bool);
+
+    /// apply a whole fixed-function state, only issuing the calls changing the last applied one.
+    ///
+    /// State changed by other calls in between is not noticed, see [`PipelineState`].
+    fn apply_pipeline(&self, state: &PipelineState);
+
+    /// specify the value of a mat4 uniform variable for the current program object
+    fn uniform_matrix_4fv(
+        &self,
+        location: &WebGLUniformLocation<Self::UniformLocation>,
+        value: &[[f32; 4]; 4],
+    );
+
+    /// specify the value of a mat3 uniform variable for the current program object
+    fn uniform_matrix_3fv(
+        &self,
+        location: &WebGLUniformLocation<Self::UniformLocation>,
+        value: &[[f32; 3]; 3],
+    );
+
+    /// specify the value of a mat2 uniform variable for the current program object
+    fn uniform_matrix_2fv(
+        &self,
+        location: &WebGLUniformLocation<Self::UniformLocation>,
+        value: &[[f32; 2]; 2],
+    );
+
+    /// specify the value of an int uniform variable for the current program object
+    fn uniform_1i(&self, location: &WebGLUniformLocation<Self::UniformLocation>, value: i32);
+
+    /// specify the value of a float uniform variable for the current program object
+    fn uniform_1f(&self, location: &WebGLUniformLocation<Self::UniformLocation>, value: f32);
+
+    /// specify the value of a vec2 uniform variable for the current program object
+    fn uniform_2f(&self, location: &WebGLUniformLocation<Self::UniformLocation>, value: (f32, f32));
+
+    /// specify the value of a vec3 uniform variable for the current program object
+    fn uniform_3f(
+        &self,
+        location: &WebGLUniformLocation<Self::UniformLocation>,
+        value: (f32, f32, f32),
+    );
+
+    /// specify the value of a vec4 uniform variable for the current program object
+    fn uniform_4f(
+        &self,
+        location: &WebGLUniformLocation<Self::UniformLocation>,
+        value: (f32, f32, f32, f32),
+    );
+
+    /// set texture integer parameters
+    fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32);
+
+    /// set texture float parameters
+    fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32);
+
+    /// create a vertex array object
+    #[track_caller]
+    fn create_vertex_array(&self) -> Result<WebGLVertexArray<Self::VertexArray>, GlError>;
+
+    /// destroy a vertex array object
+    fn delete_vertex_array(&self, vao: &WebGLVertexArray<Self::VertexArray>);
+
+    /// bind a vertex array object to current state
+    fn bind_vertex_array(&self, vao: &WebGLVertexArray<Self::VertexArray>);
+
+    /// current vertex array object is not bound to the current state anymore
+    fn unbind_vertex_array(&self, vao: &WebGLVertexArray<Self::VertexArray>);
+
+    /// specify which color buffers are to be drawn into
+    fn draw_buffer(&self, buffers: &[ColorBuffer]) -> Result<(), GlError>;
+
+    /// create a new framebuffer
+    #[track_caller]
+    fn create_framebuffer(&self) -> Result<WebGLFrameBuffer<Self::FrameBuffer>, GlError>;
+
+    /// destroy a framebuffer
+    fn delete_framebuffer(&self, fb: &WebGLFrameBuffer<Self::FrameBuffer>);
+
+    /// bind a framebuffer to the current state
+    fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer<Self::FrameBuffer>);
+
+    /// attach a texture to a framebuffer
+    fn framebuffer_texture2d(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        textarget: TextureBindPoint,
+        texture: &WebGLTexture<Self::Texture>,
+        level: i32,
+    );
+
+    /// unbind a framebuffer
+    fn unbind_framebuffer(&self, buffer: Buffers);
+
+    /// create a new renderbuffer
+    #[track_caller]
+    fn create_renderbuffer(&self) -> Result<WebGLRenderbuffer<Self::Renderbuffer>, GlError>;
+
+    /// destroy a renderbuffer
+    fn delete_renderbuffer(&self, rb: &WebGLRenderbuffer<Self::Renderbuffer>);
+
+    /// bind a renderbuffer to the current state
+    ///
+    /// target : [`Buffers::Renderbuffer`]
+    fn bind_renderbuffer(&self, target: Buffers, rb: &WebGLRenderbuffer<Self::Renderbuffer>);
+
+    /// unbind a renderbuffer
+    fn unbind_renderbuffer(&self, target: Buffers);
+
+    /// allocate the image of the bound renderbuffer
+    ///
+    /// format : [`Buffers::DepthComponent16`], [`Buffers::DepthStencil`], [`Buffers::Rgba4`]...
+    fn renderbuffer_storage(
+        &self,
+        target: Buffers,
+        format: Buffers,
+        width: u16,
+        height: u16,
+    ) -> Result<(), GlError>;
+
+    /// attach a renderbuffer to a framebuffer
+    ///
+    /// attachment : [`Buffers::ColorAttachment0`], [`Buffers::DepthAttachment`], [`Buffers::DepthStencilAttachment`]...
+    fn framebuffer_renderbuffer(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        renderbuffertarget: Buffers,
+        rb: &WebGLRenderbuffer<Self::Renderbuffer>,
+    );
+
+    /// return a parameter of the bound renderbuffer
+    ///
+    /// pname : [`Buffers::RenderbufferWidth`], [`Buffers::RenderbufferInternalFormat`]...
+    fn get_renderbuffer_parameter(&self, target: Buffers, pname: Buffers) -> i32;
+
+    /// return whether the framebuffer bound to `target` can be rendered to
+    fn check_framebuffer_status(&self, target: Buffers) -> Result<FramebufferStatus, GlError>;
+
+    /// create a query measuring GPU time.
+    ///
+    /// Fails with [`GlError::Unsupported`] without [`GlExtensions::disjoint_timer_query`].
+    fn create_timer_query(&self) -> Result<WebGLQuery<Self::Query>, GlError>;
+
+    /// delete a timer query
+    fn delete_timer_query(&self, query: &WebGLQuery<Self::Query>);
+
+    /// start measuring the GPU time of the following commands, one query running at a time
+    fn begin_time_elapsed(&self, query: &WebGLQuery<Self::Query>) -> Result<(), GlError>;
+
+    /// stop measuring the GPU time
+    fn end_time_elapsed(&self) -> Result<(), GlError>;
+
+    /// GPU time measured by a query in nanoseconds, `None` until the GPU is done, typically a few frames later.
+    ///
+    /// Fails with [`GlError::TimerDisjoint`] when the results of the running queries are meaningless.
+    fn get_time_elapsed(&self, query: &WebGLQuery<Self::Query>) -> Result<Option<u64>, GlError>;
+}
@@ -0,0 +1,213 @@
+//! Version, vendor, extensions and limits of a context, gathered once at creation.
+use std::collections::HashSet;
+
+use backend::GlBackend;
+use glenum::Parameter;
+
+/// The flavour of OpenGL implemented by a context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Api {
+    /// desktop OpenGL
+    OpenGl,
+    /// OpenGL ES
+    OpenGlEs,
+    /// WebGL, whose versions follow OpenGL ES : WebGL 1.0 is based on ES 2.0, WebGL 2.0 on ES 3.0
+    WebGl,
+}
+
+/// What a context supports, see [`GlBackend::capabilities`](::GlBackend::capabilities).
+///
+/// ```ignore
+/// let caps = gl.capabilities();
+/// if caps.max_draw_buffers >= 4 {
+///     setup_deferred_shading(&gl);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub api: Api,
+    /// major and minor version of the API, e.g. `(2, 0)` for WebGL 2.0
+    pub version: (u32, u32),
+    /// shading language version as written in a `#version` directive, e.g. 330, or 300 for GLSL ES 3.00
+    pub glsl_version: u32,
+    pub vendor: String,
+    pub renderer: String,
+    /// names of the supported extensions
+    pub extensions: HashSet<String>,
+    pub max_texture_size: u32,
+    pub max_cube_map_texture_size: u32,
+    pub max_renderbuffer_size: u32,
+    /// texture units available to the fragment shader
+    pub max_texture_units: u32,
+    /// texture units available to the vertex shader, possibly 0
+    pub max_vertex_texture_units: u32,
+    /// texture units available to all shader stages together
+    pub max_combined_texture_units: u32,
+    pub max_vertex_attribs: u32,
+    /// 4-component varyings between the vertex and fragment shaders
+    pub max_varying_vectors: u32,
+    /// samples of a multisampled renderbuffer, 0 without multisampled renderbuffers
+    pub max_samples: u32,
+    /// color buffers drawn at once, 1 without multiple render targets
+    pub max_draw_buffers: u32,
+}
+
+impl Default for Capabilities {
+    /// nothing known : version 0.0 and zero limits
+    fn default() -> Capabilities {
+        Capabilities {
+            api: Api::OpenGl,
+            version: (0, 0),
+            glsl_version: 0,
+            vendor: String::new(),
+            renderer: String::new(),
+            extensions: HashSet::new(),
+            max_texture_size: 0,
+            max_cube_map_texture_size: 0,
+            max_renderbuffer_size: 0,
+            max_texture_units: 0,
+            max_vertex_texture_units: 0,
+            max_combined_texture_units: 0,
+            max_vertex_attribs: 0,
+            max_varying_vectors: 0,
+            max_samples: 0,
+            max_draw_buffers: 1,
+        }
+    }
+}
+
+impl Capabilities {
+    /// query the capabilities of a context.
+    ///
+    /// api : [`Api::WebGl`] on web, [`Api::OpenGlEs`] or [`Api::OpenGl`] natively depending on the version string
+    pub(crate) fn query<B: GlBackend>(
+        gl: &B,
+        api: Api,
+        extensions: HashSet<String>,
+    ) -> Capabilities {
+        let version = api_version(&gl.get_parameter_string(Parameter::Version));
+        let glsl_version =
+            glsl_version(&gl.get_parameter_string(Parameter::ShadingLanguageVersion));
+        let limit = |pname| gl.get_parameter_i32(pname).max(0) as u32;
+
+        // multisampled renderbuffers and multiple render targets are core from ES 3.0 / WebGL 2.0 / GL 3.0
+        let gles3 = match api {
+            Api::WebGl => version.0 >= 2,
+            Api::OpenGl | Api::OpenGlEs => version.0 >= 3,
+        };
+        let draw_buffers = gles3
+            || extensions.contains("WEBGL_draw_buffers")
+            || extensions.contains("GL_EXT_draw_buffers");
+
+        Capabilities {
+            api,
+            version,
+            glsl_version,
+            vendor: gl.get_parameter_string(Parameter::Vendor),
+            renderer: gl.get_parameter_string(Parameter::Renderer),
+            max_texture_size: limit(Parameter::MaxTextureSize),
+            max_cube_map_texture_size: limit(Parameter::MaxCubeMapTextureSize),
+            max_renderbuffer_size: limit(Parameter::MaxRenderbufferSize),
+            max_texture_units: limit(Parameter::MaxTextureImageUnits),
+            max_vertex_texture_units: limit(Parameter::MaxVertexTextureImageUnits),
+            max_combined_texture_units: limit(Parameter::MaxCombinedTextureImageUnits),
+            max_vertex_attribs: limit(Parameter::MaxVertexAttribs),
+            // GL_MAX_VARYING_VECTORS needs GL 4.1 on desktop
+            max_varying_vectors: match api {
+                Api::OpenGl => limit(Parameter::MaxVaryingComponents) / 4,
+                Api::OpenGlEs | Api::WebGl => limit(Parameter::MaxVaryingVectors),
+            },
+            max_samples: if gles3 {
+                limit(Parameter::MaxSamples)
+            } else {
+                0
+            },
+            max_draw_buffers: if draw_buffers {
+                limit(Parameter::MaxDrawBuffers)
+            } else {
+                1
+            },
+            extensions,
+        }
+    }
+
+    /// whether the extension `name` is supported
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+}
+
+/// major and minor version of a context version string, `(0, 0)` if unknown
+fn api_version(version: &str) -> (u32, u32) {
+    version_number(version)
+        .and_then(|(major, minor)| Some((major, minor.parse().ok()?)))
+        .unwrap_or((0, 0))
+}
+
+/// shading language version of a version string as written in a `#version` directive,
+/// "1.0" being 100 and "4.60" 460, 0 if unknown
+fn glsl_version(version: &str) -> u32 {
+    version_number(version)
+        .and_then(|(major, minor)| {
+            let value: u32 = minor.parse().ok()?;
+            Some(major * 100 + if minor.len() == 1 { value * 10 } else { value })
+        })
+        .unwrap_or(0)
+}
+
+/// major version and minor version digits of the first `major.minor` number of a version string,
+/// like "OpenGL ES 3.2 Mesa" or "4.60 NVIDIA"
+fn version_number(version: &str) -> Option<(u32, String)> {
+    version.split_whitespace().find_map(|word| {
+        let mut numbers = word.splitn(2, '.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor: String = numbers
+            .next()?
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if minor.is_empty() {
+            return None;
+        }
+        Some((major, minor))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_versions() {
+        assert_eq!(api_version("4.6.0 NVIDIA 535.113.01"), (4, 6));
+        assert_eq!(api_version("OpenGL ES 3.2 Mesa 23.2.1"), (3, 2));
+        assert_eq!(api_version("WebGL 2.0 (OpenGL ES 3.0 Chromium)"), (2, 0));
+        assert_eq!(api_version("3.3 (Core Profile) Mesa 23.2.1"), (3, 3));
+        assert_eq!(api_version(""), (0, 0));
+        assert_eq!(api_version("unknown"), (0, 0));
+    }
+
+    #[test]
+    fn glsl_versions() {
+        assert_eq!(glsl_version("4.60 NVIDIA"), 460);
+        assert_eq!(glsl_version("WebGL GLSL ES 3.00"), 300);
+        assert_eq!(
+            glsl_version("WebGL GLSL ES 1.0 (OpenGL ES GLSL ES 1.0 Chromium)"),
+            100
+        );
+        assert_eq!(glsl_version("OpenGL ES GLSL ES 3.20"), 320);
+        assert_eq!(glsl_version("1.10"), 110);
+        assert_eq!(glsl_version(""), 0);
+    }
+
+    #[test]
+    fn version_numbers() {
+        assert_eq!(version_number("4.6.0 NVIDIA"), Some((4, "6".into())));
+        assert_eq!(version_number("OpenGL ES 3.2 Mesa"), Some((3, "2".into())));
+        assert_eq!(version_number("WebGL GLSL ES 3.00"), Some((3, "00".into())));
+        assert_eq!(version_number("4.60"), Some((4, "60".into())));
+        // words without a number and a dot are skipped
+        assert_eq!(version_number("ES 3 3.x 2.1"), Some((2, "1".into())));
+        assert_eq!(version_number("no version"), None);
+    }
+}
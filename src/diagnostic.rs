@@ -0,0 +1,302 @@
+//! Parsing of shader compiler info logs into [`ShaderDiagnostic`]s.
+//!
+//! Recognized formats :
+//! * Mesa : `0:12(5): error: syntax error, unexpected '}'`
+//! * NVIDIA : `0(12) : error C0000: syntax error, unexpected '}'`
+//! * AMD, ANGLE (Chrome), Firefox, Apple : `ERROR: 0:12: 'foo' : undeclared identifier`
+use error::GlError;
+
+/// Severity of a [`ShaderDiagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A located message from a shader compiler info log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderDiagnostic {
+    /// 1-based line in the source without its header, `None` if unknown or inside the header
+    pub line: Option<u32>,
+    /// 1-based column, only reported by some drivers
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ShaderDiagnostic {
+    /// parse a compiler info log.
+    ///
+    /// header_lines : number of lines prepended to the source, subtracted from reported lines.
+    ///
+    /// A log in no known format gives a single unlocated error holding the whole log.
+    pub fn parse(log: &str, header_lines: u32) -> Vec<ShaderDiagnostic> {
+        let mut diagnostics: Vec<ShaderDiagnostic> = log
+            .lines()
+            .filter_map(|l| {
+                let l = l.trim();
+                parse_mesa(l)
+                    .or_else(|| parse_nvidia(l))
+                    .or_else(|| parse_prefixed(l))
+            })
+            .collect();
+
+        for d in diagnostics.iter_mut() {
+            d.line = d
+                .line
+                .and_then(|l| l.checked_sub(header_lines))
+                .filter(|&l| l > 0);
+        }
+
+        let log = log.trim();
+        if diagnostics.is_empty() && !log.is_empty() {
+            diagnostics.push(ShaderDiagnostic {
+                line: None,
+                column: None,
+                severity: Severity::Error,
+                message: log.into(),
+            });
+        }
+        diagnostics
+    }
+}
+
+impl GlError {
+    /// diagnostics of a [`GlError::ShaderCompile`], empty for any other error.
+    ///
+    /// Lines are relative to the source following the header given to
+    /// [`GlBackend::shader_source_with_header`](::GlBackend::shader_source_with_header).
+    pub fn shader_diagnostics(&self) -> Vec<ShaderDiagnostic> {
+        match *self {
+            GlError::ShaderCompile {
+                ref log,
+                header_lines,
+            } => ShaderDiagnostic::parse(log, header_lines),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `header` followed by `source` on a new line, and the number of header lines
+pub(crate) fn with_header(header: &str, source: &str) -> (String, u32) {
+    let mut full = String::with_capacity(header.len() + source.len() + 1);
+    full.push_str(header);
+    if !header.is_empty() && !header.ends_with('\n') {
+        full.push('\n');
+    }
+    full.push_str(source);
+    (full, header.lines().count() as u32)
+}
+
+fn severity(s: &str) -> Option<Severity> {
+    match s.to_ascii_lowercase().as_str() {
+        "error" | "fatal error" | "preprocessor error" => Some(Severity::Error),
+        "warning" => Some(Severity::Warning),
+        "info" | "note" => Some(Severity::Info),
+        _ => None,
+    }
+}
+
+/// split a leading decimal number
+fn number(s: &str) -> Option<(u32, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse().ok().map(|n| (n, &s[end..]))
+}
+
+/// `0:12(5): error: message`
+fn parse_mesa(l: &str) -> Option<ShaderDiagnostic> {
+    let (_, rest) = number(l)?;
+    let (line, rest) = number(rest.strip_prefix(':')?)?;
+    let (column, rest) = number(rest.strip_prefix('(')?)?;
+    let rest = rest.strip_prefix("): ")?;
+    let colon = rest.find(": ")?;
+    Some(ShaderDiagnostic {
+        line: Some(line),
+        column: Some(column),
+        severity: severity(&rest[..colon])?,
+        message: rest[colon + 2..].into(),
+    })
+}
+
+/// `0(12) : error C0000: message`
+fn parse_nvidia(l: &str) -> Option<ShaderDiagnostic> {
+    let (_, rest) = number(l)?;
+    let (line, rest) = number(rest.strip_prefix('(')?)?;
+    let rest = rest.strip_prefix(") : ")?;
+    let colon = rest.find(": ")?;
+    let kind = rest[..colon].split_whitespace().next()?;
+    Some(ShaderDiagnostic {
+        line: Some(line),
+        column: None,
+        severity: severity(kind)?,
+        message: rest[colon + 2..].into(),
+    })
+}
+
+/// `ERROR: 0:12: message`, the location being optional
+fn parse_prefixed(l: &str) -> Option<ShaderDiagnostic> {
+    let colon = l.find(": ")?;
+    let severity = severity(&l[..colon])?;
+    let rest = &l[colon + 2..];
+
+    let located = number(rest)
+        .and_then(|(_, r)| r.strip_prefix(':'))
+        .and_then(number)
+        .and_then(|(line, r)| r.strip_prefix(':').map(|r| (line, r.trim_start())));
+    let (line, message) = match located {
+        Some((line, message)) => (Some(line), message),
+        // summary line, e.g. `ERROR: 2 compilation errors.  No code generated.`
+        None if rest.contains("No code generated") => return None,
+        None => (None, strip_unknown_location(rest)),
+    };
+
+    Some(ShaderDiagnostic {
+        line,
+        column: None,
+        severity,
+        message: strip_amd_code(message).into(),
+    })
+}
+
+/// `0:? : message`, from a line the driver could not tell
+fn strip_unknown_location(rest: &str) -> &str {
+    number(rest)
+        .and_then(|(_, r)| r.strip_prefix(":?"))
+        .and_then(|r| r.trim_start().strip_prefix(':'))
+        .map(str::trim_start)
+        .unwrap_or(rest)
+}
+
+/// AMD prefixes messages with `error(#143) `
+fn strip_amd_code(message: &str) -> &str {
+    for kind in &["error(#", "warning(#"] {
+        if message.starts_with(kind) {
+            if let Some(end) = message.find(") ") {
+                return &message[end + 2..];
+            }
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: Option<u32>, column: Option<u32>, message: &str) -> ShaderDiagnostic {
+        ShaderDiagnostic {
+            line,
+            column,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn mesa() {
+        let log = "0:5(10): error: `color' undeclared\n\
+                   0:3(1): warning: extension `GL_OES_standard_derivatives' unsupported in fragment shader\n";
+        let diagnostics = ShaderDiagnostic::parse(log, 0);
+        assert_eq!(
+            diagnostics[0],
+            diagnostic(Some(5), Some(10), "`color' undeclared")
+        );
+        assert_eq!(diagnostics[1].line, Some(3));
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn nvidia() {
+        let log = "0(7) : error C1008: undefined variable \"color\"\n";
+        assert_eq!(
+            ShaderDiagnostic::parse(log, 0),
+            vec![diagnostic(Some(7), None, "undefined variable \"color\"")]
+        );
+    }
+
+    #[test]
+    fn prefixed() {
+        // ANGLE, with its summary line
+        let log = "ERROR: 0:3: 'color' : undeclared identifier\n\
+                   ERROR: 1 compilation errors.  No code generated.\n";
+        assert_eq!(
+            ShaderDiagnostic::parse(log, 0),
+            vec![diagnostic(Some(3), None, "'color' : undeclared identifier")]
+        );
+
+        // AMD, with its error code
+        let log = "ERROR: 0:12: error(#143) Undeclared identifier: color\n";
+        assert_eq!(
+            ShaderDiagnostic::parse(log, 0),
+            vec![diagnostic(Some(12), None, "Undeclared identifier: color")]
+        );
+
+        // without location
+        let log = "WARNING: 0:? : extension 'GL_EXT_shader_texture_lod' is not supported\n";
+        let diagnostics = ShaderDiagnostic::parse(log, 0);
+        assert_eq!(diagnostics[0].line, None);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(
+            diagnostics[0].message,
+            "extension 'GL_EXT_shader_texture_lod' is not supported"
+        );
+    }
+
+    #[test]
+    fn header_lines() {
+        let log = "0:5(10): error: `color' undeclared\n0:2(1): error: in the header\n";
+        let diagnostics = ShaderDiagnostic::parse(log, 2);
+        assert_eq!(diagnostics[0].line, Some(3));
+        // reported inside the header
+        assert_eq!(diagnostics[1].line, None);
+
+        let error = GlError::ShaderCompile {
+            log: log.into(),
+            header_lines: 2,
+        };
+        assert_eq!(error.shader_diagnostics(), diagnostics);
+    }
+
+    #[test]
+    fn header() {
+        assert_eq!(
+            with_header("", "void main() {}"),
+            ("void main() {}".into(), 0)
+        );
+        assert_eq!(
+            with_header("#version 300 es\n#define N 4", "void main() {}"),
+            ("#version 300 es\n#define N 4\nvoid main() {}".into(), 2)
+        );
+        assert_eq!(
+            with_header("#version 100\n", "void main() {}"),
+            ("#version 100\nvoid main() {}".into(), 1)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(ShaderDiagnostic::parse("", 0).is_empty());
+        assert!(ShaderDiagnostic::parse("  \n", 0).is_empty());
+
+        // unknown format : the whole log as a single unlocated error
+        let log = "Compile failed.\n(12 : bad";
+        assert_eq!(
+            ShaderDiagnostic::parse(log, 0),
+            vec![diagnostic(None, None, log)]
+        );
+
+        // truncated lines are skipped when others parse
+        let log = "0:12(: error: truncated\n0(: error\n0:4(2): error: kept\n";
+        assert_eq!(
+            ShaderDiagnostic::parse(log, 0),
+            vec![diagnostic(Some(4), Some(2), "kept")]
+        );
+
+        assert_eq!(
+            GlError::ContextLost.shader_diagnostics(),
+            Vec::<ShaderDiagnostic>::new()
+        );
+    }
+}
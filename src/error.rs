@@ -0,0 +1,254 @@
+use std::cell::{Cell, RefCell};
+use std::error;
+use std::fmt;
+
+use glenum::{Buffers, Error, FramebufferStatus};
+
+/// An error reported by a fallible [`GlBackend`](::GlBackend) call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlError {
+    /// an error flag raised by the driver, as returned by `glGetError` / `getError`
+    Gl(Error),
+    /// an error code not listed in [`Error`]
+    Unknown(u32),
+    /// the context was lost, every object created with it is invalid
+    ContextLost,
+    /// a shader failed to compile
+    ShaderCompile {
+        log: String,
+        /// lines prepended by [`GlBackend::shader_source_with_header`](::GlBackend::shader_source_with_header)
+        header_lines: u32,
+    },
+    /// a program failed to link
+    ProgramLink { log: String },
+    /// the feature is not available in the current context
+    Unsupported(&'static str),
+    /// a disjoint operation, e.g. a GPU power state change, made the timer query results meaningless
+    TimerDisjoint,
+    /// a draw targeted an incomplete framebuffer, found by framebuffer validation
+    IncompleteFramebuffer {
+        status: FramebufferStatus,
+        /// the attachment at fault, when it could be told
+        attachment: Option<Buffers>,
+        /// what is wrong with it
+        reason: String,
+    },
+}
+
+impl GlError {
+    /// convert a `glGetError` / `getError` code, `None` meaning no error
+    pub fn from_code(code: u32) -> Option<GlError> {
+        Some(match code {
+            0 => return None,
+            0x0500 => GlError::Gl(Error::InvalidEnum),
+            0x0501 => GlError::Gl(Error::InvalidValue),
+            0x0502 => GlError::Gl(Error::InvalidOperation),
+            0x0503 => GlError::Gl(Error::StackOverflow),
+            0x0504 => GlError::Gl(Error::StackUnderflow),
+            0x0505 => GlError::Gl(Error::OutOfMemory),
+            0x0506 => GlError::Gl(Error::InvalidFramebufferOperation),
+            0x9242 => GlError::ContextLost,
+            code => GlError::Unknown(code),
+        })
+    }
+}
+
+impl From<Error> for GlError {
+    fn from(e: Error) -> GlError {
+        match e {
+            Error::ContextLostWebgl => GlError::ContextLost,
+            e => GlError::Gl(e),
+        }
+    }
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GlError::Gl(e) => write!(
+                f,
+                "{} ({})",
+                match e {
+                    Error::NoError => "no error",
+                    Error::InvalidEnum => "invalid enum",
+                    Error::InvalidValue => "invalid value",
+                    Error::InvalidOperation => "invalid operation",
+                    Error::StackOverflow => "stack overflow",
+                    Error::StackUnderflow => "stack underflow",
+                    Error::OutOfMemory => "out of memory",
+                    Error::InvalidFramebufferOperation => "invalid framebuffer operation",
+                    Error::ContextLostWebgl => "context lost",
+                },
+                e as u32
+            ),
+            GlError::Unknown(code) => write!(f, "unknown error ({})", code),
+            GlError::ContextLost => write!(f, "context lost"),
+            GlError::ShaderCompile { ref log, .. } => {
+                write!(f, "shader compilation failed: {}", log)
+            }
+            GlError::ProgramLink { ref log } => write!(f, "program link failed: {}", log),
+            GlError::Unsupported(what) => write!(f, "unsupported: {}", what),
+            GlError::TimerDisjoint => write!(f, "timer query disjoint"),
+            GlError::IncompleteFramebuffer {
+                status,
+                attachment,
+                ref reason,
+            } => match attachment {
+                Some(attachment) => write!(
+                    f,
+                    "incomplete framebuffer ({:?}): {:?} {}",
+                    status, attachment, reason
+                ),
+                None => write!(f, "incomplete framebuffer ({:?}): {}", status, reason),
+            },
+        }
+    }
+}
+
+impl error::Error for GlError {}
+
+/// How calls not returning a [`Result`] deal with the error flag.
+///
+/// Fallible calls always check it and return the error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    /// never query the error flag, avoiding a pipeline stall after each call
+    Off,
+    /// print each error with the call name
+    Log,
+    /// panic on the first error
+    Panic,
+    /// keep errors with the call name until drained with `take_errors`
+    Collect,
+}
+
+impl Default for ErrorPolicy {
+    /// `Panic` in debug builds, `Off` in release builds
+    fn default() -> ErrorPolicy {
+        if cfg!(debug_assertions) {
+            ErrorPolicy::Panic
+        } else {
+            ErrorPolicy::Off
+        }
+    }
+}
+
+/// An error kept under [`ErrorPolicy::Collect`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallError {
+    /// name of the call after which the error flag was raised
+    pub call: String,
+    pub error: GlError,
+}
+
+/// error policy and collected errors of a context, shared by its clones
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ErrorState {
+    policy: Cell<ErrorPolicy>,
+    errors: RefCell<Vec<CallError>>,
+}
+
+impl ErrorState {
+    pub fn policy(&self) -> ErrorPolicy {
+        self.policy.get()
+    }
+
+    pub fn set_policy(&self, policy: ErrorPolicy) {
+        self.policy.set(policy);
+    }
+
+    pub fn take_errors(&self) -> Vec<CallError> {
+        self.errors.replace(Vec::new())
+    }
+
+    /// deal with the error flag raised after `call`, queried with `get_error` unless the policy is `Off`
+    pub fn check<F: FnOnce() -> Result<(), GlError>>(&self, call: &str, get_error: F) {
+        let policy = self.policy.get();
+        if policy == ErrorPolicy::Off {
+            return;
+        }
+        if let Err(error) = get_error() {
+            match policy {
+                ErrorPolicy::Off => (),
+                ErrorPolicy::Log => log_error(call, &error),
+                ErrorPolicy::Panic => panic!("GLError: {} {}", call, error),
+                ErrorPolicy::Collect => self.errors.borrow_mut().push(CallError {
+                    call: call.into(),
+                    error,
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn log_error(call: &str, error: &GlError) {
+    eprintln!("GLError: {} {}", call, error);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn log_error(call: &str, error: &GlError) {
+    ::web_sys::console::error_1(&format!("GLError: {} {}", call, error).into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(policy: ErrorPolicy) -> ErrorState {
+        let state = ErrorState::default();
+        state.set_policy(policy);
+        state
+    }
+
+    fn invalid_value() -> Result<(), GlError> {
+        Err(GlError::Gl(Error::InvalidValue))
+    }
+
+    #[test]
+    fn off_never_queries_the_error_flag() {
+        let state = state(ErrorPolicy::Off);
+        state.check("clear", || panic!("error flag queried"));
+        assert!(state.take_errors().is_empty());
+    }
+
+    #[test]
+    fn log_keeps_nothing() {
+        let state = state(ErrorPolicy::Log);
+        state.check("clear", invalid_value);
+        assert!(state.take_errors().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "GLError: clear invalid value")]
+    fn panic_on_error() {
+        state(ErrorPolicy::Panic).check("clear", invalid_value);
+    }
+
+    #[test]
+    fn panic_ignores_success() {
+        state(ErrorPolicy::Panic).check("clear", || Ok(()));
+    }
+
+    #[test]
+    fn collect_until_taken() {
+        let state = state(ErrorPolicy::Collect);
+        state.check("clear", invalid_value);
+        state.check("viewport", || Ok(()));
+        state.check("draw_arrays", || Err(GlError::ContextLost));
+        assert_eq!(
+            state.take_errors(),
+            vec![
+                CallError {
+                    call: "clear".into(),
+                    error: GlError::Gl(Error::InvalidValue),
+                },
+                CallError {
+                    call: "draw_arrays".into(),
+                    error: GlError::ContextLost,
+                },
+            ]
+        );
+        assert!(state.take_errors().is_empty());
+    }
+}
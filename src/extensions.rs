@@ -0,0 +1,353 @@
+//! Optional features of a context, detected and enabled once at creation.
+use backend::GlBackend;
+use capabilities::{Api, Capabilities};
+use glenum::Parameter;
+
+/// How a context provides an optional feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// part of the context version
+    Core,
+    /// through the named extension, already enabled on WebGL
+    Extension(&'static str),
+}
+
+/// Anisotropic texture filtering, set with [`TextureParameter::MaxAnisotropy`](::TextureParameter::MaxAnisotropy).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnisotropicFiltering {
+    pub support: Support,
+    /// highest anisotropy a texture accepts
+    pub max_anisotropy: f32,
+}
+
+/// Optional features of a context, `None` when unsupported.
+/// See [`GlBackend::extensions`](::GlBackend::extensions).
+///
+/// ```ignore
+/// if let Some(anisotropic) = gl.extensions().anisotropic_filtering {
+///     let anisotropy = anisotropic.max_anisotropy.min(8.0);
+///     gl.tex_parameterfv(TextureKind::Texture2d, TextureParameter::MaxAnisotropy, anisotropy);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlExtensions {
+    pub anisotropic_filtering: Option<AnisotropicFiltering>,
+    /// vertex attribute divisors and instanced draws
+    pub instanced_arrays: Option<Support>,
+    pub vertex_array_object: Option<Support>,
+    /// depth textures, usable as framebuffer attachments
+    pub depth_texture: Option<Support>,
+    /// float textures, with nearest filtering at least
+    pub texture_float: Option<Support>,
+    pub texture_half_float: Option<Support>,
+    /// float color attachments
+    pub color_buffer_float: Option<Support>,
+    /// DXT1, DXT3 and DXT5 compressed textures
+    pub compressed_texture_s3tc: Option<Support>,
+    /// ETC2 and EAC compressed textures
+    pub compressed_texture_etc: Option<Support>,
+    pub compressed_texture_astc: Option<Support>,
+    /// GPU timer queries, see [`GlBackend::create_timer_query`](::GlBackend::create_timer_query)
+    pub disjoint_timer_query: Option<Support>,
+    /// object labels set with [`GlBackend::set_object_name`](::GlBackend::set_object_name),
+    /// never available on WebGL
+    pub debug_output: Option<Support>,
+}
+
+/// version from which a feature is core, for desktop OpenGL, OpenGL ES and WebGL
+type CoreSince = [Option<(u32, u32)>; 3];
+
+impl GlExtensions {
+    /// detect the optional features of a context from its capabilities.
+    ///
+    /// enable : called with the name of each supported extension providing a feature,
+    /// false if it could not be enabled
+    pub(crate) fn detect<B: GlBackend, F: FnMut(&'static str) -> bool>(
+        gl: &B,
+        mut enable: F,
+    ) -> GlExtensions {
+        let caps = gl.capabilities();
+        let mut support =
+            |core: CoreSince, names: &[&'static str]| find(caps, &mut enable, core, names);
+
+        let anisotropic = support(
+            [Some((4, 6)), None, None],
+            &[
+                "EXT_texture_filter_anisotropic",
+                "MOZ_EXT_texture_filter_anisotropic",
+                "WEBKIT_EXT_texture_filter_anisotropic",
+                "GL_EXT_texture_filter_anisotropic",
+                "GL_ARB_texture_filter_anisotropic",
+            ],
+        );
+        GlExtensions {
+            instanced_arrays: support(
+                [Some((3, 3)), Some((3, 0)), Some((2, 0))],
+                &[
+                    "ANGLE_instanced_arrays",
+                    "GL_ARB_instanced_arrays",
+                    "GL_EXT_instanced_arrays",
+                    "GL_ANGLE_instanced_arrays",
+                ],
+            ),
+            vertex_array_object: support(
+                [Some((3, 0)), Some((3, 0)), Some((2, 0))],
+                &[
+                    "OES_vertex_array_object",
+                    "GL_ARB_vertex_array_object",
+                    "GL_OES_vertex_array_object",
+                ],
+            ),
+            depth_texture: support(
+                [Some((1, 4)), Some((3, 0)), Some((2, 0))],
+                &[
+                    "WEBGL_depth_texture",
+                    "WEBKIT_WEBGL_depth_texture",
+                    "GL_OES_depth_texture",
+                    "GL_ANGLE_depth_texture",
+                ],
+            ),
+            texture_float: support(
+                [Some((3, 0)), Some((3, 0)), Some((2, 0))],
+                &[
+                    "OES_texture_float",
+                    "GL_ARB_texture_float",
+                    "GL_OES_texture_float",
+                ],
+            ),
+            texture_half_float: support(
+                [Some((3, 0)), Some((3, 0)), Some((2, 0))],
+                &[
+                    "OES_texture_half_float",
+                    "GL_ARB_half_float_pixel",
+                    "GL_OES_texture_half_float",
+                ],
+            ),
+            // float textures can be sampled but not rendered to on ES 3.0 / WebGL 2.0
+            color_buffer_float: support(
+                [Some((3, 0)), None, None],
+                &[
+                    "EXT_color_buffer_float",
+                    "WEBGL_color_buffer_float",
+                    "GL_EXT_color_buffer_float",
+                ],
+            ),
+            compressed_texture_s3tc: support(
+                [None, None, None],
+                &[
+                    "WEBGL_compressed_texture_s3tc",
+                    "MOZ_WEBGL_compressed_texture_s3tc",
+                    "WEBKIT_WEBGL_compressed_texture_s3tc",
+                    "GL_EXT_texture_compression_s3tc",
+                ],
+            ),
+            compressed_texture_etc: support(
+                [Some((4, 3)), Some((3, 0)), None],
+                &["WEBGL_compressed_texture_etc", "GL_ARB_ES3_compatibility"],
+            ),
+            compressed_texture_astc: support(
+                [None, Some((3, 2)), None],
+                &[
+                    "WEBGL_compressed_texture_astc",
+                    "GL_KHR_texture_compression_astc_ldr",
+                ],
+            ),
+            disjoint_timer_query: support(
+                [Some((3, 3)), None, None],
+                &[
+                    "EXT_disjoint_timer_query_webgl2",
+                    "EXT_disjoint_timer_query",
+                    "GL_EXT_disjoint_timer_query",
+                    "GL_ARB_timer_query",
+                ],
+            ),
+            debug_output: support(
+                [Some((4, 3)), Some((3, 2)), None],
+                &["GL_KHR_debug", "GL_ARB_debug_output"],
+            ),
+            // queried once the extension is enabled
+            anisotropic_filtering: anisotropic.map(|support| AnisotropicFiltering {
+                support,
+                max_anisotropy: gl.get_parameter_f32(Parameter::MaxTextureMaxAnisotropy),
+            }),
+        }
+    }
+
+    /// names of the extensions providing a feature
+    pub fn names(&self) -> Vec<&'static str> {
+        let features = [
+            self.anisotropic_filtering.map(|a| a.support),
+            self.instanced_arrays,
+            self.vertex_array_object,
+            self.depth_texture,
+            self.texture_float,
+            self.texture_half_float,
+            self.color_buffer_float,
+            self.compressed_texture_s3tc,
+            self.compressed_texture_etc,
+            self.compressed_texture_astc,
+            self.disjoint_timer_query,
+            self.debug_output,
+        ];
+        features
+            .iter()
+            .filter_map(|support| match *support {
+                Some(Support::Extension(name)) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// how a context provides a feature, preferring the core version over the first supported extension
+fn find<F: FnMut(&'static str) -> bool>(
+    caps: &Capabilities,
+    enable: &mut F,
+    core: CoreSince,
+    names: &[&'static str],
+) -> Option<Support> {
+    let since = match caps.api {
+        Api::OpenGl => core[0],
+        Api::OpenGlEs => core[1],
+        Api::WebGl => core[2],
+    };
+    match since {
+        Some(version) if caps.version >= version => Some(Support::Core),
+        _ => names
+            .iter()
+            .find(|&&name| caps.has_extension(name) && enable(name))
+            .map(|&name| Support::Extension(name)),
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use mock::MockContext;
+
+    fn mock(api: Api, version: (u32, u32), extensions: &[&str]) -> MockContext {
+        let mut gl = MockContext::new();
+        gl.set_capabilities(Capabilities {
+            api,
+            version,
+            extensions: extensions.iter().map(|&name| name.into()).collect(),
+            ..Capabilities::default()
+        });
+        gl
+    }
+
+    #[test]
+    fn webgl1_extensions() {
+        let gl = mock(
+            Api::WebGl,
+            (1, 0),
+            &[
+                "MOZ_EXT_texture_filter_anisotropic",
+                "OES_vertex_array_object",
+                "WEBGL_compressed_texture_s3tc",
+            ],
+        );
+        gl.set_parameter(Parameter::MaxTextureMaxAnisotropy, &[16.0]);
+        let extensions = GlExtensions::detect(&gl, |_| true);
+
+        assert_eq!(
+            extensions.anisotropic_filtering,
+            Some(AnisotropicFiltering {
+                support: Support::Extension("MOZ_EXT_texture_filter_anisotropic"),
+                max_anisotropy: 16.0,
+            })
+        );
+        assert_eq!(
+            extensions.vertex_array_object,
+            Some(Support::Extension("OES_vertex_array_object"))
+        );
+        assert_eq!(extensions.instanced_arrays, None);
+        assert_eq!(extensions.debug_output, None);
+        assert_eq!(
+            extensions.names(),
+            vec![
+                "MOZ_EXT_texture_filter_anisotropic",
+                "OES_vertex_array_object",
+                "WEBGL_compressed_texture_s3tc",
+            ]
+        );
+    }
+
+    #[test]
+    fn core_versions() {
+        let gl = mock(Api::WebGl, (2, 0), &[]);
+        let extensions = GlExtensions::detect(&gl, |_| true);
+        assert_eq!(extensions.instanced_arrays, Some(Support::Core));
+        assert_eq!(extensions.depth_texture, Some(Support::Core));
+        // renderable float textures are never core on WebGL
+        assert_eq!(extensions.color_buffer_float, None);
+        assert!(extensions.names().is_empty());
+
+        let gl = mock(Api::OpenGl, (3, 3), &[]);
+        let extensions = GlExtensions::detect(&gl, |_| true);
+        assert_eq!(extensions.instanced_arrays, Some(Support::Core));
+        assert_eq!(extensions.disjoint_timer_query, Some(Support::Core));
+        assert_eq!(extensions.debug_output, None);
+    }
+
+    #[test]
+    fn gles_extension_names() {
+        let names = [
+            "GL_OES_vertex_array_object",
+            "GL_EXT_disjoint_timer_query",
+            "GL_KHR_debug",
+        ];
+        let gl = mock(Api::OpenGlEs, (2, 0), &names);
+        let extensions = GlExtensions::detect(&gl, |_| true);
+        assert_eq!(
+            extensions.vertex_array_object,
+            Some(Support::Extension("GL_OES_vertex_array_object"))
+        );
+        assert_eq!(
+            extensions.disjoint_timer_query,
+            Some(Support::Extension("GL_EXT_disjoint_timer_query"))
+        );
+        assert_eq!(
+            extensions.debug_output,
+            Some(Support::Extension("GL_KHR_debug"))
+        );
+
+        // core from ES 3.0 and 3.2, whatever the extensions
+        let gl = mock(Api::OpenGlEs, (3, 2), &names);
+        let extensions = GlExtensions::detect(&gl, |_| true);
+        assert_eq!(extensions.vertex_array_object, Some(Support::Core));
+        assert_eq!(extensions.debug_output, Some(Support::Core));
+    }
+
+    #[test]
+    fn extensions_failing_to_enable_are_skipped() {
+        let gl = mock(
+            Api::WebGl,
+            (1, 0),
+            &[
+                "EXT_texture_filter_anisotropic",
+                "WEBKIT_EXT_texture_filter_anisotropic",
+                "ANGLE_instanced_arrays",
+            ],
+        );
+        let mut tried = Vec::new();
+        let extensions = GlExtensions::detect(&gl, |name| {
+            tried.push(name);
+            name != "EXT_texture_filter_anisotropic" && name != "ANGLE_instanced_arrays"
+        });
+        assert_eq!(
+            extensions.anisotropic_filtering.map(|a| a.support),
+            Some(Support::Extension("WEBKIT_EXT_texture_filter_anisotropic"))
+        );
+        assert_eq!(extensions.instanced_arrays, None);
+        // unsupported names are never enabled
+        assert_eq!(
+            tried,
+            vec![
+                "EXT_texture_filter_anisotropic",
+                "WEBKIT_EXT_texture_filter_anisotropic",
+                "ANGLE_instanced_arrays",
+            ]
+        );
+    }
+}
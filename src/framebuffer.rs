@@ -0,0 +1,386 @@
+//! Optional framebuffer validation, telling which attachment makes a framebuffer incomplete.
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use capabilities::{Api, Capabilities};
+use error::GlError;
+use glenum::*;
+use tracking::ObjectKind;
+
+/// an image that can be attached to a framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Image {
+    /// texture key, target and level
+    Texture(u64, u32, u8),
+    /// renderbuffer key
+    Renderbuffer(u64),
+}
+
+impl Image {
+    fn object(self) -> (ObjectKind, u64) {
+        match self {
+            Image::Texture(key, _, _) => (ObjectKind::Texture, key),
+            Image::Renderbuffer(key) => (ObjectKind::Renderbuffer, key),
+        }
+    }
+}
+
+/// what an image holds, deciding the attachments it can be bound to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ImageFormat {
+    Color,
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl ImageFormat {
+    pub fn of_pixels(format: PixelFormat) -> ImageFormat {
+        match format {
+            PixelFormat::DepthComponent => ImageFormat::Depth,
+            _ => ImageFormat::Color,
+        }
+    }
+
+    pub fn of_renderbuffer(format: Buffers) -> ImageFormat {
+        match format {
+            Buffers::DepthComponent16 | Buffers::DepthComponent24 => ImageFormat::Depth,
+            Buffers::StencilIndex | Buffers::StencilIndex8 => ImageFormat::Stencil,
+            Buffers::DepthStencil | Buffers::Depth24Stencil8 => ImageFormat::DepthStencil,
+            _ => ImageFormat::Color,
+        }
+    }
+
+    /// whether an image of this format can be attached to `attachment`
+    fn fits(self, attachment: Buffers) -> bool {
+        match attachment {
+            Buffers::DepthAttachment => {
+                self == ImageFormat::Depth || self == ImageFormat::DepthStencil
+            }
+            Buffers::StencilAttachment => {
+                self == ImageFormat::Stencil || self == ImageFormat::DepthStencil
+            }
+            Buffers::DepthStencilAttachment => self == ImageFormat::DepthStencil,
+            _ => self == ImageFormat::Color,
+        }
+    }
+}
+
+/// attachments and image formats seen so far
+#[derive(Debug, Default, PartialEq)]
+struct Framebuffers {
+    /// attached images by framebuffer key
+    attachments: HashMap<u64, Vec<(Buffers, Image)>>,
+    /// format, width and height of specified images
+    images: HashMap<Image, (ImageFormat, u16, u16)>,
+    /// framebuffers changed since they were last validated
+    dirty: HashSet<u64>,
+    /// key of the bound framebuffer, 0 for the default one
+    bound: u64,
+}
+
+impl Framebuffers {
+    /// flag every framebuffer using an image of `object`
+    fn touch(&mut self, object: (ObjectKind, u64)) {
+        for (fb, attachments) in &self.attachments {
+            if attachments
+                .iter()
+                .any(|&(_, image)| image.object() == object)
+            {
+                self.dirty.insert(*fb);
+            }
+        }
+    }
+
+    /// the attachment at fault in an incomplete framebuffer, and why.
+    ///
+    /// same_size : whether attachments must have the same size, as on OpenGL ES 2.0 / WebGL 1.0
+    fn diagnose(&self, fb: u64, same_size: bool) -> (Option<Buffers>, String) {
+        let attachments = match self.attachments.get(&fb) {
+            Some(attachments) if !attachments.is_empty() => attachments,
+            _ => return (None, "has no attachment".into()),
+        };
+        let mut first: Option<(Buffers, u16, u16)> = None;
+        for &(attachment, image) in attachments {
+            let (format, width, height) = match self.images.get(&image) {
+                Some(&(_, 0, _)) | Some(&(_, _, 0)) | None => {
+                    return (Some(attachment), "has an image without storage".into())
+                }
+                Some(&info) => info,
+            };
+            if !format.fits(attachment) {
+                return (
+                    Some(attachment),
+                    format!("cannot hold a {:?} image", format),
+                );
+            }
+            match first {
+                Some((other, w, h)) if same_size && (w, h) != (width, height) => {
+                    return (
+                        Some(attachment),
+                        format!("is {}x{} while {:?} is {}x{}", width, height, other, w, h),
+                    )
+                }
+                Some(_) => (),
+                None => first = Some((attachment, width, height)),
+            }
+        }
+        (
+            None,
+            "has attachments the implementation does not support together".into(),
+        )
+    }
+}
+
+/// framebuffer validation of a context, disabled by default
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FramebufferValidator {
+    enabled: Cell<bool>,
+    state: RefCell<Framebuffers>,
+}
+
+impl FramebufferValidator {
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// turning it on validates every known framebuffer on its next draw
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        if enabled {
+            let mut state = self.state.borrow_mut();
+            let framebuffers: Vec<u64> = state.attachments.keys().cloned().collect();
+            state.dirty.extend(framebuffers);
+        }
+    }
+
+    /// forget every framebuffer and image, after a context loss
+    #[cfg(target_arch = "wasm32")]
+    pub fn clear(&self) {
+        *self.state.borrow_mut() = Framebuffers::default();
+    }
+
+    /// a framebuffer was bound, 0 for the default one
+    pub fn bind(&self, fb: u64) {
+        let mut state = self.state.borrow_mut();
+        state.bound = fb;
+        // without attachment it is incomplete, and nothing would flag it
+        if fb != 0 && !state.attachments.contains_key(&fb) {
+            state.dirty.insert(fb);
+        }
+    }
+
+    /// an image was attached to the bound framebuffer
+    pub fn attach(&self, attachment: Buffers, image: Image) {
+        let mut state = self.state.borrow_mut();
+        let fb = state.bound;
+        if fb == 0 {
+            return;
+        }
+        let attachments = state.attachments.entry(fb).or_default();
+        attachments.retain(|&(a, _)| a != attachment);
+        attachments.push((attachment, image));
+        state.dirty.insert(fb);
+    }
+
+    /// an image was (re)specified
+    pub fn image(&self, image: Image, format: ImageFormat, width: u16, height: u16) {
+        let mut state = self.state.borrow_mut();
+        state.images.insert(image, (format, width, height));
+        state.touch(image.object());
+    }
+
+    /// an object was deleted, leaving the framebuffers using it without image
+    pub fn deleted(&self, kind: ObjectKind, key: u64) {
+        let mut state = self.state.borrow_mut();
+        match kind {
+            ObjectKind::FrameBuffer => {
+                state.attachments.remove(&key);
+                state.dirty.remove(&key);
+                if state.bound == key {
+                    state.bound = 0;
+                }
+            }
+            ObjectKind::Texture | ObjectKind::Renderbuffer => {
+                state
+                    .images
+                    .retain(|image, _| image.object() != (kind, key));
+                state.touch((kind, key));
+            }
+            _ => (),
+        }
+    }
+
+    /// on the first draw since the bound framebuffer changed, an error if it is incomplete.
+    ///
+    /// status : checks the bound framebuffer, see [`GlBackend::check_framebuffer_status`](::GlBackend::check_framebuffer_status)
+    pub fn validate<F: FnOnce() -> Result<FramebufferStatus, GlError>>(
+        &self,
+        caps: &Capabilities,
+        status: F,
+    ) -> Result<(), GlError> {
+        if !self.enabled.get() {
+            return Ok(());
+        }
+        let fb = {
+            let mut state = self.state.borrow_mut();
+            let fb = state.bound;
+            if !state.dirty.remove(&fb) {
+                return Ok(());
+            }
+            fb
+        };
+        let status = status()?;
+        if status == FramebufferStatus::Complete {
+            return Ok(());
+        }
+        // later versions use the smallest attachment size instead
+        let same_size = match caps.api {
+            Api::OpenGlEs => caps.version < (3, 0),
+            Api::WebGl => caps.version < (2, 0),
+            Api::OpenGl => false,
+        };
+        let (attachment, reason) = self.state.borrow().diagnose(fb, same_size);
+        Err(GlError::IncompleteFramebuffer {
+            status,
+            attachment,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(api: Api, version: (u32, u32)) -> Capabilities {
+        Capabilities {
+            api,
+            version,
+            ..Capabilities::default()
+        }
+    }
+
+    /// a validator with framebuffer 1 bound
+    fn validator() -> FramebufferValidator {
+        let validator = FramebufferValidator::default();
+        validator.set_enabled(true);
+        validator.bind(1);
+        validator
+    }
+
+    fn error(
+        validator: &FramebufferValidator,
+        caps: &Capabilities,
+        status: FramebufferStatus,
+    ) -> (Option<Buffers>, String) {
+        match validator.validate(caps, || Ok(status)) {
+            Err(GlError::IncompleteFramebuffer {
+                attachment, reason, ..
+            }) => (attachment, reason),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_attachment() {
+        let validator = validator();
+        assert_eq!(
+            error(
+                &validator,
+                &Capabilities::default(),
+                FramebufferStatus::IncompleteMissingAttachment
+            ),
+            (None, "has no attachment".into())
+        );
+    }
+
+    #[test]
+    fn missing_storage_and_wrong_format() {
+        let validator = validator();
+        let caps = Capabilities::default();
+        validator.attach(Buffers::DepthAttachment, Image::Renderbuffer(2));
+        assert_eq!(
+            error(&validator, &caps, FramebufferStatus::IncompleteAttachment),
+            (
+                Some(Buffers::DepthAttachment),
+                "has an image without storage".into()
+            )
+        );
+
+        validator.image(Image::Renderbuffer(2), ImageFormat::Color, 64, 64);
+        assert_eq!(
+            error(&validator, &caps, FramebufferStatus::IncompleteAttachment),
+            (
+                Some(Buffers::DepthAttachment),
+                "cannot hold a Color image".into()
+            )
+        );
+    }
+
+    #[test]
+    fn mismatched_sizes_only_on_es2() {
+        let validator = validator();
+        validator.image(Image::Texture(2, 0x0DE1, 0), ImageFormat::Color, 64, 64);
+        validator.image(Image::Renderbuffer(3), ImageFormat::Depth, 32, 32);
+        validator.attach(Buffers::ColorAttachment0, Image::Texture(2, 0x0DE1, 0));
+        validator.attach(Buffers::DepthAttachment, Image::Renderbuffer(3));
+
+        let sized = (
+            Some(Buffers::DepthAttachment),
+            "is 32x32 while ColorAttachment0 is 64x64".to_string(),
+        );
+        let webgl1 = caps(Api::WebGl, (1, 0));
+        assert_eq!(
+            error(&validator, &webgl1, FramebufferStatus::IncompleteDimensions),
+            sized
+        );
+        validator.set_enabled(true);
+        let es2 = caps(Api::OpenGlEs, (2, 0));
+        assert_eq!(
+            error(&validator, &es2, FramebufferStatus::IncompleteDimensions),
+            sized
+        );
+
+        // mixed sizes are fine there, something else is wrong
+        for caps in &[
+            caps(Api::WebGl, (2, 0)),
+            caps(Api::OpenGlEs, (3, 0)),
+            caps(Api::OpenGl, (3, 3)),
+        ] {
+            validator.set_enabled(true);
+            assert_eq!(
+                error(&validator, caps, FramebufferStatus::Unsupported),
+                (
+                    None,
+                    "has attachments the implementation does not support together".into()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn validated_once_until_changed() {
+        let validator = validator();
+        let caps = Capabilities::default();
+        validator.image(Image::Renderbuffer(2), ImageFormat::Color, 64, 64);
+        validator.attach(Buffers::ColorAttachment0, Image::Renderbuffer(2));
+        assert!(validator
+            .validate(&caps, || Ok(FramebufferStatus::Complete))
+            .is_ok());
+        // not dirty anymore : the status is not queried again
+        assert!(validator
+            .validate(&caps, || panic!("status queried"))
+            .is_ok());
+
+        // deleting the image makes the framebuffer dirty again
+        validator.deleted(ObjectKind::Renderbuffer, 2);
+        assert_eq!(
+            error(&validator, &caps, FramebufferStatus::IncompleteAttachment),
+            (
+                Some(Buffers::ColorAttachment0),
+                "has an image without storage".into()
+            )
+        );
+    }
+}
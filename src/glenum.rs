@@ -5,7 +5,7 @@
 */
 
 /// Constants passed to WebGLRenderingContext.vertexAttribPointer()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AttributeSize {
     One = 1,
     Two = 2,
@@ -14,7 +14,7 @@ pub enum AttributeSize {
 }
 
 /// Constants passed to WebGLRenderingContext.createShader()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ShaderKind {
     /// Passed to createShader to define a fragment shader.
     Fragment = 0x8B30,
@@ -23,7 +23,7 @@ pub enum ShaderKind {
 }
 
 /// Constants passed to WebGLRenderingContext.createShader()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ShaderParameter {
     /// Passed to getShaderParamter to get the status of the compilation. Returns false if the shader was not compiled. You can then query getShaderInfoLog to find the exact error
     CompileStatus = 0x8B81,
@@ -62,7 +62,7 @@ pub enum ShaderParameter {
 }
 
 /// Passed to bindBuffer or bufferData to specify the type of buffer being used.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BufferKind {
     /// to store vertex attributes
     Array = 0x8892,
@@ -70,7 +70,7 @@ pub enum BufferKind {
     ElementArray = 0x8893,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DrawMode {
     /// Passed to bufferData as a hint about whether the contents of the buffer are likely to be used often and not change often.
     Static = 0x88E4,
@@ -80,7 +80,7 @@ pub enum DrawMode {
     Stream = 0x88E0,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BufferParameter {
     /// Passed to getBufferParameter to get a buffer's size.
     Size = 0x8764,
@@ -88,7 +88,7 @@ pub enum BufferParameter {
     Usage = 0x8765,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DataType {
     I8 = 0x1400,
     U8 = 0x1401,
@@ -99,7 +99,7 @@ pub enum DataType {
     Float = 0x1406,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Flag {
     /// Passed to enable/disable to turn on/off blending. Can also be used with getParameter to find the current blending method.
     Blend = 0x0BE2,
@@ -119,7 +119,7 @@ pub enum Flag {
     StencilTest = 0x0B90,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BufferBit {
     /// Passed to clear to clear the current depth buffer.
     Depth = 0x00000100,
@@ -130,7 +130,7 @@ pub enum BufferBit {
 }
 
 /// Passed to drawElements or drawArrays to draw primitives.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Primitives {
     /// Passed to drawElements or drawArrays to draw single points.
     Points = 0x0000,
@@ -149,7 +149,7 @@ pub enum Primitives {
 }
 
 /// Constants passed to WebGLRenderingContext.blendFunc() or WebGLRenderingContext.blendFuncSeparate() to specify the blending mode (for both, RBG and alpha, or separately).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlendMode {
     /// Passed to blendFunc or blendFuncSeparate to turn off a component.
     Zero = 0,
@@ -186,7 +186,7 @@ pub enum BlendMode {
 /// Constants passed to WebGLRenderingContext.blendEquation()
 /// or WebGLRenderingContext.blendEquationSeparate() to control
 /// how the blending is calculated (for both, RBG and alpha, or separately).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlendEquation {
     /// Passed to blendEquation or blendEquationSeparate to set an addition blend function.
     FuncAdd = 0x8006,
@@ -197,7 +197,7 @@ pub enum BlendEquation {
 }
 
 /// Constants passed to WebGLRenderingContext.getParameter() to specify what information to return.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Parameter {
     /// Passed to getParameter to get the current RGB blend function. same as BlendEquationRgb
     BlendEquation = 0x8009,
@@ -309,8 +309,10 @@ pub enum Parameter {
     SampleCoverageValue = 0x80AA,
     ///
     SampleCoverageInvert = 0x80AB,
-    ///
+    /// supported compressed texture formats, see [`GlBackend::get_compressed_texture_formats`](::GlBackend::get_compressed_texture_formats)
     CompressedTextureFormats = 0x86A3,
+    /// number of values of [`Parameter::CompressedTextureFormats`]
+    NumCompressedTextureFormats = 0x86A2,
     ///
     Vendor = 0x1F00,
     ///
@@ -329,10 +331,70 @@ pub enum Parameter {
 
     ///
     MaxCubeMapTextureSize = 0x851C,
+    /// version string of the shading language, like "4.60 NVIDIA" or "WebGL GLSL ES 3.00"
+    ShadingLanguageVersion = 0x8B8C,
+    /// active texture unit, as `TEXTURE0` plus the unit index
+    ActiveTexture = 0x84E0,
+    /// program in use
+    CurrentProgram = 0x8B8D,
+    /// bound framebuffer, 0 for the default one
+    FramebufferBinding = 0x8CA6,
+    /// bound renderbuffer
+    RenderbufferBinding = 0x8CA7,
+    /// OpenGL ES 3.0 / WebGL 2.0, or OES_vertex_array_object
+    VertexArrayBinding = 0x85B5,
+    /// number of vertex attributes
+    MaxVertexAttribs = 0x8869,
+    /// 4-component uniforms available to the vertex shader
+    MaxVertexUniformVectors = 0x8DFB,
+    /// 4-component varyings between the vertex and fragment shaders, OpenGL 4.1 on desktop
+    MaxVaryingVectors = 0x8DFC,
+    /// desktop OpenGL equivalent of MaxVaryingVectors, in components
+    MaxVaryingComponents = 0x8B4B,
+    /// 4-component uniforms available to the fragment shader
+    MaxFragmentUniformVectors = 0x8DFD,
+    /// texture units available to the fragment shader
+    MaxTextureImageUnits = 0x8872,
+    /// texture units available to the vertex shader, possibly 0
+    MaxVertexTextureImageUnits = 0x8B4C,
+    /// texture units available to all shader stages together
+    MaxCombinedTextureImageUnits = 0x8B4D,
+    /// largest width or height of a renderbuffer
+    MaxRenderbufferSize = 0x84E8,
+    /// OpenGL ES 3.0 / WebGL 2.0
+    MaxSamples = 0x8D57,
+    /// OpenGL ES 3.0 / WebGL 2.0, or WEBGL_draw_buffers
+    MaxDrawBuffers = 0x8824,
+    /// OpenGL ES 3.0 / WebGL 2.0, or WEBGL_draw_buffers
+    MaxColorAttachments = 0x8CDF,
+    /// with anisotropic filtering, see [`GlExtensions`](::GlExtensions)
+    MaxTextureMaxAnisotropy = 0x84FF,
+    /// with disjoint timer queries, whether a disjoint operation invalidated the running timer queries.
+    /// Reading it resets it
+    GpuDisjoint = 0x8FBB,
+}
+
+impl Parameter {
+    /// number of values of the state, 0 for [`Parameter::CompressedTextureFormats`] whose count varies
+    pub(crate) fn value_count(self) -> usize {
+        match self {
+            Parameter::BlendColor
+            | Parameter::Viewport
+            | Parameter::ScissorBox
+            | Parameter::ColorClearValue
+            | Parameter::ColorWritemask => 4,
+            Parameter::AliasedPointSizeRange
+            | Parameter::AliasedLineWidthRange
+            | Parameter::DepthRange
+            | Parameter::MaxViewportDims => 2,
+            Parameter::CompressedTextureFormats => 0,
+            _ => 1,
+        }
+    }
 }
 
 /// Constants passed to WebGLRenderingContext.getVertexAttrib().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VertexAttrib {
     /// Passed to getVertexAttrib to read back the current vertex attribute.
     Current = 0x8626,
@@ -353,7 +415,7 @@ pub enum VertexAttrib {
 }
 
 /// Constants passed to WebGLRenderingContext.cullFace().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Culling {
     /// Passed to enable/disable to turn on/off culling. Can also be used with getParameter to find the current culling method.
     CullFace = 0x0B44,
@@ -366,7 +428,7 @@ pub enum Culling {
 }
 
 /// Constants returned from WebGLRenderingContext.getError().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Error {
     /// Returned from getError.
     NoError = 0,
@@ -376,14 +438,20 @@ pub enum Error {
     InvalidValue = 0x0501,
     /// Returned from getError.
     InvalidOperation = 0x0502,
+    /// Returned from glGetError on desktop OpenGL.
+    StackOverflow = 0x0503,
+    /// Returned from glGetError on desktop OpenGL.
+    StackUnderflow = 0x0504,
     /// Returned from getError.
     OutOfMemory = 0x0505,
     /// Returned from getError.
+    InvalidFramebufferOperation = 0x0506,
+    /// Returned from getError.
     ContextLostWebgl = 0x9242,
 }
 
 /// Constants passed to WebGLRenderingContext.frontFace().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FrontFaceDirection {
     /// Passed to frontFace to specify the front face of a polygon is drawn in the clockwise direction
     CW = 0x0900,
@@ -392,7 +460,7 @@ pub enum FrontFaceDirection {
 }
 
 /// Constants passed to WebGLRenderingContext.depthFunc().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DepthTest {
     /// Passed to depthFunction or stencilFunction to specify depth or stencil tests will never pass. i.e. Nothing will be drawn.
     Never = 0x0200,
@@ -413,7 +481,7 @@ pub enum DepthTest {
 }
 
 /// Constants passed to WebGLRenderingContext.stencilFunc().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StencilTest {
     /// Passed to depthFunction or stencilFunction to specify depth or stencil tests will never pass. i.e. Nothing will be drawn.
     Never = 0x0200,
@@ -434,7 +502,7 @@ pub enum StencilTest {
 }
 
 /// Constants passed to WebGLRenderingContext.stencilOp().
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StencilAction {
     ///
     Keep = 0x1E00,
@@ -452,7 +520,7 @@ pub enum StencilAction {
     DecrWrap = 0x8508,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PixelType {
     ///
     UnsignedByte = 0x1401,
@@ -475,7 +543,7 @@ pub enum PixelType {
     Float = 0x1406,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PixelFormat {
     ///
     DepthComponent = 0x1902,
@@ -492,7 +560,7 @@ pub enum PixelFormat {
 }
 
 /// Constants passed to WebGLRenderingContext.hint()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Hint {
     /// There is no preference for this behavior.
     DontCare = 0x1100,
@@ -505,7 +573,7 @@ pub enum Hint {
 }
 
 /// WebGLRenderingContext.texParameter[fi]() or WebGLRenderingContext.bindTexture() "target" parameter
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureKind {
     ///
     Texture2d = 0x0DE1,
@@ -514,7 +582,7 @@ pub enum TextureKind {
 }
 
 /// WebGLRenderingContext.texParameter[fi]() "pname" parameter
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureParameter {
     ///
     TextureMagFilter = 0x2800,
@@ -529,10 +597,12 @@ pub enum TextureParameter {
 
     /// WebGL 2.0 only
     TextureWrapR = 32882,
+    /// with anisotropic filtering, see [`GlExtensions`](::GlExtensions)
+    MaxAnisotropy = 0x84FE,
 }
 
 /// WebGLRenderingContext.texImage2D() "target" parameter
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureBindPoint {
     ///
     Texture2d = 0x0DE1,
@@ -551,7 +621,7 @@ pub enum TextureBindPoint {
 }
 
 /// WebGLRenderingContext.texParameter[fi]() "param" parameter
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureMagFilter {
     ///
     Nearest = 0x2600,
@@ -560,7 +630,7 @@ pub enum TextureMagFilter {
 }
 
 /// WebGLRenderingContext.texParameter[fi]() "param" parameter
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureMinFilter {
     ///
     Nearest = 0x2600,
@@ -588,7 +658,7 @@ pub enum TextureWrap {
 }
 
 /// Constants passed to WebGLRenderingContext.hint()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Buffers {
     ///
     Framebuffer = 0x8D40,
@@ -608,6 +678,12 @@ pub enum Buffers {
     StencilIndex8 = 0x8D48,
     ///
     DepthStencil = 0x84F9,
+    /// sized format, OpenGL ES 3.0 / WebGL 2.0
+    Rgba8 = 0x8058,
+    /// sized format, OpenGL ES 3.0 / WebGL 2.0
+    DepthComponent24 = 0x81A6,
+    /// sized format, OpenGL ES 3.0 / WebGL 2.0
+    Depth24Stencil8 = 0x88F0,
     ///
     RenderbufferWidth = 0x8D42,
     ///
@@ -665,7 +741,7 @@ pub enum Buffers {
 }
 
 /// Constants passed to WebGLRenderingContext.hint()
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PixelStorageMode {
     ///
     UnpackFlipYWebgl = 0x9240,
@@ -682,7 +758,7 @@ pub enum PixelStorageMode {
 }
 
 ///
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ShaderPrecision {
     ///
     LowFloat = 0x8DF0,
@@ -698,9 +774,15 @@ pub enum ShaderPrecision {
     HighInt = 0x8DF5,
 }
 
-/// Constants passed to WebGLRenderingContext.hint()
-#[derive(Debug, Clone, Copy)]
+/// Types of active uniforms and attributes, returned by WebGLRenderingContext.getActiveUniform() / getActiveAttrib()
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UniformType {
+    /// float scalar
+    Float = 0x1406,
+    /// int scalar
+    Int = 0x1404,
+    /// WebGL 2 only
+    UnsignedInt = 0x1405,
     ///
     FloatVec2 = 0x8B50,
     ///
@@ -731,10 +813,103 @@ pub enum UniformType {
     Sampler2d = 0x8B5E,
     ///
     SamplerCube = 0x8B60,
+    /// WebGL 2 only
+    UnsignedIntVec2 = 0x8DC6,
+    /// WebGL 2 only
+    UnsignedIntVec3 = 0x8DC7,
+    /// WebGL 2 only
+    UnsignedIntVec4 = 0x8DC8,
+    /// WebGL 2 only
+    FloatMat2x3 = 0x8B65,
+    /// WebGL 2 only
+    FloatMat2x4 = 0x8B66,
+    /// WebGL 2 only
+    FloatMat3x2 = 0x8B67,
+    /// WebGL 2 only
+    FloatMat3x4 = 0x8B68,
+    /// WebGL 2 only
+    FloatMat4x2 = 0x8B69,
+    /// WebGL 2 only
+    FloatMat4x3 = 0x8B6A,
+    /// WebGL 2 only
+    Sampler3d = 0x8B5F,
+    /// WebGL 2 only
+    Sampler2dShadow = 0x8B62,
+    /// WebGL 2 only
+    Sampler2dArray = 0x8DC1,
+    /// WebGL 2 only
+    Sampler2dArrayShadow = 0x8DC4,
+    /// WebGL 2 only
+    SamplerCubeShadow = 0x8DC5,
+    /// WebGL 2 only
+    IntSampler2d = 0x8DCA,
+    /// WebGL 2 only
+    IntSampler3d = 0x8DCB,
+    /// WebGL 2 only
+    IntSamplerCube = 0x8DCC,
+    /// WebGL 2 only
+    IntSampler2dArray = 0x8DCF,
+    /// WebGL 2 only
+    UnsignedIntSampler2d = 0x8DD2,
+    /// WebGL 2 only
+    UnsignedIntSampler3d = 0x8DD3,
+    /// WebGL 2 only
+    UnsignedIntSamplerCube = 0x8DD4,
+    /// WebGL 2 only
+    UnsignedIntSampler2dArray = 0x8DD7,
+}
+
+impl UniformType {
+    /// convert a type returned by getActiveUniform / getActiveAttrib, `None` if unknown
+    pub fn from_code(code: u32) -> Option<UniformType> {
+        Some(match code {
+            0x1406 => UniformType::Float,
+            0x1404 => UniformType::Int,
+            0x1405 => UniformType::UnsignedInt,
+            0x8B50 => UniformType::FloatVec2,
+            0x8B51 => UniformType::FloatVec3,
+            0x8B52 => UniformType::FloatVec4,
+            0x8B53 => UniformType::IntVec2,
+            0x8B54 => UniformType::IntVec3,
+            0x8B55 => UniformType::IntVec4,
+            0x8B56 => UniformType::Bool,
+            0x8B57 => UniformType::BoolVec2,
+            0x8B58 => UniformType::BoolVec3,
+            0x8B59 => UniformType::BoolVec4,
+            0x8B5A => UniformType::FloatMat2,
+            0x8B5B => UniformType::FloatMat3,
+            0x8B5C => UniformType::FloatMat4,
+            0x8B5E => UniformType::Sampler2d,
+            0x8B60 => UniformType::SamplerCube,
+            0x8DC6 => UniformType::UnsignedIntVec2,
+            0x8DC7 => UniformType::UnsignedIntVec3,
+            0x8DC8 => UniformType::UnsignedIntVec4,
+            0x8B65 => UniformType::FloatMat2x3,
+            0x8B66 => UniformType::FloatMat2x4,
+            0x8B67 => UniformType::FloatMat3x2,
+            0x8B68 => UniformType::FloatMat3x4,
+            0x8B69 => UniformType::FloatMat4x2,
+            0x8B6A => UniformType::FloatMat4x3,
+            0x8B5F => UniformType::Sampler3d,
+            0x8B62 => UniformType::Sampler2dShadow,
+            0x8DC1 => UniformType::Sampler2dArray,
+            0x8DC4 => UniformType::Sampler2dArrayShadow,
+            0x8DC5 => UniformType::SamplerCubeShadow,
+            0x8DCA => UniformType::IntSampler2d,
+            0x8DCB => UniformType::IntSampler3d,
+            0x8DCC => UniformType::IntSamplerCube,
+            0x8DCF => UniformType::IntSampler2dArray,
+            0x8DD2 => UniformType::UnsignedIntSampler2d,
+            0x8DD3 => UniformType::UnsignedIntSampler3d,
+            0x8DD4 => UniformType::UnsignedIntSamplerCube,
+            0x8DD7 => UniformType::UnsignedIntSampler2dArray,
+            _ => return None,
+        })
+    }
 }
 
 ///
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextureCompression {
     /// A DXT1-compressed image in an RGB image format.
     RgbDxt1 = 0x83F0,
@@ -750,7 +925,7 @@ pub enum TextureCompression {
 }
 
 ///
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColorBuffer {
     None = 0,
     Back = 0x0405,
@@ -764,3 +939,44 @@ pub enum ColorBuffer {
     ColorAttachment7 = 0x8CE7,
     ColorAttachment8 = 0x8CE8,
 }
+
+/// Status returned by WebGLRenderingContext.checkFramebufferStatus()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramebufferStatus {
+    /// the framebuffer can be rendered to
+    Complete = 0x8CD5,
+    /// an attachment has no image, or an image of a format it cannot render to
+    IncompleteAttachment = 0x8CD6,
+    /// nothing is attached
+    IncompleteMissingAttachment = 0x8CD7,
+    /// attachments of different sizes, OpenGL ES 2.0 / WebGL 1.0 only
+    IncompleteDimensions = 0x8CD9,
+    /// a draw buffer has no attachment, desktop OpenGL only
+    IncompleteDrawBuffer = 0x8CDB,
+    /// the read buffer has no attachment, desktop OpenGL only
+    IncompleteReadBuffer = 0x8CDC,
+    /// the combination of formats is not supported by the implementation
+    Unsupported = 0x8CDD,
+    /// attachments with different numbers of samples
+    IncompleteMultisample = 0x8D56,
+    /// the default framebuffer is bound and does not exist
+    Undefined = 0x8219,
+}
+
+impl FramebufferStatus {
+    /// convert a status returned by checkFramebufferStatus, `None` if unknown
+    pub fn from_code(code: u32) -> Option<FramebufferStatus> {
+        Some(match code {
+            0x8CD5 => FramebufferStatus::Complete,
+            0x8CD6 => FramebufferStatus::IncompleteAttachment,
+            0x8CD7 => FramebufferStatus::IncompleteMissingAttachment,
+            0x8CD9 => FramebufferStatus::IncompleteDimensions,
+            0x8CDB => FramebufferStatus::IncompleteDrawBuffer,
+            0x8CDC => FramebufferStatus::IncompleteReadBuffer,
+            0x8CDD => FramebufferStatus::Unsupported,
+            0x8D56 => FramebufferStatus::IncompleteMultisample,
+            0x8219 => FramebufferStatus::Undefined,
+            _ => return None,
+        })
+    }
+}
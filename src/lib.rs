@@ -3,6 +3,12 @@
 #[cfg(not(target_arch = "wasm32"))]
 extern crate gl;
 
+#[cfg(feature = "derive")]
+extern crate uni_gl_derive;
+
+#[cfg(target_arch = "wasm32")]
+extern crate js_sys;
+
 #[cfg(target_arch = "wasm32")]
 extern crate web_sys;
 
@@ -19,26 +25,66 @@ mod webgl;
 
 #[cfg(not(target_arch = "wasm32"))]
 /// whether current OpenGL context is OpenGL ES (Embedded System)
+#[deprecated(note = "native contexts can be OpenGL ES, use GLContext::is_gles")]
 pub const IS_GL_ES: bool = false;
 
 #[cfg(target_arch = "wasm32")]
+#[deprecated(note = "use GLContext::is_gles")]
 pub const IS_GL_ES: bool = true;
 
+mod backend;
+mod capabilities;
+mod diagnostic;
+mod error;
+mod extensions;
+mod framebuffer;
 mod glenum;
-
+mod owned;
+mod pipeline;
+mod reflection;
+mod state;
+mod tracking;
+mod vertex;
+
+#[cfg(feature = "mock")]
+/// recording backend for GPU-less tests, enabled with the `mock` feature
+pub mod mock;
+
+pub use backend::GlBackend;
+pub use capabilities::{Api, Capabilities};
+pub use diagnostic::{Severity, ShaderDiagnostic};
+pub use error::{CallError, ErrorPolicy, GlError};
+pub use extensions::{AnisotropicFiltering, GlExtensions, Support};
 pub use glenum::*;
+pub use owned::*;
+pub use pipeline::{BlendState, DepthState, PipelineState, PolygonOffset, StencilState};
+pub use reflection::{ActiveAttribute, ActiveUniform, ProgramReflection};
+pub use tracking::{LiveObject, ObjectKind, ObjectRef};
+pub use vertex::{
+    as_bytes, AttributeType, Pod, TypedBuffer, Vertex, VertexAttribute, VertexLayout,
+};
 pub use webgl::{GLContext, WebGLContext};
 
+#[cfg(feature = "derive")]
+pub use uni_gl_derive::Vertex;
+
 pub mod common {
     use std::ops::Deref;
+    use std::rc::Rc;
 
+    use super::{DeletionQueue, GlBackend, UniformType};
     type Reference = super::webgl::Reference;
     type GLContext = super::GLContext;
 
     #[derive(Debug, Clone)]
     /// The OpenGL rendering context. This is the struct providing most of the OpenGL API.
-    pub struct WebGLRenderingContext {
-        pub common: GLContext,
+    ///
+    /// It is generic over the [`GlBackend`] doing the actual work,
+    /// which defaults to the [`GLContext`] of the current target.
+    pub struct WebGLRenderingContext<B: GlBackend = GLContext> {
+        pub common: B,
+        /// objects dropped by their [`Owned`](::Owned) handle
+        pub(crate) deletions: DeletionQueue<B>,
     }
 
     impl From<GLContext> for Reference {
@@ -47,9 +93,9 @@ pub mod common {
         }
     }
 
-    impl Deref for WebGLRenderingContext {
-        type Target = GLContext;
-        fn deref(&self) -> &GLContext {
+    impl<B: GlBackend> Deref for WebGLRenderingContext<B> {
+        type Target = B;
+        fn deref(&self) -> &B {
             &self.common
         }
     }
@@ -118,15 +164,27 @@ pub mod common {
     /// the reference to a uniform (global GLSL variable) inside a shader, obtained with [`GLContext::get_uniform_location`].
     pub struct WebGLUniformLocation<T> {
         pub reference: T,
-        pub name: String,
+        /// name in the shader, shared with the location cache of the context
+        pub name: Rc<str>,
     }
-    impl<T> Deref for WebGLUniformLocation <T>{
+    impl<T> Deref for WebGLUniformLocation<T> {
         type Target = T;
         fn deref(&self) -> &Self::Target {
             &self.reference
         }
     }
 
+    #[derive(Debug, Clone, PartialEq)]
+    /// an active uniform or attribute of a linked program,
+    /// obtained with [`GLContext::get_active_uniform`] or [`GLContext::get_active_attrib`].
+    pub struct WebGLActiveInfo {
+        /// name in the shader, with a `[0]` suffix for arrays
+        pub name: String,
+        /// number of elements, greater than 1 for arrays
+        pub size: u32,
+        pub kind: UniformType,
+    }
+
     #[derive(Debug)]
     /// an OpenGL Framebuffer created with [`GLContext::create_framebuffer`].
     ///
@@ -139,6 +197,31 @@ pub mod common {
         }
     }
 
+    #[derive(Debug)]
+    /// an OpenGL Renderbuffer created with [`GLContext::create_renderbuffer`].
+    ///
+    /// A render target image that cannot be sampled, typically the depth and stencil buffers of a framebuffer.
+    pub struct WebGLRenderbuffer<T>(pub T);
+    impl<T> Deref for WebGLRenderbuffer<T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    #[derive(Debug)]
+    /// an OpenGL query object created with [`GLContext::create_timer_query`].
+    ///
+    /// It measures the GPU time of the commands issued between [`GLContext::begin_time_elapsed`]
+    /// and [`GLContext::end_time_elapsed`].
+    pub struct WebGLQuery<T>(pub T);
+    impl<T> Deref for WebGLQuery<T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
     /// Utility function to print messages to stdout (native) or the js console (web)
     pub fn print(s: &str) {
         GLContext::print(s);
@@ -0,0 +1,1546 @@
+//! A GPU-less [`GlBackend`] recording every call.
+//!
+//! [`MockContext`] allocates fake object handles and appends a [`GlCommand`] to an
+//! inspectable log for each call, so rendering code can run in plain `cargo test`:
+//!
+//! ```ignore
+//! let gl = uni_gl::WebGLRenderingContext::new_mock();
+//! render_frame(&gl);
+//! let draws = gl
+//!     .commands()
+//!     .iter()
+//!     .filter(|c| match c {
+//!         uni_gl::mock::GlCommand::DrawElements { .. } => true,
+//!         _ => false,
+//!     })
+//!     .count();
+//! assert_eq!(draws, 3);
+//! ```
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::panic::Location;
+
+use backend::GlBackend;
+use capabilities::{Api, Capabilities};
+use common::*;
+use diagnostic::with_header;
+use error::GlError;
+use extensions::GlExtensions;
+use glenum::*;
+use owned::DeletionQueue;
+use pipeline::{PipelineCache, PipelineState};
+use reflection::UniformCache;
+use state::{ShadowState, Slot};
+use tracking::{
+    buffer_binding, image_size, renderbuffer_size, texture_binding, LiveObject, ObjectKind,
+    ObjectRef, ObjectTracker,
+};
+
+/// A call issued on a [`MockContext`].
+///
+/// Object handles are the fake ids returned by the matching `create_*` call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlCommand {
+    CreateBuffer(u32),
+    DeleteBuffer(u32),
+    BindBuffer {
+        kind: BufferKind,
+        buffer: u32,
+    },
+    BufferData {
+        kind: BufferKind,
+        data: Vec<u8>,
+        draw: DrawMode,
+    },
+    BufferSubData {
+        kind: BufferKind,
+        offset: u32,
+        data: Vec<u8>,
+    },
+    UnbindBuffer(BufferKind),
+    CreateShader {
+        kind: ShaderKind,
+        shader: u32,
+    },
+    ShaderSource {
+        shader: u32,
+        source: String,
+    },
+    CompileShader(u32),
+    DeleteShader(u32),
+    CreateProgram(u32),
+    DeleteProgram(u32),
+    LinkProgram(u32),
+    UseProgram(u32),
+    AttachShader {
+        program: u32,
+        shader: u32,
+    },
+    BindAttribLocation {
+        program: u32,
+        name: String,
+        loc: u32,
+    },
+    GetAttribLocation {
+        program: u32,
+        name: String,
+    },
+    GetUniformLocation {
+        program: u32,
+        name: String,
+    },
+    VertexAttribPointer {
+        location: u32,
+        size: AttributeSize,
+        kind: DataType,
+        normalized: bool,
+        stride: u32,
+        offset: u32,
+    },
+    EnableVertexAttribArray(u32),
+    VertexAttribDivisor {
+        location: u32,
+        divisor: u32,
+    },
+    ClearColor(f32, f32, f32, f32),
+    Enable(i32),
+    Disable(i32),
+    CullFace(Culling),
+    DepthMask(bool),
+    DepthFunc(DepthTest),
+    ClearDepth(f32),
+    Clear(BufferBit),
+    Viewport {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    DrawElements {
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+    },
+    DrawArrays {
+        mode: Primitives,
+        count: usize,
+    },
+    DrawElementsInstanced {
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+        instances: usize,
+    },
+    DrawArraysInstanced {
+        mode: Primitives,
+        count: usize,
+        instances: usize,
+    },
+    ReadPixels {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        kind: PixelType,
+    },
+    PixelStorei(PixelStorageMode, i32),
+    TexImage2D {
+        target: TextureBindPoint,
+        level: u8,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: Vec<u8>,
+    },
+    TexSubImage2D {
+        target: TextureBindPoint,
+        level: u8,
+        xoffset: u16,
+        yoffset: u16,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: Vec<u8>,
+    },
+    CompressedTexImage2D {
+        target: TextureBindPoint,
+        level: u8,
+        compression: TextureCompression,
+        width: u16,
+        height: u16,
+        data: Vec<u8>,
+    },
+    GetProgramParameter {
+        program: u32,
+        pname: ShaderParameter,
+    },
+    GetActiveUniform {
+        program: u32,
+        index: u32,
+    },
+    GetActiveAttrib {
+        program: u32,
+        index: u32,
+    },
+    CreateTexture(u32),
+    DeleteTexture(u32),
+    GenerateMipmap,
+    GenerateMipmapCube,
+    ActiveTexture(u32),
+    BindTexture(u32),
+    UnbindTexture,
+    BindTextureCube(u32),
+    UnbindTextureCube,
+    BlendEquation(BlendEquation),
+    BlendFunc(BlendMode, BlendMode),
+    BlendColor(f32, f32, f32, f32),
+    StencilFunc {
+        func: StencilTest,
+        reference: i32,
+        mask: u32,
+    },
+    StencilOp {
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    },
+    StencilMask(u32),
+    StencilFuncSeparate {
+        face: Culling,
+        func: StencilTest,
+        reference: i32,
+        mask: u32,
+    },
+    StencilOpSeparate {
+        face: Culling,
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    },
+    StencilMaskSeparate(Culling, u32),
+    ClearStencil(i32),
+    ColorMask(bool, bool, bool, bool),
+    PolygonOffset(f32, f32),
+    Scissor {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    FrontFace(FrontFaceDirection),
+    LineWidth(f32),
+    Hint(Hint, Hint),
+    DepthRange(f32, f32),
+    SampleCoverage(f32, bool),
+    UniformMatrix4fv(u32, [[f32; 4]; 4]),
+    UniformMatrix3fv(u32, [[f32; 3]; 3]),
+    UniformMatrix2fv(u32, [[f32; 2]; 2]),
+    Uniform1i(u32, i32),
+    Uniform1f(u32, f32),
+    Uniform2f(u32, (f32, f32)),
+    Uniform3f(u32, (f32, f32, f32)),
+    Uniform4f(u32, (f32, f32, f32, f32)),
+    TexParameteri(TextureKind, TextureParameter, i32),
+    TexParameterfv(TextureKind, TextureParameter, f32),
+    CreateVertexArray(u32),
+    DeleteVertexArray(u32),
+    BindVertexArray(u32),
+    UnbindVertexArray(u32),
+    DrawBuffer(Vec<ColorBuffer>),
+    CreateFramebuffer(u32),
+    DeleteFramebuffer(u32),
+    BindFramebuffer {
+        buffer: Buffers,
+        fb: u32,
+    },
+    FramebufferTexture2D {
+        target: Buffers,
+        attachment: Buffers,
+        textarget: TextureBindPoint,
+        texture: u32,
+        level: i32,
+    },
+    UnbindFramebuffer(Buffers),
+    CreateRenderbuffer(u32),
+    DeleteRenderbuffer(u32),
+    BindRenderbuffer {
+        target: Buffers,
+        rb: u32,
+    },
+    UnbindRenderbuffer(Buffers),
+    RenderbufferStorage {
+        target: Buffers,
+        format: Buffers,
+        width: u16,
+        height: u16,
+    },
+    FramebufferRenderbuffer {
+        target: Buffers,
+        attachment: Buffers,
+        renderbuffertarget: Buffers,
+        rb: u32,
+    },
+    GetRenderbufferParameter {
+        target: Buffers,
+        pname: Buffers,
+    },
+    CheckFramebufferStatus(Buffers),
+    GetParameter(Parameter),
+    ObjectLabel {
+        kind: ObjectKind,
+        object: u32,
+        label: String,
+    },
+    CreateQuery(u32),
+    DeleteQuery(u32),
+    BeginTimeElapsed(u32),
+    EndTimeElapsed,
+    GetTimeElapsed(u32),
+}
+
+/// active attributes and uniforms of a program
+type Interface = (Vec<WebGLActiveInfo>, Vec<WebGLActiveInfo>);
+
+#[derive(Debug, Default)]
+/// A [`GlBackend`] that never touches a GPU.
+///
+/// Every call is recorded as a [`GlCommand`]. `create_*` calls return unique non-zero ids.
+/// Attribute and uniform locations are handed out per program in order of first query,
+/// unless bound explicitly with [`GlBackend::bind_attrib_location`].
+pub struct MockContext {
+    commands: RefCell<Vec<GlCommand>>,
+    next_handle: Cell<u32>,
+    attrib_locations: RefCell<HashMap<(u32, String), u32>>,
+    uniform_locations: RefCell<HashMap<(u32, String), u32>>,
+    /// uniform locations already queried, as cached by the other backends
+    uniforms: UniformCache<u32>,
+    /// active attributes and uniforms by program
+    interfaces: RefCell<HashMap<u32, Interface>>,
+    error: RefCell<Option<GlError>>,
+    context_lost: Cell<bool>,
+    /// format, width and height of allocated renderbuffers
+    renderbuffers: RefCell<HashMap<u32, (Buffers, u16, u16)>>,
+    /// values returned by the `get_parameter_*` calls
+    parameters: RefCell<HashMap<u32, Vec<f32>>>,
+    strings: RefCell<HashMap<u32, String>>,
+    /// returned by `check_framebuffer_status`, complete if `None`
+    framebuffer_status: Cell<Option<FramebufferStatus>>,
+    /// results of the timer queries, in nanoseconds
+    time_elapsed: RefCell<HashMap<u32, u64>>,
+    objects: ObjectTracker,
+    state: ShadowState,
+    pipeline: PipelineCache,
+    capabilities: Capabilities,
+    extensions: GlExtensions,
+}
+
+impl MockContext {
+    pub fn new() -> MockContext {
+        MockContext::default()
+    }
+
+    /// a copy of every command recorded so far
+    pub fn commands(&self) -> Vec<GlCommand> {
+        self.commands.borrow().clone()
+    }
+
+    /// return and forget every command recorded so far, typically once per frame
+    pub fn take_commands(&self) -> Vec<GlCommand> {
+        self.commands.replace(Vec::new())
+    }
+
+    /// forget every command recorded so far
+    pub fn clear_commands(&self) {
+        self.commands.borrow_mut().clear();
+    }
+
+    /// whether redundant state changes are skipped, see [`MockContext::set_state_cache`]
+    pub fn state_cache(&self) -> bool {
+        self.state.enabled()
+    }
+
+    /// skip calls setting bindings or fixed-function state to their current value,
+    /// as [`GLContext::set_state_cache`](::GLContext::set_state_cache) does. Skipped calls are not recorded.
+    pub fn set_state_cache(&self, enabled: bool) {
+        self.state.set_enabled(enabled);
+    }
+
+    /// forget the state known by the state cache and the last applied pipeline
+    pub fn invalidate_state(&self) {
+        self.state.invalidate();
+        self.objects.forget_bindings();
+        self.pipeline.invalidate();
+    }
+
+    /// make the next fallible call (or [`GlBackend::get_error`]) fail with `error`
+    pub fn set_error(&self, error: GlError) {
+        *self.error.borrow_mut() = Some(error);
+    }
+
+    /// simulate a context loss or restoration, as seen by [`GlBackend::is_context_lost`]
+    pub fn set_context_lost(&self, lost: bool) {
+        self.context_lost.set(lost);
+    }
+
+    /// set the value returned by the numeric `get_parameter_*` calls, 0 when not set.
+    ///
+    /// Booleans are non-zero values. The values of [`Parameter::CompressedTextureFormats`]
+    /// are returned by [`GlBackend::get_compressed_texture_formats`].
+    pub fn set_parameter(&self, pname: Parameter, values: &[f32]) {
+        self.parameters
+            .borrow_mut()
+            .insert(pname as u32, values.to_vec());
+    }
+
+    /// set the value returned by [`GlBackend::get_parameter_string`], empty when not set
+    pub fn set_parameter_string(&self, pname: Parameter, value: &str) {
+        self.strings.borrow_mut().insert(pname as u32, value.into());
+    }
+
+    /// set the value returned by [`GlBackend::capabilities`], [`Capabilities::default`] until then
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// set the value returned by [`GlBackend::extensions`], nothing supported until then.
+    ///
+    /// [`GlBackend::get_supported_extensions`] lists the extensions of [`MockContext::set_capabilities`].
+    pub fn set_extensions(&mut self, extensions: GlExtensions) {
+        self.extensions = extensions;
+    }
+
+    /// set the status returned by [`GlBackend::check_framebuffer_status`]
+    pub fn set_framebuffer_status(&self, status: FramebufferStatus) {
+        self.framebuffer_status.set(Some(status));
+    }
+
+    /// make a timer query result available, returned by [`GlBackend::get_time_elapsed`]
+    pub fn set_time_elapsed(&self, query: &WebGLQuery<u32>, nanoseconds: u64) {
+        self.time_elapsed.borrow_mut().insert(query.0, nanoseconds);
+    }
+
+    /// an error unless instancing was set as supported with [`MockContext::set_extensions`]
+    fn check_instancing(&self) -> Result<(), GlError> {
+        if self.extensions.instanced_arrays.is_none() {
+            return Err(GlError::Unsupported("instanced rendering"));
+        }
+        Ok(())
+    }
+
+    /// an error unless timer queries were set as supported with [`MockContext::set_extensions`]
+    fn check_timer_queries(&self) -> Result<(), GlError> {
+        if self.extensions.disjoint_timer_query.is_none() {
+            return Err(GlError::Unsupported("timer queries"));
+        }
+        Ok(())
+    }
+
+    /// set the active attributes and uniforms reported for a program
+    pub fn set_active_interface(
+        &self,
+        program: &WebGLProgram<u32>,
+        attributes: Vec<WebGLActiveInfo>,
+        uniforms: Vec<WebGLActiveInfo>,
+    ) {
+        self.interfaces
+            .borrow_mut()
+            .insert(program.0, (attributes, uniforms));
+    }
+
+    /// the values of a parameter, padded with zeros.
+    ///
+    /// As the other backends, a parameter with more than `len` values is not queried
+    /// and raises an invalid enum error, returned by [`GlBackend::get_error`].
+    fn parameter(&self, pname: Parameter, len: usize) -> [f32; 4] {
+        let count = pname.value_count();
+        if count == 0 || count > len {
+            self.set_error(GlError::Gl(Error::InvalidEnum));
+            return [0.0; 4];
+        }
+        self.record(GlCommand::GetParameter(pname));
+        let mut res = [0.0; 4];
+        if let Some(values) = self.parameters.borrow().get(&(pname as u32)) {
+            for (r, v) in res.iter_mut().zip(values) {
+                *r = *v;
+            }
+        }
+        res
+    }
+
+    fn take_error(&self) -> Result<(), GlError> {
+        match self.error.borrow_mut().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn record(&self, cmd: GlCommand) {
+        self.commands.borrow_mut().push(cmd);
+    }
+
+    fn alloc(&self) -> u32 {
+        let id = self.next_handle.get() + 1;
+        self.next_handle.set(id);
+        id
+    }
+}
+
+/// first location not yet handed out for this program
+fn next_location(locations: &HashMap<(u32, String), u32>, program: u32) -> u32 {
+    locations
+        .iter()
+        .filter(|&(k, _)| k.0 == program)
+        .map(|(_, &v)| v + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+impl WebGLRenderingContext<MockContext> {
+    /// create a rendering context backed by a [`MockContext`]
+    pub fn new_mock() -> WebGLRenderingContext<MockContext> {
+        WebGLRenderingContext {
+            common: MockContext::new(),
+            deletions: DeletionQueue::default(),
+        }
+    }
+}
+
+impl GlBackend for MockContext {
+    type Buffer = u32;
+    type Shader = u32;
+    type Program = u32;
+    type Texture = u32;
+    type VertexArray = u32;
+    type UniformLocation = u32;
+    type FrameBuffer = u32;
+    type Renderbuffer = u32;
+    type Query = u32;
+
+    fn get_error(&self) -> Result<(), GlError> {
+        self.take_error()
+    }
+
+    fn is_context_lost(&self) -> bool {
+        self.context_lost.get()
+    }
+
+    fn set_object_name<'a, O: Into<ObjectRef<'a, Self>>>(&self, object: O, name: &str) {
+        let object = object.into();
+        let key = match object {
+            ObjectRef::Buffer(o) => o.0,
+            ObjectRef::Shader(o) => o.0,
+            ObjectRef::Program(o) => o.0,
+            ObjectRef::Texture(o) => o.0,
+            ObjectRef::VertexArray(o) => o.0,
+            ObjectRef::FrameBuffer(o) => o.0,
+            ObjectRef::Renderbuffer(o) => o.0,
+        };
+        if self.extensions.debug_output.is_some() {
+            self.record(GlCommand::ObjectLabel {
+                kind: object.kind(),
+                object: key,
+                label: name.into(),
+            });
+        }
+        self.objects.set_name(object.kind(), key as u64, name);
+    }
+
+    fn live_objects(&self) -> Vec<LiveObject> {
+        self.objects.live()
+    }
+
+    fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    fn get_supported_extensions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.capabilities.extensions.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn extensions(&self) -> &GlExtensions {
+        &self.extensions
+    }
+
+    fn create_buffer(&self) -> Result<WebGLBuffer<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateBuffer(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::Buffer, id as u64, Location::caller());
+        Ok(WebGLBuffer(id))
+    }
+
+    fn delete_buffer(&self, buffer: &WebGLBuffer<u32>) {
+        self.record(GlCommand::DeleteBuffer(buffer.0));
+        self.objects.deleted(ObjectKind::Buffer, buffer.0 as u64);
+        self.state.deleted(ObjectKind::Buffer, buffer.0 as u64);
+    }
+
+    fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<u32>) {
+        self.objects.bind(buffer_binding(kind), buffer.0 as u64);
+        if !self
+            .state
+            .bind(Slot::Buffer(kind as u32), Some(buffer.0 as u64))
+        {
+            return;
+        }
+        self.record(GlCommand::BindBuffer {
+            kind,
+            buffer: buffer.0,
+        });
+    }
+
+    fn buffer_data(&self, kind: BufferKind, data: &[u8], draw: DrawMode) -> Result<(), GlError> {
+        self.record(GlCommand::BufferData {
+            kind,
+            data: data.to_vec(),
+            draw,
+        });
+        self.take_error()?;
+        self.objects
+            .set_buffer_size(self.objects.bound(buffer_binding(kind)), data.len());
+        Ok(())
+    }
+
+    fn buffer_sub_data(&self, kind: BufferKind, offset: u32, data: &[u8]) {
+        self.record(GlCommand::BufferSubData {
+            kind,
+            offset,
+            data: data.to_vec(),
+        });
+    }
+
+    fn unbind_buffer(&self, kind: BufferKind) {
+        self.objects.bind(buffer_binding(kind), 0);
+        if !self.state.bind(Slot::Buffer(kind as u32), Some(0)) {
+            return;
+        }
+        self.record(GlCommand::UnbindBuffer(kind));
+    }
+
+    fn create_shader(&self, kind: ShaderKind) -> Result<WebGLShader<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateShader { kind, shader: id });
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::Shader, id as u64, Location::caller());
+        Ok(WebGLShader(id))
+    }
+
+    fn shader_source(&self, shader: &WebGLShader<u32>, source: &str) {
+        self.record(GlCommand::ShaderSource {
+            shader: shader.0,
+            source: source.into(),
+        });
+        self.objects.set_header_lines(shader.0 as u64, 0);
+    }
+
+    fn shader_source_with_header(&self, shader: &WebGLShader<u32>, header: &str, source: &str) {
+        let (source, header_lines) = with_header(header, source);
+        self.shader_source(shader, &source);
+        self.objects.set_header_lines(shader.0 as u64, header_lines);
+    }
+
+    fn compile_shader(&self, shader: &WebGLShader<u32>) -> Result<(), GlError> {
+        self.record(GlCommand::CompileShader(shader.0));
+        // a compile error set with `set_error` gets the header of the shader
+        self.take_error().map_err(|e| match e {
+            GlError::ShaderCompile { log, .. } => GlError::ShaderCompile {
+                log,
+                header_lines: self.objects.header_lines(shader.0 as u64),
+            },
+            e => e,
+        })
+    }
+
+    fn create_program(&self) -> Result<WebGLProgram<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateProgram(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::Program, id as u64, Location::caller());
+        Ok(WebGLProgram(id))
+    }
+
+    fn link_program(&self, program: &WebGLProgram<u32>) -> Result<(), GlError> {
+        self.record(GlCommand::LinkProgram(program.0));
+        self.uniforms.clear(program.0 as u64);
+        self.take_error()
+    }
+
+    fn delete_shader(&self, shader: &WebGLShader<u32>) {
+        self.record(GlCommand::DeleteShader(shader.0));
+        self.objects.deleted(ObjectKind::Shader, shader.0 as u64);
+    }
+
+    fn delete_program(&self, program: &WebGLProgram<u32>) {
+        self.record(GlCommand::DeleteProgram(program.0));
+        self.uniforms.clear(program.0 as u64);
+        self.objects.deleted(ObjectKind::Program, program.0 as u64);
+        self.state.deleted(ObjectKind::Program, program.0 as u64);
+    }
+
+    fn use_program(&self, program: &WebGLProgram<u32>) {
+        if !self.state.bind(Slot::Program, Some(program.0 as u64)) {
+            return;
+        }
+        self.record(GlCommand::UseProgram(program.0));
+    }
+
+    fn attach_shader(&self, program: &WebGLProgram<u32>, shader: &WebGLShader<u32>) {
+        self.record(GlCommand::AttachShader {
+            program: program.0,
+            shader: shader.0,
+        });
+    }
+
+    fn bind_attrib_location(&self, program: &WebGLProgram<u32>, name: &str, loc: u32) {
+        self.attrib_locations
+            .borrow_mut()
+            .insert((program.0, name.into()), loc);
+        self.record(GlCommand::BindAttribLocation {
+            program: program.0,
+            name: name.into(),
+            loc,
+        });
+    }
+
+    fn get_attrib_location(&self, program: &WebGLProgram<u32>, name: &str) -> Option<u32> {
+        self.record(GlCommand::GetAttribLocation {
+            program: program.0,
+            name: name.into(),
+        });
+        let mut locations = self.attrib_locations.borrow_mut();
+        let next = next_location(&locations, program.0);
+        Some(*locations.entry((program.0, name.into())).or_insert(next))
+    }
+
+    fn get_uniform_location(
+        &self,
+        program: &WebGLProgram<u32>,
+        name: &str,
+    ) -> Option<WebGLUniformLocation<u32>> {
+        if let Some(location) = self.uniforms.get(program.0 as u64, name) {
+            return location;
+        }
+        self.record(GlCommand::GetUniformLocation {
+            program: program.0,
+            name: name.into(),
+        });
+        let mut locations = self.uniform_locations.borrow_mut();
+        let next = next_location(&locations, program.0);
+        let location = *locations.entry((program.0, name.into())).or_insert(next);
+        self.uniforms.insert(program.0 as u64, name, Some(location))
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        size: AttributeSize,
+        kind: DataType,
+        normalized: bool,
+        stride: u32,
+        offset: u32,
+    ) {
+        self.record(GlCommand::VertexAttribPointer {
+            location,
+            size,
+            kind,
+            normalized,
+            stride,
+            offset,
+        });
+    }
+
+    fn enable_vertex_attrib_array(&self, location: u32) {
+        self.record(GlCommand::EnableVertexAttribArray(location));
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32) -> Result<(), GlError> {
+        self.check_instancing()?;
+        self.record(GlCommand::VertexAttribDivisor { location, divisor });
+        self.take_error()
+    }
+
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.clear_color, [r, g, b, a]) {
+            return;
+        }
+        self.record(GlCommand::ClearColor(r, g, b, a));
+    }
+
+    fn enable(&self, flag: i32) {
+        if !self.state.flag(flag, true) {
+            return;
+        }
+        self.record(GlCommand::Enable(flag));
+    }
+
+    fn disable(&self, flag: i32) {
+        if !self.state.flag(flag, false) {
+            return;
+        }
+        self.record(GlCommand::Disable(flag));
+    }
+
+    fn cull_face(&self, flag: Culling) {
+        if !self.state.set(|s| &mut s.cull_face, flag) {
+            return;
+        }
+        self.record(GlCommand::CullFace(flag));
+    }
+
+    fn depth_mask(&self, b: bool) {
+        if !self.state.set(|s| &mut s.depth_mask, b) {
+            return;
+        }
+        self.record(GlCommand::DepthMask(b));
+    }
+
+    fn depth_func(&self, d: DepthTest) {
+        if !self.state.set(|s| &mut s.depth_func, d) {
+            return;
+        }
+        self.record(GlCommand::DepthFunc(d));
+    }
+
+    fn clear_depth(&self, value: f32) {
+        if !self.state.set(|s| &mut s.clear_depth, value) {
+            return;
+        }
+        self.record(GlCommand::ClearDepth(value));
+    }
+
+    fn clear(&self, bit: BufferBit) {
+        self.record(GlCommand::Clear(bit));
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.viewport, (x, y, width, height)) {
+            return;
+        }
+        self.record(GlCommand::Viewport {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32) {
+        self.record(GlCommand::DrawElements {
+            mode,
+            count,
+            kind,
+            offset,
+        });
+    }
+
+    fn draw_arrays(&self, mode: Primitives, count: usize) {
+        self.record(GlCommand::DrawArrays { mode, count });
+    }
+
+    fn draw_elements_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.check_instancing()?;
+        self.record(GlCommand::DrawElementsInstanced {
+            mode,
+            count,
+            kind,
+            offset,
+            instances,
+        });
+        self.take_error()
+    }
+
+    fn draw_arrays_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.check_instancing()?;
+        self.record(GlCommand::DrawArraysInstanced {
+            mode,
+            count,
+            instances,
+        });
+        self.take_error()
+    }
+
+    fn read_pixels(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        kind: PixelType,
+        data: &mut [u8],
+    ) -> Result<(), GlError> {
+        for b in data.iter_mut() {
+            *b = 0;
+        }
+        self.record(GlCommand::ReadPixels {
+            x,
+            y,
+            width,
+            height,
+            format,
+            kind,
+        });
+        self.take_error()
+    }
+
+    fn pixel_storei(&self, storage: PixelStorageMode, value: i32) {
+        self.record(GlCommand::PixelStorei(storage, value));
+    }
+
+    fn tex_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: &[u8],
+    ) -> Result<(), GlError> {
+        self.record(GlCommand::TexImage2D {
+            target,
+            level,
+            width,
+            height,
+            format,
+            kind,
+            pixels: pixels.to_vec(),
+        });
+        self.take_error()?;
+        self.objects.set_image_size(
+            self.objects.bound(texture_binding(target)),
+            target,
+            level,
+            image_size(width, height, format, kind),
+        );
+        Ok(())
+    }
+
+    fn tex_sub_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        xoffset: u16,
+        yoffset: u16,
+        width: u16,
+        height: u16,
+        format: PixelFormat,
+        kind: PixelType,
+        pixels: &[u8],
+    ) {
+        self.record(GlCommand::TexSubImage2D {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            width,
+            height,
+            format,
+            kind,
+            pixels: pixels.to_vec(),
+        });
+    }
+
+    fn compressed_tex_image2d(
+        &self,
+        target: TextureBindPoint,
+        level: u8,
+        compression: TextureCompression,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> Result<(), GlError> {
+        self.record(GlCommand::CompressedTexImage2D {
+            target,
+            level,
+            compression,
+            width,
+            height,
+            data: data.to_vec(),
+        });
+        self.take_error()?;
+        self.objects.set_image_size(
+            self.objects.bound(texture_binding(target)),
+            target,
+            level,
+            data.len(),
+        );
+        Ok(())
+    }
+
+    fn get_program_parameter(&self, program: &WebGLProgram<u32>, pname: ShaderParameter) -> i32 {
+        self.record(GlCommand::GetProgramParameter {
+            program: program.0,
+            pname,
+        });
+        let interfaces = self.interfaces.borrow();
+        let interface = interfaces.get(&program.0);
+        match pname {
+            ShaderParameter::LinkStatus | ShaderParameter::ValidateStatus => 1,
+            ShaderParameter::ActiveAttributes => interface.map_or(0, |i| i.0.len() as i32),
+            ShaderParameter::ActiveUniforms => interface.map_or(0, |i| i.1.len() as i32),
+            _ => 0,
+        }
+    }
+
+    fn get_active_uniform(
+        &self,
+        program: &WebGLProgram<u32>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.record(GlCommand::GetActiveUniform {
+            program: program.0,
+            index,
+        });
+        self.interfaces
+            .borrow()
+            .get(&program.0)
+            .and_then(|i| i.1.get(index as usize).cloned())
+    }
+
+    fn get_active_attrib(
+        &self,
+        program: &WebGLProgram<u32>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.record(GlCommand::GetActiveAttrib {
+            program: program.0,
+            index,
+        });
+        self.interfaces
+            .borrow()
+            .get(&program.0)
+            .and_then(|i| i.0.get(index as usize).cloned())
+    }
+
+    fn get_parameter_i32(&self, pname: Parameter) -> i32 {
+        self.parameter(pname, 1)[0] as i32
+    }
+
+    fn get_parameter_f32(&self, pname: Parameter) -> f32 {
+        self.parameter(pname, 1)[0]
+    }
+
+    fn get_parameter_bool(&self, pname: Parameter) -> bool {
+        self.parameter(pname, 1)[0] != 0.0
+    }
+
+    fn get_parameter_i32x4(&self, pname: Parameter) -> [i32; 4] {
+        let [a, b, c, d] = self.parameter(pname, 4);
+        [a as i32, b as i32, c as i32, d as i32]
+    }
+
+    fn get_parameter_f32x4(&self, pname: Parameter) -> [f32; 4] {
+        self.parameter(pname, 4)
+    }
+
+    fn get_parameter_string(&self, pname: Parameter) -> String {
+        self.record(GlCommand::GetParameter(pname));
+        self.strings
+            .borrow()
+            .get(&(pname as u32))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn get_compressed_texture_formats(&self) -> Vec<u32> {
+        self.record(GlCommand::GetParameter(Parameter::CompressedTextureFormats));
+        self.parameters
+            .borrow()
+            .get(&(Parameter::CompressedTextureFormats as u32))
+            .map(|formats| formats.iter().map(|&f| f as u32).collect())
+            .unwrap_or_default()
+    }
+
+    fn create_texture(&self) -> Result<WebGLTexture<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateTexture(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::Texture, id as u64, Location::caller());
+        Ok(WebGLTexture(id))
+    }
+
+    fn delete_texture(&self, texture: &WebGLTexture<u32>) {
+        self.record(GlCommand::DeleteTexture(texture.0));
+        self.objects.deleted(ObjectKind::Texture, texture.0 as u64);
+        self.state.deleted(ObjectKind::Texture, texture.0 as u64);
+    }
+
+    fn generate_mipmap(&self) {
+        self.record(GlCommand::GenerateMipmap);
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::Texture2d)),
+            TextureKind::Texture2d,
+        );
+    }
+
+    fn generate_mipmap_cube(&self) {
+        self.record(GlCommand::GenerateMipmapCube);
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::TextureCubeMapPositiveX)),
+            TextureKind::TextureCubeMap,
+        );
+    }
+
+    fn active_texture(&self, active: u32) {
+        self.objects.active_texture(active);
+        if !self.state.set(|s| &mut s.active_texture, active) {
+            return;
+        }
+        self.record(GlCommand::ActiveTexture(active));
+    }
+
+    fn bind_texture(&self, texture: &WebGLTexture<u32>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::Texture2d),
+            texture.0 as u64,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::Texture2d, Some(texture.0 as u64))
+        {
+            return;
+        }
+        self.record(GlCommand::BindTexture(texture.0));
+    }
+
+    fn unbind_texture(&self) {
+        self.objects
+            .bind(texture_binding(TextureBindPoint::Texture2d), 0);
+        if !self.state.bind_texture(TextureKind::Texture2d, Some(0)) {
+            return;
+        }
+        self.record(GlCommand::UnbindTexture);
+    }
+
+    fn bind_texture_cube(&self, texture: &WebGLTexture<u32>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            texture.0 as u64,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, Some(texture.0 as u64))
+        {
+            return;
+        }
+        self.record(GlCommand::BindTextureCube(texture.0));
+    }
+
+    fn unbind_texture_cube(&self) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            0,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, Some(0))
+        {
+            return;
+        }
+        self.record(GlCommand::UnbindTextureCube);
+    }
+
+    fn blend_equation(&self, eq: BlendEquation) {
+        if !self.state.set(|s| &mut s.blend_equation, eq) {
+            return;
+        }
+        self.record(GlCommand::BlendEquation(eq));
+    }
+
+    fn blend_func(&self, b1: BlendMode, b2: BlendMode) {
+        if !self.state.set(|s| &mut s.blend_func, (b1, b2)) {
+            return;
+        }
+        self.record(GlCommand::BlendFunc(b1, b2));
+    }
+
+    fn blend_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.blend_color, [r, g, b, a]) {
+            return;
+        }
+        self.record(GlCommand::BlendColor(r, g, b, a));
+    }
+
+    fn stencil_func(&self, func: StencilTest, reference: i32, mask: u32) {
+        self.record(GlCommand::StencilFunc {
+            func,
+            reference,
+            mask,
+        });
+    }
+
+    fn stencil_op(&self, fail: StencilAction, depth_fail: StencilAction, pass: StencilAction) {
+        self.record(GlCommand::StencilOp {
+            fail,
+            depth_fail,
+            pass,
+        });
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        self.record(GlCommand::StencilMask(mask));
+    }
+
+    fn stencil_func_separate(&self, face: Culling, func: StencilTest, reference: i32, mask: u32) {
+        self.record(GlCommand::StencilFuncSeparate {
+            face,
+            func,
+            reference,
+            mask,
+        });
+    }
+
+    fn stencil_op_separate(
+        &self,
+        face: Culling,
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    ) {
+        self.record(GlCommand::StencilOpSeparate {
+            face,
+            fail,
+            depth_fail,
+            pass,
+        });
+    }
+
+    fn stencil_mask_separate(&self, face: Culling, mask: u32) {
+        self.record(GlCommand::StencilMaskSeparate(face, mask));
+    }
+
+    fn clear_stencil(&self, value: i32) {
+        self.record(GlCommand::ClearStencil(value));
+    }
+
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool) {
+        self.record(GlCommand::ColorMask(r, g, b, a));
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        self.record(GlCommand::PolygonOffset(factor, units));
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.scissor, (x, y, width, height)) {
+            return;
+        }
+        self.record(GlCommand::Scissor {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    fn front_face(&self, mode: FrontFaceDirection) {
+        if !self.state.set(|s| &mut s.front_face, mode) {
+            return;
+        }
+        self.record(GlCommand::FrontFace(mode));
+    }
+
+    fn line_width(&self, width: f32) {
+        if !self.state.set(|s| &mut s.line_width, width) {
+            return;
+        }
+        self.record(GlCommand::LineWidth(width));
+    }
+
+    fn hint(&self, target: Hint, mode: Hint) {
+        self.record(GlCommand::Hint(target, mode));
+    }
+
+    fn depth_range(&self, near: f32, far: f32) {
+        if !self.state.set(|s| &mut s.depth_range, (near, far)) {
+            return;
+        }
+        self.record(GlCommand::DepthRange(near, far));
+    }
+
+    fn sample_coverage(&self, value: f32, invert: bool) {
+        self.record(GlCommand::SampleCoverage(value, invert));
+    }
+
+    fn apply_pipeline(&self, state: &PipelineState) {
+        self.pipeline.apply(self, state);
+    }
+
+    fn uniform_matrix_4fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 4]; 4]) {
+        self.record(GlCommand::UniformMatrix4fv(location.reference, *value));
+    }
+
+    fn uniform_matrix_3fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 3]; 3]) {
+        self.record(GlCommand::UniformMatrix3fv(location.reference, *value));
+    }
+
+    fn uniform_matrix_2fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 2]; 2]) {
+        self.record(GlCommand::UniformMatrix2fv(location.reference, *value));
+    }
+
+    fn uniform_1i(&self, location: &WebGLUniformLocation<u32>, value: i32) {
+        self.record(GlCommand::Uniform1i(location.reference, value));
+    }
+
+    fn uniform_1f(&self, location: &WebGLUniformLocation<u32>, value: f32) {
+        self.record(GlCommand::Uniform1f(location.reference, value));
+    }
+
+    fn uniform_2f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32)) {
+        self.record(GlCommand::Uniform2f(location.reference, value));
+    }
+
+    fn uniform_3f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32, f32)) {
+        self.record(GlCommand::Uniform3f(location.reference, value));
+    }
+
+    fn uniform_4f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32, f32, f32)) {
+        self.record(GlCommand::Uniform4f(location.reference, value));
+    }
+
+    fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32) {
+        self.record(GlCommand::TexParameteri(kind, pname, param));
+    }
+
+    fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32) {
+        self.record(GlCommand::TexParameterfv(kind, pname, param));
+    }
+
+    fn create_vertex_array(&self) -> Result<WebGLVertexArray<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateVertexArray(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::VertexArray, id as u64, Location::caller());
+        Ok(WebGLVertexArray(id))
+    }
+
+    fn delete_vertex_array(&self, vao: &WebGLVertexArray<u32>) {
+        self.record(GlCommand::DeleteVertexArray(vao.0));
+        self.objects.deleted(ObjectKind::VertexArray, vao.0 as u64);
+        self.state.deleted(ObjectKind::VertexArray, vao.0 as u64);
+    }
+
+    fn bind_vertex_array(&self, vao: &WebGLVertexArray<u32>) {
+        self.objects.bind_vertex_array(vao.0 as u64);
+        if !self.state.bind(Slot::VertexArray, Some(vao.0 as u64)) {
+            return;
+        }
+        self.record(GlCommand::BindVertexArray(vao.0));
+    }
+
+    fn unbind_vertex_array(&self, vao: &WebGLVertexArray<u32>) {
+        self.objects.bind_vertex_array(0);
+        if !self.state.bind(Slot::VertexArray, Some(0)) {
+            return;
+        }
+        self.record(GlCommand::UnbindVertexArray(vao.0));
+    }
+
+    fn draw_buffer(&self, buffers: &[ColorBuffer]) -> Result<(), GlError> {
+        if self.capabilities.api == Api::OpenGlEs && self.capabilities.version.0 < 3 {
+            return Err(GlError::Unsupported("draw buffers: OpenGL ES 2.0"));
+        }
+        self.record(GlCommand::DrawBuffer(buffers.to_vec()));
+        self.take_error()
+    }
+
+    fn create_framebuffer(&self) -> Result<WebGLFrameBuffer<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateFramebuffer(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::FrameBuffer, id as u64, Location::caller());
+        Ok(WebGLFrameBuffer(id))
+    }
+
+    fn delete_framebuffer(&self, fb: &WebGLFrameBuffer<u32>) {
+        self.record(GlCommand::DeleteFramebuffer(fb.0));
+        self.objects.deleted(ObjectKind::FrameBuffer, fb.0 as u64);
+        self.state.deleted(ObjectKind::FrameBuffer, fb.0 as u64);
+    }
+
+    fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer<u32>) {
+        if !self
+            .state
+            .bind(Slot::FrameBuffer(buffer as u32), Some(fb.0 as u64))
+        {
+            return;
+        }
+        self.record(GlCommand::BindFramebuffer { buffer, fb: fb.0 });
+    }
+
+    fn framebuffer_texture2d(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        textarget: TextureBindPoint,
+        texture: &WebGLTexture<u32>,
+        level: i32,
+    ) {
+        self.record(GlCommand::FramebufferTexture2D {
+            target,
+            attachment,
+            textarget,
+            texture: texture.0,
+            level,
+        });
+    }
+
+    fn unbind_framebuffer(&self, buffer: Buffers) {
+        if !self.state.bind(Slot::FrameBuffer(buffer as u32), Some(0)) {
+            return;
+        }
+        self.record(GlCommand::UnbindFramebuffer(buffer));
+    }
+
+    fn create_renderbuffer(&self) -> Result<WebGLRenderbuffer<u32>, GlError> {
+        let id = self.alloc();
+        self.record(GlCommand::CreateRenderbuffer(id));
+        self.take_error()?;
+        self.objects
+            .created(ObjectKind::Renderbuffer, id as u64, Location::caller());
+        Ok(WebGLRenderbuffer(id))
+    }
+
+    fn delete_renderbuffer(&self, rb: &WebGLRenderbuffer<u32>) {
+        self.record(GlCommand::DeleteRenderbuffer(rb.0));
+        self.renderbuffers.borrow_mut().remove(&rb.0);
+        self.objects.deleted(ObjectKind::Renderbuffer, rb.0 as u64);
+        self.state.deleted(ObjectKind::Renderbuffer, rb.0 as u64);
+    }
+
+    fn bind_renderbuffer(&self, target: Buffers, rb: &WebGLRenderbuffer<u32>) {
+        self.objects
+            .bind(Buffers::RenderbufferBinding as u32, rb.0 as u64);
+        if !self.state.bind(Slot::Renderbuffer, Some(rb.0 as u64)) {
+            return;
+        }
+        self.record(GlCommand::BindRenderbuffer { target, rb: rb.0 });
+    }
+
+    fn unbind_renderbuffer(&self, target: Buffers) {
+        self.objects.bind(Buffers::RenderbufferBinding as u32, 0);
+        if !self.state.bind(Slot::Renderbuffer, Some(0)) {
+            return;
+        }
+        self.record(GlCommand::UnbindRenderbuffer(target));
+    }
+
+    fn renderbuffer_storage(
+        &self,
+        target: Buffers,
+        format: Buffers,
+        width: u16,
+        height: u16,
+    ) -> Result<(), GlError> {
+        self.record(GlCommand::RenderbufferStorage {
+            target,
+            format,
+            width,
+            height,
+        });
+        self.take_error()?;
+        let rb = self.objects.bound(Buffers::RenderbufferBinding as u32);
+        self.renderbuffers
+            .borrow_mut()
+            .insert(rb as u32, (format, width, height));
+        self.objects
+            .set_renderbuffer_size(rb, renderbuffer_size(width, height, format));
+        Ok(())
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        renderbuffertarget: Buffers,
+        rb: &WebGLRenderbuffer<u32>,
+    ) {
+        self.record(GlCommand::FramebufferRenderbuffer {
+            target,
+            attachment,
+            renderbuffertarget,
+            rb: rb.0,
+        });
+    }
+
+    fn get_renderbuffer_parameter(&self, target: Buffers, pname: Buffers) -> i32 {
+        self.record(GlCommand::GetRenderbufferParameter { target, pname });
+        let rb = self.objects.bound(Buffers::RenderbufferBinding as u32) as u32;
+        let storage = self.renderbuffers.borrow().get(&rb).cloned();
+        match (pname, storage) {
+            (Buffers::RenderbufferWidth, Some((_, width, _))) => width as i32,
+            (Buffers::RenderbufferHeight, Some((_, _, height))) => height as i32,
+            (Buffers::RenderbufferInternalFormat, Some((format, _, _))) => format as i32,
+            _ => 0,
+        }
+    }
+
+    fn check_framebuffer_status(&self, target: Buffers) -> Result<FramebufferStatus, GlError> {
+        self.record(GlCommand::CheckFramebufferStatus(target));
+        self.take_error()?;
+        Ok(self
+            .framebuffer_status
+            .get()
+            .unwrap_or(FramebufferStatus::Complete))
+    }
+
+    fn create_timer_query(&self) -> Result<WebGLQuery<u32>, GlError> {
+        self.check_timer_queries()?;
+        let id = self.alloc();
+        self.record(GlCommand::CreateQuery(id));
+        self.take_error()?;
+        Ok(WebGLQuery(id))
+    }
+
+    fn delete_timer_query(&self, query: &WebGLQuery<u32>) {
+        self.record(GlCommand::DeleteQuery(query.0));
+        self.time_elapsed.borrow_mut().remove(&query.0);
+    }
+
+    fn begin_time_elapsed(&self, query: &WebGLQuery<u32>) -> Result<(), GlError> {
+        self.check_timer_queries()?;
+        self.record(GlCommand::BeginTimeElapsed(query.0));
+        self.take_error()
+    }
+
+    fn end_time_elapsed(&self) -> Result<(), GlError> {
+        self.check_timer_queries()?;
+        self.record(GlCommand::EndTimeElapsed);
+        self.take_error()
+    }
+
+    fn get_time_elapsed(&self, query: &WebGLQuery<u32>) -> Result<Option<u64>, GlError> {
+        self.check_timer_queries()?;
+        self.record(GlCommand::GetTimeElapsed(query.0));
+        self.take_error()?;
+        Ok(self.time_elapsed.borrow().get(&query.0).cloned())
+    }
+}
@@ -0,0 +1,219 @@
+//! Owned GL object handles, deleting the object when dropped.
+//!
+//! The raw handles of [`common`](::common) are plain values: the object they refer to lives until the
+//! matching `delete_*` call. Wrapping one in an [`Owned`] makes the deletion automatic:
+//!
+//! ```ignore
+//! let vbo = gl.own(gl.create_buffer()?);
+//! gl.bind_buffer(BufferKind::Array, &vbo);
+//! drop(vbo);
+//! // once per frame, on the thread owning the context
+//! gl.flush_deletions();
+//! ```
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use backend::GlBackend;
+use common::*;
+use webgl::GLContext;
+
+/// A GL object waiting in a [`DeletionQueue`].
+pub enum Deletion<B: GlBackend> {
+    Buffer(WebGLBuffer<B::Buffer>),
+    Shader(WebGLShader<B::Shader>),
+    Program(WebGLProgram<B::Program>),
+    Texture(WebGLTexture<B::Texture>),
+    VertexArray(WebGLVertexArray<B::VertexArray>),
+    FrameBuffer(WebGLFrameBuffer<B::FrameBuffer>),
+    Renderbuffer(WebGLRenderbuffer<B::Renderbuffer>),
+}
+
+impl<B: GlBackend> Deletion<B> {
+    fn run(self, gl: &B) {
+        match self {
+            Deletion::Buffer(buffer) => gl.delete_buffer(&buffer),
+            Deletion::Shader(shader) => gl.delete_shader(&shader),
+            Deletion::Program(program) => gl.delete_program(&program),
+            Deletion::Texture(texture) => gl.delete_texture(&texture),
+            Deletion::VertexArray(vao) => gl.delete_vertex_array(&vao),
+            Deletion::FrameBuffer(fb) => gl.delete_framebuffer(&fb),
+            Deletion::Renderbuffer(rb) => gl.delete_renderbuffer(&rb),
+        }
+    }
+}
+
+/// A raw handle type that can be wrapped in an [`Owned`].
+pub trait GlObject<B: GlBackend> {
+    /// the deletion of this object
+    fn into_deletion(self) -> Deletion<B>;
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLBuffer<B::Buffer> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::Buffer(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLShader<B::Shader> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::Shader(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLProgram<B::Program> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::Program(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLTexture<B::Texture> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::Texture(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLVertexArray<B::VertexArray> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::VertexArray(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLFrameBuffer<B::FrameBuffer> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::FrameBuffer(self)
+    }
+}
+
+impl<B: GlBackend> GlObject<B> for WebGLRenderbuffer<B::Renderbuffer> {
+    fn into_deletion(self) -> Deletion<B> {
+        Deletion::Renderbuffer(self)
+    }
+}
+
+/// Objects dropped by their [`Owned`] handle, shared by every clone of a [`WebGLRenderingContext`].
+pub struct DeletionQueue<B: GlBackend>(Arc<Mutex<Vec<Deletion<B>>>>);
+
+impl<B: GlBackend> DeletionQueue<B> {
+    fn push(&self, deletion: Deletion<B>) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(deletion);
+    }
+
+    fn take(&self) -> Vec<Deletion<B>> {
+        mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// number of objects waiting for deletion
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<B: GlBackend> Default for DeletionQueue<B> {
+    fn default() -> DeletionQueue<B> {
+        DeletionQueue(Arc::new(Mutex::new(Vec::new())))
+    }
+}
+
+impl<B: GlBackend> Clone for DeletionQueue<B> {
+    fn clone(&self) -> DeletionQueue<B> {
+        DeletionQueue(self.0.clone())
+    }
+}
+
+impl<B: GlBackend> fmt::Debug for DeletionQueue<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeletionQueue")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+/// A GL object handle deleting the object when dropped.
+///
+/// Deletion is deferred to the next [`WebGLRenderingContext::flush_deletions`],
+/// so owned handles can be dropped anywhere, including on a thread not owning the context.
+/// It dereferences into the raw handle, so it can be passed to every [`GlBackend`] call.
+pub struct Owned<B: GlBackend, H: GlObject<B>> {
+    handle: Option<H>,
+    queue: DeletionQueue<B>,
+}
+
+impl<B: GlBackend, H: GlObject<B>> Owned<B, H> {
+    /// take ownership of a raw handle created with `gl`
+    pub fn from_raw(gl: &WebGLRenderingContext<B>, handle: H) -> Owned<B, H> {
+        Owned {
+            handle: Some(handle),
+            queue: gl.deletions.clone(),
+        }
+    }
+
+    /// release ownership, the object must then be deleted by hand
+    pub fn into_raw(mut self) -> H {
+        self.handle.take().unwrap()
+    }
+}
+
+impl<B: GlBackend, H: GlObject<B>> Deref for Owned<B, H> {
+    type Target = H;
+    fn deref(&self) -> &H {
+        self.handle.as_ref().unwrap()
+    }
+}
+
+impl<B: GlBackend, H: GlObject<B>> Drop for Owned<B, H> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.queue.push(handle.into_deletion());
+        }
+    }
+}
+
+impl<B: GlBackend, H: GlObject<B> + fmt::Debug> fmt::Debug for Owned<B, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Owned").field(&self.handle).finish()
+    }
+}
+
+/// an owned [`WebGLBuffer`]
+pub type OwnedBuffer<B = GLContext> = Owned<B, WebGLBuffer<<B as GlBackend>::Buffer>>;
+/// an owned [`WebGLShader`]
+pub type OwnedShader<B = GLContext> = Owned<B, WebGLShader<<B as GlBackend>::Shader>>;
+/// an owned [`WebGLProgram`]
+pub type OwnedProgram<B = GLContext> = Owned<B, WebGLProgram<<B as GlBackend>::Program>>;
+/// an owned [`WebGLTexture`]
+pub type OwnedTexture<B = GLContext> = Owned<B, WebGLTexture<<B as GlBackend>::Texture>>;
+/// an owned [`WebGLVertexArray`]
+pub type OwnedVertexArray<B = GLContext> =
+    Owned<B, WebGLVertexArray<<B as GlBackend>::VertexArray>>;
+/// an owned [`WebGLFrameBuffer`]
+pub type OwnedFrameBuffer<B = GLContext> =
+    Owned<B, WebGLFrameBuffer<<B as GlBackend>::FrameBuffer>>;
+/// an owned [`WebGLRenderbuffer`]
+pub type OwnedRenderbuffer<B = GLContext> =
+    Owned<B, WebGLRenderbuffer<<B as GlBackend>::Renderbuffer>>;
+
+impl<B: GlBackend> WebGLRenderingContext<B> {
+    /// take ownership of a raw handle, see [`Owned::from_raw`]
+    pub fn own<H: GlObject<B>>(&self, handle: H) -> Owned<B, H> {
+        Owned::from_raw(self, handle)
+    }
+
+    /// delete every object dropped by its [`Owned`] handle since last call.
+    ///
+    /// Must be called on the thread owning the context, typically once per frame,
+    /// and once more before the context is destroyed.
+    pub fn flush_deletions(&self) {
+        for deletion in self.deletions.take() {
+            deletion.run(&self.common);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Fixed-function state applied as a whole, only changed parts reaching the driver.
+//!
+//! ```ignore
+//! const OPAQUE: PipelineState = PipelineState::new()
+//!     .depth(DepthState::LESS)
+//!     .cull(Culling::Back);
+//! const TRANSPARENT: PipelineState = OPAQUE.blend(BlendState::ALPHA);
+//!
+//! gl.apply_pipeline(&OPAQUE);
+//! draw_opaque(&gl);
+//! gl.apply_pipeline(&TRANSPARENT); // only enables and configures blending
+//! draw_transparent(&gl);
+//! ```
+use std::cell::RefCell;
+
+use backend::GlBackend;
+use glenum::*;
+
+/// Blending, see [`GlBackend::blend_equation`] and [`GlBackend::blend_func`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+    pub equation: BlendEquation,
+    pub src: BlendMode,
+    pub dst: BlendMode,
+}
+
+impl BlendState {
+    /// straight alpha blending
+    pub const ALPHA: BlendState = BlendState {
+        equation: BlendEquation::FuncAdd,
+        src: BlendMode::SrcAlpha,
+        dst: BlendMode::OneMinusSrcAlpha,
+    };
+    /// premultiplied alpha blending
+    pub const PREMULTIPLIED: BlendState = BlendState {
+        equation: BlendEquation::FuncAdd,
+        src: BlendMode::One,
+        dst: BlendMode::OneMinusSrcAlpha,
+    };
+    /// additive blending
+    pub const ADD: BlendState = BlendState {
+        equation: BlendEquation::FuncAdd,
+        src: BlendMode::One,
+        dst: BlendMode::One,
+    };
+}
+
+/// Depth test, see [`GlBackend::depth_func`] and [`GlBackend::depth_mask`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthState {
+    pub func: DepthTest,
+    /// whether passing fragments write their depth
+    pub write: bool,
+}
+
+impl DepthState {
+    /// nearest fragment wins, the usual opaque geometry setting
+    pub const LESS: DepthState = DepthState {
+        func: DepthTest::Less,
+        write: true,
+    };
+    /// test against the depth buffer without writing it, e.g. for transparent geometry
+    pub const READ_ONLY: DepthState = DepthState {
+        func: DepthTest::Lequal,
+        write: false,
+    };
+}
+
+/// Stencil test, see [`GlBackend::stencil_func`], [`GlBackend::stencil_op`] and [`GlBackend::stencil_mask`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StencilState {
+    pub func: StencilTest,
+    pub reference: i32,
+    /// mask ANDed with the reference and stored values before the test
+    pub mask: u32,
+    /// action when the stencil test fails
+    pub fail: StencilAction,
+    /// action when the stencil test passes and the depth test fails
+    pub depth_fail: StencilAction,
+    /// action when both tests pass
+    pub pass: StencilAction,
+    /// bits of the stencil buffer that can be written
+    pub write_mask: u32,
+}
+
+/// Depth offset of polygons, see [`GlBackend::polygon_offset`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32,
+}
+
+/// The fixed-function state of a draw, applied with [`GlBackend::apply_pipeline`].
+///
+/// `None` parts are disabled. The default value matches the initial OpenGL state.
+///
+/// Disabling the depth or stencil test restores its write mask, which also applies to
+/// [`GlBackend::clear`]. The color mask is applied as is, so clearing the color buffer requires
+/// a pipeline writing all channels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipelineState {
+    pub blend: Option<BlendState>,
+    pub depth: Option<DepthState>,
+    pub stencil: Option<StencilState>,
+    /// faces culled
+    pub cull: Option<Culling>,
+    /// red, green, blue, alpha channels written
+    pub color_mask: [bool; 4],
+    pub polygon_offset: Option<PolygonOffset>,
+    /// whether the scissor test is enabled
+    pub scissor: bool,
+}
+
+impl PipelineState {
+    /// the initial OpenGL state : everything disabled, all channels written
+    pub const fn new() -> PipelineState {
+        PipelineState {
+            blend: None,
+            depth: None,
+            stencil: None,
+            cull: None,
+            color_mask: [true; 4],
+            polygon_offset: None,
+            scissor: false,
+        }
+    }
+
+    pub const fn blend(mut self, blend: BlendState) -> PipelineState {
+        self.blend = Some(blend);
+        self
+    }
+
+    pub const fn depth(mut self, depth: DepthState) -> PipelineState {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub const fn stencil(mut self, stencil: StencilState) -> PipelineState {
+        self.stencil = Some(stencil);
+        self
+    }
+
+    pub const fn cull(mut self, cull: Culling) -> PipelineState {
+        self.cull = Some(cull);
+        self
+    }
+
+    pub const fn color_mask(mut self, r: bool, g: bool, b: bool, a: bool) -> PipelineState {
+        self.color_mask = [r, g, b, a];
+        self
+    }
+
+    pub const fn polygon_offset(mut self, factor: f32, units: f32) -> PipelineState {
+        self.polygon_offset = Some(PolygonOffset { factor, units });
+        self
+    }
+
+    pub const fn scissor(mut self, scissor: bool) -> PipelineState {
+        self.scissor = scissor;
+        self
+    }
+}
+
+impl Default for PipelineState {
+    fn default() -> PipelineState {
+        PipelineState::new()
+    }
+}
+
+/// enable or disable a capability
+fn set_flag<B: GlBackend>(gl: &B, flag: i32, on: bool) {
+    if on {
+        gl.enable(flag);
+    } else {
+        gl.disable(flag);
+    }
+}
+
+/// issue the calls turning a part on or off and changing its values.
+///
+/// last : the part in the previous state, `None` if disabled, the previous state being unknown if `first`
+fn apply_part<B: GlBackend, T: PartialEq, F: Fn(&T, Option<&T>)>(
+    gl: &B,
+    flag: i32,
+    first: bool,
+    last: Option<&T>,
+    next: Option<&T>,
+    configure: F,
+) {
+    if first || last.is_some() != next.is_some() {
+        set_flag(gl, flag, next.is_some());
+    }
+    if let Some(next) = next {
+        let last = if first { None } else { last };
+        if last != Some(next) {
+            configure(next, last);
+        }
+    }
+}
+
+/// last pipeline applied to a context
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct PipelineCache {
+    last: RefCell<Option<PipelineState>>,
+}
+
+impl PipelineCache {
+    /// the next apply issues every call
+    pub fn invalidate(&self) {
+        *self.last.borrow_mut() = None;
+    }
+
+    /// issue the calls changing the last applied state into `next`
+    pub fn apply<B: GlBackend>(&self, gl: &B, next: &PipelineState) {
+        let mut last = self.last.borrow_mut();
+        let first = last.is_none();
+        let prev = last.unwrap_or_default();
+
+        apply_part(
+            gl,
+            Flag::Blend as i32,
+            first,
+            prev.blend.as_ref(),
+            next.blend.as_ref(),
+            |b, last| {
+                if last.map(|l| l.equation) != Some(b.equation) {
+                    gl.blend_equation(b.equation);
+                }
+                if last.map(|l| (l.src, l.dst)) != Some((b.src, b.dst)) {
+                    gl.blend_func(b.src, b.dst);
+                }
+            },
+        );
+
+        apply_part(
+            gl,
+            Flag::DepthTest as i32,
+            first,
+            prev.depth.as_ref(),
+            next.depth.as_ref(),
+            |d, last| {
+                if last.map(|l| l.func) != Some(d.func) {
+                    gl.depth_func(d.func);
+                }
+                if last.map(|l| l.write) != Some(d.write) {
+                    gl.depth_mask(d.write);
+                }
+            },
+        );
+        // the write masks also apply to clear, restore them when the test goes off
+        if next.depth.is_none() && (first || prev.depth.is_some_and(|d| !d.write)) {
+            gl.depth_mask(true);
+        }
+
+        apply_part(
+            gl,
+            Flag::StencilTest as i32,
+            first,
+            prev.stencil.as_ref(),
+            next.stencil.as_ref(),
+            |s, last| {
+                if last.map(|l| (l.func, l.reference, l.mask))
+                    != Some((s.func, s.reference, s.mask))
+                {
+                    gl.stencil_func(s.func, s.reference, s.mask);
+                }
+                if last.map(|l| (l.fail, l.depth_fail, l.pass))
+                    != Some((s.fail, s.depth_fail, s.pass))
+                {
+                    gl.stencil_op(s.fail, s.depth_fail, s.pass);
+                }
+                if last.map(|l| l.write_mask) != Some(s.write_mask) {
+                    gl.stencil_mask(s.write_mask);
+                }
+            },
+        );
+        if next.stencil.is_none() && (first || prev.stencil.is_some_and(|s| s.write_mask != !0)) {
+            gl.stencil_mask(!0);
+        }
+
+        if first || prev.cull.is_some() != next.cull.is_some() {
+            set_flag(gl, Culling::CullFace as i32, next.cull.is_some());
+        }
+        if let Some(cull) = next.cull {
+            if first || prev.cull != Some(cull) {
+                gl.cull_face(cull);
+            }
+        }
+
+        apply_part(
+            gl,
+            Flag::PolygonOffsetFill as i32,
+            first,
+            prev.polygon_offset.as_ref(),
+            next.polygon_offset.as_ref(),
+            |o, _| gl.polygon_offset(o.factor, o.units),
+        );
+
+        if first || prev.color_mask != next.color_mask {
+            let [r, g, b, a] = next.color_mask;
+            gl.color_mask(r, g, b, a);
+        }
+
+        if first || prev.scissor != next.scissor {
+            set_flag(gl, Flag::ScissorTest as i32, next.scissor);
+        }
+
+        *last = Some(*next);
+    }
+}
@@ -0,0 +1,175 @@
+//! Active attributes and uniforms of a linked program.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use backend::GlBackend;
+use common::*;
+use glenum::*;
+use webgl::GLContext;
+
+/// An active attribute of a [`ProgramReflection`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveAttribute {
+    pub name: String,
+    /// number of elements, greater than 1 for arrays
+    pub size: u32,
+    pub kind: UniformType,
+    pub location: u32,
+}
+
+/// An active uniform of a [`ProgramReflection`].
+#[derive(Debug, PartialEq)]
+pub struct ActiveUniform<L> {
+    /// name in the shader, with a `[0]` suffix for arrays
+    pub name: String,
+    /// number of elements, greater than 1 for arrays
+    pub size: u32,
+    pub kind: UniformType,
+    pub location: WebGLUniformLocation<L>,
+}
+
+/// Every active attribute and uniform of a linked program.
+///
+/// ```ignore
+/// gl.link_program(&program)?;
+/// let reflection = ProgramReflection::new(&gl, &program);
+/// for u in &reflection.uniforms {
+///     println!("{} : {:?}", u.name, u.kind);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProgramReflection<B: GlBackend = GLContext> {
+    /// attributes ordered by location
+    pub attributes: Vec<ActiveAttribute>,
+    /// uniforms ordered by name
+    pub uniforms: Vec<ActiveUniform<B::UniformLocation>>,
+}
+
+impl<B: GlBackend> ProgramReflection<B> {
+    /// query the interface of a program, which must be linked
+    pub fn new(
+        gl: &WebGLRenderingContext<B>,
+        program: &WebGLProgram<B::Program>,
+    ) -> ProgramReflection<B> {
+        ProgramReflection::query(&gl.common, program)
+    }
+
+    pub(crate) fn query(gl: &B, program: &WebGLProgram<B::Program>) -> ProgramReflection<B> {
+        let count = gl.get_program_parameter(program, ShaderParameter::ActiveAttributes);
+        let mut attributes: Vec<ActiveAttribute> = (0..count.max(0) as u32)
+            .filter_map(|i| gl.get_active_attrib(program, i))
+            .filter_map(|info| {
+                // built-in attributes like gl_VertexID have no location
+                let location = gl.get_attrib_location(program, &info.name)?;
+                Some(ActiveAttribute {
+                    name: info.name,
+                    size: info.size,
+                    kind: info.kind,
+                    location,
+                })
+            })
+            .collect();
+        attributes.sort_by_key(|a| a.location);
+
+        let count = gl.get_program_parameter(program, ShaderParameter::ActiveUniforms);
+        let mut uniforms: Vec<ActiveUniform<B::UniformLocation>> = (0..count.max(0) as u32)
+            .filter_map(|i| gl.get_active_uniform(program, i))
+            .filter_map(|info| {
+                // members of uniform blocks have no location
+                let location = gl.get_uniform_location(program, &info.name)?;
+                Some(ActiveUniform {
+                    name: info.name,
+                    size: info.size,
+                    kind: info.kind,
+                    location,
+                })
+            })
+            .collect();
+        uniforms.sort_by(|a, b| a.name.cmp(&b.name));
+
+        ProgramReflection {
+            attributes,
+            uniforms,
+        }
+    }
+
+    /// the active attribute named `name`
+    pub fn attribute(&self, name: &str) -> Option<&ActiveAttribute> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+
+    /// the active uniform named `name`, arrays matching with or without their `[0]` suffix
+    pub fn uniform(&self, name: &str) -> Option<&ActiveUniform<B::UniformLocation>> {
+        self.uniforms
+            .iter()
+            .find(|u| u.name == name || (u.size > 1 && u.name.trim_end_matches("[0]") == name))
+    }
+}
+
+/// uniform locations of a program by name, including names without location
+type Locations<L> = HashMap<Rc<str>, Option<L>>;
+
+/// uniform locations by program key
+#[derive(Debug, PartialEq)]
+pub(crate) struct UniformCache<L> {
+    programs: RefCell<HashMap<u64, Locations<L>>>,
+}
+
+impl<L: Clone> UniformCache<L> {
+    /// `None` on a cache miss, `Some(None)` for a name known to have no location
+    pub fn get(&self, program: u64, name: &str) -> Option<Option<WebGLUniformLocation<L>>> {
+        let programs = self.programs.borrow();
+        let (name, location) = programs.get(&program)?.get_key_value(name)?;
+        Some(location.as_ref().map(|l| WebGLUniformLocation {
+            reference: l.clone(),
+            name: name.clone(),
+        }))
+    }
+
+    /// remember the location of a name, returned as a [`WebGLUniformLocation`]
+    pub fn insert(
+        &self,
+        program: u64,
+        name: &str,
+        location: Option<L>,
+    ) -> Option<WebGLUniformLocation<L>> {
+        let name: Rc<str> = name.into();
+        self.programs
+            .borrow_mut()
+            .entry(program)
+            .or_default()
+            .insert(name.clone(), location.clone());
+        location.map(|reference| WebGLUniformLocation { reference, name })
+    }
+
+    /// forget the locations of a program, after it is linked again or deleted
+    pub fn clear(&self, program: u64) {
+        self.programs.borrow_mut().remove(&program);
+    }
+
+    /// forget every location, after a context loss
+    #[cfg(target_arch = "wasm32")]
+    pub fn clear_all(&self) {
+        self.programs.borrow_mut().clear();
+    }
+}
+
+impl<L> Default for UniformCache<L> {
+    fn default() -> UniformCache<L> {
+        UniformCache {
+            programs: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+/// look up the location of every active uniform of a freshly linked program,
+/// filling the location cache of `gl`
+pub(crate) fn cache_active_uniforms<B: GlBackend>(gl: &B, program: &WebGLProgram<B::Program>) {
+    let count = gl.get_program_parameter(program, ShaderParameter::ActiveUniforms);
+    for i in 0..count.max(0) as u32 {
+        if let Some(info) = gl.get_active_uniform(program, i) {
+            gl.get_uniform_location(program, &info.name);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Optional shadow copy of the GL state, skipping calls that would not change it.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use glenum::*;
+use tracking::ObjectKind;
+
+/// a binding point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Slot {
+    Program,
+    Buffer(u32),
+    Texture { unit: u32, target: u32 },
+    VertexArray,
+    FrameBuffer(u32),
+    Renderbuffer,
+}
+
+impl Slot {
+    fn kind(self) -> ObjectKind {
+        match self {
+            Slot::Program => ObjectKind::Program,
+            Slot::Buffer(_) => ObjectKind::Buffer,
+            Slot::Texture { .. } => ObjectKind::Texture,
+            Slot::VertexArray => ObjectKind::VertexArray,
+            Slot::FrameBuffer(_) => ObjectKind::FrameBuffer,
+            Slot::Renderbuffer => ObjectKind::Renderbuffer,
+        }
+    }
+}
+
+/// last values set, `None` when unknown
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct State {
+    /// object key by binding point, 0 for no object
+    bindings: HashMap<Slot, u64>,
+    flags: HashMap<i32, bool>,
+    pub active_texture: Option<u32>,
+    pub blend_equation: Option<BlendEquation>,
+    pub blend_func: Option<(BlendMode, BlendMode)>,
+    pub blend_color: Option<[f32; 4]>,
+    pub clear_color: Option<[f32; 4]>,
+    pub clear_depth: Option<f32>,
+    pub cull_face: Option<Culling>,
+    pub front_face: Option<FrontFaceDirection>,
+    pub depth_func: Option<DepthTest>,
+    pub depth_mask: Option<bool>,
+    pub depth_range: Option<(f32, f32)>,
+    pub line_width: Option<f32>,
+    pub scissor: Option<(i32, i32, u32, u32)>,
+    pub viewport: Option<(i32, i32, u32, u32)>,
+}
+
+/// shadow state of a context, disabled by default
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ShadowState {
+    enabled: Cell<bool>,
+    state: RefCell<State>,
+}
+
+impl ShadowState {
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// turning it on or off starts from an unknown state
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        self.invalidate();
+    }
+
+    /// forget every value, the next calls being forwarded to GL
+    pub fn invalidate(&self) {
+        *self.state.borrow_mut() = State::default();
+    }
+
+    /// whether setting a value must be forwarded to GL, remembering it
+    pub fn set<T: PartialEq, F: FnOnce(&mut State) -> &mut Option<T>>(
+        &self,
+        field: F,
+        value: T,
+    ) -> bool {
+        if !self.enabled.get() {
+            return true;
+        }
+        let mut state = self.state.borrow_mut();
+        let current = field(&mut state);
+        if current.as_ref() == Some(&value) {
+            return false;
+        }
+        *current = Some(value);
+        true
+    }
+
+    /// whether enabling or disabling a flag must be forwarded to GL
+    pub fn flag(&self, flag: i32, on: bool) -> bool {
+        if !self.enabled.get() {
+            return true;
+        }
+        self.state.borrow_mut().flags.insert(flag, on) != Some(on)
+    }
+
+    /// whether binding an object must be forwarded to GL.
+    ///
+    /// object : key of the object, 0 to unbind, `None` for an object without key
+    pub fn bind(&self, slot: Slot, object: Option<u64>) -> bool {
+        if !self.enabled.get() {
+            return true;
+        }
+        let mut state = self.state.borrow_mut();
+        let bindings = &mut state.bindings;
+        let key = match object {
+            Some(key) => key,
+            None => {
+                bindings.remove(&slot);
+                return true;
+            }
+        };
+        if bindings.insert(slot, key) == Some(key) {
+            return false;
+        }
+        if slot == Slot::VertexArray {
+            // the element array buffer binding is part of the vertex array object
+            bindings.remove(&Slot::Buffer(BufferKind::ElementArray as u32));
+        }
+        true
+    }
+
+    /// whether binding a texture to the active unit must be forwarded to GL
+    pub fn bind_texture(&self, target: TextureKind, object: Option<u64>) -> bool {
+        let unit = match self.state.borrow().active_texture {
+            Some(unit) => unit,
+            None => return true,
+        };
+        self.bind(
+            Slot::Texture {
+                unit,
+                target: target as u32,
+            },
+            object,
+        )
+    }
+
+    /// forget the bindings of a deleted object, which GL unbinds
+    pub fn deleted(&self, kind: ObjectKind, key: u64) {
+        self.state
+            .borrow_mut()
+            .bindings
+            .retain(|slot, bound| slot.kind() != kind || *bound != key);
+    }
+}
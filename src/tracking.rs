@@ -0,0 +1,407 @@
+//! Live GL object tracking, reported by [`GlBackend::live_objects`](::GlBackend::live_objects)
+//! and dumped as leaks when the context is dropped.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::Location;
+
+use backend::GlBackend;
+use common::*;
+use glenum::*;
+
+/// Kind of a GL object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    Buffer,
+    Shader,
+    Program,
+    Texture,
+    VertexArray,
+    FrameBuffer,
+    Renderbuffer,
+}
+
+/// A GL object created and not deleted yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveObject {
+    pub kind: ObjectKind,
+    /// where the `create_*` call was made
+    pub location: &'static Location<'static>,
+    /// name given with [`GlBackend::set_object_name`](::GlBackend::set_object_name)
+    pub name: Option<String>,
+    /// estimated memory used by the object data, in bytes
+    pub size: usize,
+}
+
+impl fmt::Display for LiveObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.kind)?;
+        if let Some(ref name) = self.name {
+            write!(f, " '{}'", name)?;
+        }
+        write!(f, " ({} bytes) created at {}", self.size, self.location)
+    }
+}
+
+/// A reference to any GL object, see [`GlBackend::set_object_name`](::GlBackend::set_object_name).
+pub enum ObjectRef<'a, B: GlBackend + 'a> {
+    Buffer(&'a WebGLBuffer<B::Buffer>),
+    Shader(&'a WebGLShader<B::Shader>),
+    Program(&'a WebGLProgram<B::Program>),
+    Texture(&'a WebGLTexture<B::Texture>),
+    VertexArray(&'a WebGLVertexArray<B::VertexArray>),
+    FrameBuffer(&'a WebGLFrameBuffer<B::FrameBuffer>),
+    Renderbuffer(&'a WebGLRenderbuffer<B::Renderbuffer>),
+}
+
+impl<'a, B: GlBackend> ObjectRef<'a, B> {
+    pub fn kind(&self) -> ObjectKind {
+        match *self {
+            ObjectRef::Buffer(_) => ObjectKind::Buffer,
+            ObjectRef::Shader(_) => ObjectKind::Shader,
+            ObjectRef::Program(_) => ObjectKind::Program,
+            ObjectRef::Texture(_) => ObjectKind::Texture,
+            ObjectRef::VertexArray(_) => ObjectKind::VertexArray,
+            ObjectRef::FrameBuffer(_) => ObjectKind::FrameBuffer,
+            ObjectRef::Renderbuffer(_) => ObjectKind::Renderbuffer,
+        }
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLBuffer<B::Buffer>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLBuffer<B::Buffer>) -> ObjectRef<'a, B> {
+        ObjectRef::Buffer(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLShader<B::Shader>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLShader<B::Shader>) -> ObjectRef<'a, B> {
+        ObjectRef::Shader(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLProgram<B::Program>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLProgram<B::Program>) -> ObjectRef<'a, B> {
+        ObjectRef::Program(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLTexture<B::Texture>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLTexture<B::Texture>) -> ObjectRef<'a, B> {
+        ObjectRef::Texture(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLVertexArray<B::VertexArray>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLVertexArray<B::VertexArray>) -> ObjectRef<'a, B> {
+        ObjectRef::VertexArray(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLFrameBuffer<B::FrameBuffer>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLFrameBuffer<B::FrameBuffer>) -> ObjectRef<'a, B> {
+        ObjectRef::FrameBuffer(o)
+    }
+}
+
+impl<'a, B: GlBackend> From<&'a WebGLRenderbuffer<B::Renderbuffer>> for ObjectRef<'a, B> {
+    fn from(o: &'a WebGLRenderbuffer<B::Renderbuffer>) -> ObjectRef<'a, B> {
+        ObjectRef::Renderbuffer(o)
+    }
+}
+
+/// pseudo level holding the estimated size of generated mipmaps
+const MIPMAPS: u8 = u8::MAX;
+
+/// live objects of a context, keyed by kind and a backend specific id
+#[derive(Debug, PartialEq)]
+pub(crate) struct ObjectTracker {
+    objects: RefCell<HashMap<(ObjectKind, u64), LiveObject>>,
+    /// texture image sizes by texture, target and level
+    images: RefCell<HashMap<(u64, u32, u8), usize>>,
+    /// bound object key by binding parameter and texture unit or vertex array, see [`ObjectTracker::bound`]
+    bindings: RefCell<HashMap<(u32, u64), u64>>,
+    /// header line count by shader, see [`GlBackend::shader_source_with_header`](::GlBackend::shader_source_with_header)
+    shader_headers: RefCell<HashMap<u64, u32>>,
+    active_texture: Cell<u32>,
+    vertex_array: Cell<u64>,
+    #[cfg(target_arch = "wasm32")]
+    next_key: Cell<u64>,
+    /// whether leaks are printed when the tracker is dropped
+    report_leaks: bool,
+}
+
+impl ObjectTracker {
+    pub fn new(report_leaks: bool) -> ObjectTracker {
+        ObjectTracker {
+            objects: RefCell::new(HashMap::new()),
+            images: RefCell::new(HashMap::new()),
+            bindings: RefCell::new(HashMap::new()),
+            shader_headers: RefCell::new(HashMap::new()),
+            active_texture: Cell::new(0),
+            vertex_array: Cell::new(0),
+            #[cfg(target_arch = "wasm32")]
+            next_key: Cell::new(0),
+            report_leaks,
+        }
+    }
+
+    /// a fresh id, for backends whose handles have none
+    #[cfg(target_arch = "wasm32")]
+    pub fn next_key(&self) -> u64 {
+        let key = self.next_key.get() + 1;
+        self.next_key.set(key);
+        key
+    }
+
+    pub fn created(&self, kind: ObjectKind, key: u64, location: &'static Location<'static>) {
+        self.objects.borrow_mut().insert(
+            (kind, key),
+            LiveObject {
+                kind,
+                location,
+                name: None,
+                size: 0,
+            },
+        );
+    }
+
+    pub fn deleted(&self, kind: ObjectKind, key: u64) {
+        self.objects.borrow_mut().remove(&(kind, key));
+        if kind == ObjectKind::Texture {
+            self.images.borrow_mut().retain(|k, _| k.0 != key);
+        }
+        if kind == ObjectKind::Shader {
+            self.shader_headers.borrow_mut().remove(&key);
+        }
+        // GL unbinds deleted objects
+        if kind == ObjectKind::VertexArray {
+            if self.vertex_array.get() == key {
+                self.vertex_array.set(0);
+            }
+            return;
+        }
+        self.bindings
+            .borrow_mut()
+            .retain(|&(binding, _), bound| binding_kind(binding) != kind || *bound != key);
+    }
+
+    /// forget every object, created in a context that was lost
+    #[cfg(target_arch = "wasm32")]
+    pub fn clear(&self) {
+        self.objects.borrow_mut().clear();
+        self.images.borrow_mut().clear();
+        self.shader_headers.borrow_mut().clear();
+        self.forget_bindings();
+    }
+
+    /// an object was bound to a binding parameter, 0 when unbinding
+    pub fn bind(&self, binding: u32, key: u64) {
+        let scope = self.binding_scope(binding);
+        self.bindings.borrow_mut().insert((binding, scope), key);
+    }
+
+    /// the active texture unit changed
+    pub fn active_texture(&self, unit: u32) {
+        self.active_texture.set(unit);
+    }
+
+    /// a vertex array was bound, 0 when unbinding
+    pub fn bind_vertex_array(&self, key: u64) {
+        self.vertex_array.set(key);
+    }
+
+    /// key of the object bound to a binding parameter by the last bind call, 0 if none or unknown.
+    ///
+    /// Bindings are recorded by the bind calls, so that setting object data does not query GL.
+    pub fn bound(&self, binding: u32) -> u64 {
+        let scope = self.binding_scope(binding);
+        self.bindings
+            .borrow()
+            .get(&(binding, scope))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// forget the bindings, e.g. after external code made GL calls
+    pub fn forget_bindings(&self) {
+        self.bindings.borrow_mut().clear();
+        self.active_texture.set(0);
+        self.vertex_array.set(0);
+    }
+
+    /// texture bindings are per texture unit, the element array buffer one per vertex array
+    fn binding_scope(&self, binding: u32) -> u64 {
+        match binding_kind(binding) {
+            ObjectKind::Texture => self.active_texture.get() as u64,
+            ObjectKind::Buffer if binding == buffer_binding(BufferKind::ElementArray) => {
+                self.vertex_array.get()
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn set_name(&self, kind: ObjectKind, key: u64, name: &str) {
+        if let Some(o) = self.objects.borrow_mut().get_mut(&(kind, key)) {
+            o.name = Some(name.into());
+        }
+    }
+
+    /// record the number of header lines prepended to the source of a shader
+    pub fn set_header_lines(&self, key: u64, lines: u32) {
+        self.shader_headers.borrow_mut().insert(key, lines);
+    }
+
+    /// header lines prepended to the source of a shader, 0 if none
+    pub fn header_lines(&self, key: u64) -> u32 {
+        self.shader_headers.borrow().get(&key).cloned().unwrap_or(0)
+    }
+
+    pub fn set_buffer_size(&self, key: u64, size: usize) {
+        self.set_size(ObjectKind::Buffer, key, size);
+    }
+
+    pub fn set_renderbuffer_size(&self, key: u64, size: usize) {
+        self.set_size(ObjectKind::Renderbuffer, key, size);
+    }
+
+    fn set_size(&self, kind: ObjectKind, key: u64, size: usize) {
+        if let Some(o) = self.objects.borrow_mut().get_mut(&(kind, key)) {
+            o.size = size;
+        }
+    }
+
+    /// record the size of a texture image
+    pub fn set_image_size(&self, key: u64, target: TextureBindPoint, level: u8, size: usize) {
+        self.images
+            .borrow_mut()
+            .insert((key, target as u32, level), size);
+        self.update_texture_size(key);
+    }
+
+    /// record mipmaps generated from level 0, a third of its size
+    pub fn generate_mipmap(&self, key: u64, kind: TextureKind) {
+        let mut images = self.images.borrow_mut();
+        let level0: usize = images
+            .iter()
+            .filter(|&(k, _)| k.0 == key && k.2 == 0)
+            .map(|(_, size)| size)
+            .sum();
+        images.insert((key, kind as u32, MIPMAPS), level0 / 3);
+        drop(images);
+        self.update_texture_size(key);
+    }
+
+    fn update_texture_size(&self, key: u64) {
+        let size = self
+            .images
+            .borrow()
+            .iter()
+            .filter(|&(k, _)| k.0 == key)
+            .map(|(_, size)| size)
+            .sum();
+        if let Some(o) = self
+            .objects
+            .borrow_mut()
+            .get_mut(&(ObjectKind::Texture, key))
+        {
+            o.size = size;
+        }
+    }
+
+    /// every live object, ordered by creation site
+    pub fn live(&self) -> Vec<LiveObject> {
+        let mut objects: Vec<LiveObject> = self.objects.borrow().values().cloned().collect();
+        objects.sort_by_key(|o| (o.location.file(), o.location.line(), o.location.column()));
+        objects
+    }
+}
+
+impl Default for ObjectTracker {
+    /// a tracker not reporting leaks
+    fn default() -> ObjectTracker {
+        ObjectTracker::new(false)
+    }
+}
+
+impl Drop for ObjectTracker {
+    fn drop(&mut self) {
+        if !self.report_leaks {
+            return;
+        }
+        let leaks = self.live();
+        if leaks.is_empty() {
+            return;
+        }
+        let mut report = format!("uni-gl: {} GL objects leaked", leaks.len());
+        for o in leaks {
+            report.push_str(&format!("\n  {}", o));
+        }
+        log_leaks(&report);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn log_leaks(report: &str) {
+    eprintln!("{}", report);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn log_leaks(report: &str) {
+    ::web_sys::console::warn_1(&report.into());
+}
+
+/// estimated size of a texture image
+pub(crate) fn image_size(width: u16, height: u16, format: PixelFormat, kind: PixelType) -> usize {
+    let components = match format {
+        PixelFormat::DepthComponent | PixelFormat::Alpha | PixelFormat::Luminance => 1,
+        PixelFormat::LuminanceAlpha => 2,
+        PixelFormat::Rgb => 3,
+        PixelFormat::Rgba => 4,
+    };
+    let pixel = match kind {
+        PixelType::UnsignedByte => components,
+        PixelType::UnsignedShort => components * 2,
+        PixelType::UnsignedInt | PixelType::Float => components * 4,
+        PixelType::UnsignedShort4444
+        | PixelType::UnsignedShort5551
+        | PixelType::UnsignedShort565 => 2,
+        PixelType::UnsignedInt24 => 4,
+    };
+    width as usize * height as usize * pixel
+}
+
+/// estimated size of a renderbuffer image
+pub(crate) fn renderbuffer_size(width: u16, height: u16, format: Buffers) -> usize {
+    let pixel = match format {
+        Buffers::StencilIndex | Buffers::StencilIndex8 => 1,
+        Buffers::Rgba4 | Buffers::Rgb5A1 | Buffers::Rgb565 | Buffers::DepthComponent16 => 2,
+        _ => 4,
+    };
+    width as usize * height as usize * pixel
+}
+
+/// the parameter giving the buffer bound to `kind`
+pub(crate) fn buffer_binding(kind: BufferKind) -> u32 {
+    match kind {
+        BufferKind::Array => 0x8894,
+        BufferKind::ElementArray => 0x8895,
+    }
+}
+
+/// the kind of the objects bound to a binding parameter
+fn binding_kind(binding: u32) -> ObjectKind {
+    match binding {
+        0x8894 | 0x8895 => ObjectKind::Buffer,
+        0x8069 | 0x8514 => ObjectKind::Texture,
+        _ => ObjectKind::Renderbuffer,
+    }
+}
+
+/// the parameter giving the texture bound to the texture kind of `target`
+pub(crate) fn texture_binding(target: TextureBindPoint) -> u32 {
+    match target {
+        TextureBindPoint::Texture2d => 0x8069,
+        _ => 0x8514,
+    }
+}
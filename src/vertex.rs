@@ -0,0 +1,329 @@
+//! Typed vertex buffers and the layout of their vertices.
+//!
+//! ```ignore
+//! #[derive(Clone, Copy)]
+//! #[repr(C)]
+//! struct Vertex {
+//!     position: [f32; 3],
+//!     color: [u8; 4],
+//! }
+//! unsafe impl Pod for Vertex {}
+//!
+//! let layout = VertexLayout::new()
+//!     .attribute("position", AttributeSize::Three, DataType::Float, false)
+//!     .attribute("color", AttributeSize::Four, DataType::U8, true);
+//! let mut vbo = TypedBuffer::<Vertex>::new(&gl, BufferKind::Array, layout)?;
+//! vbo.upload(&gl, &vertices, DrawMode::Static)?;
+//! vbo.apply_layout(&gl, &program);
+//! ```
+use std::marker::PhantomData;
+use std::mem;
+use std::slice;
+
+use backend::GlBackend;
+use common::*;
+use error::GlError;
+use glenum::*;
+use webgl::GLContext;
+
+/// Plain-old-data types, which can be uploaded as raw bytes.
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]` (or a primitive), without padding bytes, pointers or references.
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for f32 {}
+unsafe impl<T: Pod> Pod for [T; 1] {}
+unsafe impl<T: Pod> Pod for [T; 2] {}
+unsafe impl<T: Pod> Pod for [T; 3] {}
+unsafe impl<T: Pod> Pod for [T; 4] {}
+unsafe impl<T: Pod> Pod for [T; 9] {}
+unsafe impl<T: Pod> Pod for [T; 16] {}
+
+/// the bytes of a slice of plain-old-data
+pub fn as_bytes<T: Pod>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+/// A type usable as a vertex attribute, giving its component count and type.
+pub trait AttributeType: Pod {
+    const SIZE: AttributeSize;
+    const KIND: DataType;
+}
+
+macro_rules! attribute_type {
+    ($t:ty, $kind:ident) => {
+        impl AttributeType for $t {
+            const SIZE: AttributeSize = AttributeSize::One;
+            const KIND: DataType = DataType::$kind;
+        }
+        impl AttributeType for [$t; 1] {
+            const SIZE: AttributeSize = AttributeSize::One;
+            const KIND: DataType = DataType::$kind;
+        }
+        impl AttributeType for [$t; 2] {
+            const SIZE: AttributeSize = AttributeSize::Two;
+            const KIND: DataType = DataType::$kind;
+        }
+        impl AttributeType for [$t; 3] {
+            const SIZE: AttributeSize = AttributeSize::Three;
+            const KIND: DataType = DataType::$kind;
+        }
+        impl AttributeType for [$t; 4] {
+            const SIZE: AttributeSize = AttributeSize::Four;
+            const KIND: DataType = DataType::$kind;
+        }
+    };
+}
+
+attribute_type!(i8, I8);
+attribute_type!(u8, U8);
+attribute_type!(i16, I16);
+attribute_type!(u16, U16);
+attribute_type!(i32, I32);
+attribute_type!(u32, U32);
+attribute_type!(f32, Float);
+
+/// A vertex type with a known layout, usually implemented with `#[derive(Vertex)]`
+/// (`derive` feature) on a `#[repr(C)]` struct:
+///
+/// ```ignore
+/// #[derive(Clone, Copy, Vertex)]
+/// #[repr(C)]
+/// struct ColorVertex {
+///     position: [f32; 3],
+///     #[vertex(normalized)]
+///     color: [u8; 4],
+///     #[vertex(name = "a_uv")]
+///     uv: [f32; 2],
+/// }
+/// ```
+///
+/// Every field type must implement [`AttributeType`]. The derive also implements [`Pod`],
+/// failing to compile if the struct has padding bytes:
+///
+#[cfg_attr(feature = "derive", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// # use uni_gl::Vertex;
+/// #[derive(Clone, Copy, Vertex)]
+/// #[repr(C)]
+/// struct Padded {
+///     color: [u8; 3],
+///     position: [f32; 3],
+/// }
+/// ```
+pub trait Vertex: Pod {
+    /// the attributes of the vertex, with offsets and stride matching the struct
+    fn layout() -> VertexLayout;
+
+    /// point the attributes of `program` to the buffer currently bound to [`BufferKind::Array`],
+    /// see [`VertexLayout::apply`]
+    fn apply_layout<B: GlBackend>(
+        gl: &WebGLRenderingContext<B>,
+        program: &WebGLProgram<B::Program>,
+    ) {
+        Self::layout().apply(gl, program);
+    }
+}
+
+/// size in bytes of one component
+fn data_type_size(kind: DataType) -> u32 {
+    match kind {
+        DataType::I8 | DataType::U8 => 1,
+        DataType::I16 | DataType::U16 => 2,
+        DataType::I32 | DataType::U32 | DataType::Float => 4,
+    }
+}
+
+/// One attribute of a [`VertexLayout`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexAttribute {
+    /// name of the attribute in the vertex shader
+    pub name: String,
+    pub size: AttributeSize,
+    pub kind: DataType,
+    /// whether integer values are mapped to [0, 1] (unsigned) or [-1, 1] (signed)
+    pub normalized: bool,
+    /// offset of the attribute in a vertex, in bytes
+    pub offset: u32,
+}
+
+/// The layout of the interleaved vertices of a buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexLayout {
+    pub attributes: Vec<VertexAttribute>,
+    /// size of a vertex in bytes
+    pub stride: u32,
+}
+
+impl VertexLayout {
+    /// an empty layout
+    pub fn new() -> VertexLayout {
+        VertexLayout::default()
+    }
+
+    /// add an attribute right after the previous ones, growing the stride
+    pub fn attribute(
+        self,
+        name: &str,
+        size: AttributeSize,
+        kind: DataType,
+        normalized: bool,
+    ) -> VertexLayout {
+        let offset = self.stride;
+        self.attribute_at(name, size, kind, normalized, offset)
+    }
+
+    /// add an attribute at a given offset, growing the stride if needed
+    pub fn attribute_at(
+        mut self,
+        name: &str,
+        size: AttributeSize,
+        kind: DataType,
+        normalized: bool,
+        offset: u32,
+    ) -> VertexLayout {
+        let end = offset + size as u32 * data_type_size(kind);
+        self.stride = self.stride.max(end);
+        self.attributes.push(VertexAttribute {
+            name: name.into(),
+            size,
+            kind,
+            normalized,
+            offset,
+        });
+        self
+    }
+
+    /// set the stride, for vertices with trailing padding
+    pub fn with_stride(mut self, stride: u32) -> VertexLayout {
+        self.stride = stride;
+        self
+    }
+
+    /// point the attributes of `program` to the buffer currently bound to [`BufferKind::Array`].
+    ///
+    /// Attributes missing from the program, e.g. optimized out by the compiler, are skipped.
+    pub fn apply<B: GlBackend>(
+        &self,
+        gl: &WebGLRenderingContext<B>,
+        program: &WebGLProgram<B::Program>,
+    ) {
+        for a in &self.attributes {
+            if let Some(location) = gl.get_attrib_location(program, &a.name) {
+                gl.vertex_attrib_pointer(
+                    location,
+                    a.size,
+                    a.kind,
+                    a.normalized,
+                    self.stride,
+                    a.offset,
+                );
+                gl.enable_vertex_attrib_array(location);
+            }
+        }
+    }
+}
+
+/// A buffer holding values of type `T`, with the layout of its vertices.
+#[derive(Debug)]
+pub struct TypedBuffer<T: Pod, B: GlBackend = GLContext> {
+    pub buffer: WebGLBuffer<B::Buffer>,
+    pub kind: BufferKind,
+    pub layout: VertexLayout,
+    len: usize,
+    _data: PhantomData<T>,
+}
+
+impl<T: Pod, B: GlBackend> TypedBuffer<T, B> {
+    /// create an empty buffer
+    #[track_caller]
+    pub fn new(
+        gl: &WebGLRenderingContext<B>,
+        kind: BufferKind,
+        layout: VertexLayout,
+    ) -> Result<TypedBuffer<T, B>, GlError> {
+        Ok(TypedBuffer {
+            buffer: gl.create_buffer()?,
+            kind,
+            layout,
+            len: 0,
+            _data: PhantomData,
+        })
+    }
+
+    /// create an empty [`BufferKind::Array`] buffer with the layout of `T`
+    #[track_caller]
+    pub fn vertices(gl: &WebGLRenderingContext<B>) -> Result<TypedBuffer<T, B>, GlError>
+    where
+        T: Vertex,
+    {
+        TypedBuffer::new(gl, BufferKind::Array, T::layout())
+    }
+
+    /// bind the buffer and replace its content
+    pub fn upload(
+        &mut self,
+        gl: &WebGLRenderingContext<B>,
+        data: &[T],
+        draw: DrawMode,
+    ) -> Result<(), GlError> {
+        gl.bind_buffer(self.kind, &self.buffer);
+        gl.buffer_data(self.kind, as_bytes(data), draw)?;
+        self.len = data.len();
+        Ok(())
+    }
+
+    /// bind the buffer and replace the values starting at index `start`.
+    ///
+    /// Fails with `InvalidValue`, as `glBufferSubData` does, when the values do not fit in the buffer.
+    pub fn update(
+        &self,
+        gl: &WebGLRenderingContext<B>,
+        start: usize,
+        data: &[T],
+    ) -> Result<(), GlError> {
+        match start.checked_add(data.len()) {
+            Some(end) if end <= self.len => {}
+            _ => return Err(GlError::Gl(Error::InvalidValue)),
+        }
+        gl.bind_buffer(self.kind, &self.buffer);
+        gl.buffer_sub_data(
+            self.kind,
+            (start * mem::size_of::<T>()) as u32,
+            as_bytes(data),
+        );
+        Ok(())
+    }
+
+    pub fn bind(&self, gl: &WebGLRenderingContext<B>) {
+        gl.bind_buffer(self.kind, &self.buffer);
+    }
+
+    /// bind the buffer and point the attributes of `program` to it, see [`VertexLayout::apply`]
+    pub fn apply_layout(&self, gl: &WebGLRenderingContext<B>, program: &WebGLProgram<B::Program>) {
+        self.bind(gl);
+        self.layout.apply(gl, program);
+    }
+
+    /// number of values uploaded
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// delete the underlying buffer
+    pub fn delete(self, gl: &WebGLRenderingContext<B>) {
+        gl.delete_buffer(&self.buffer);
+    }
+}
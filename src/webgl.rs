@@ -1,19 +1,145 @@
+use backend::GlBackend;
+use capabilities::{Api, Capabilities};
 use common::*;
+use diagnostic::with_header;
+use error::{CallError, ErrorPolicy, ErrorState, GlError};
+use extensions::GlExtensions;
+use framebuffer::{FramebufferValidator, Image, ImageFormat};
 use glenum::*;
-use std::ops::Deref;
-
-//use js_sys::WebAssembly;
+use owned::DeletionQueue;
+use pipeline::{PipelineCache, PipelineState};
+use reflection::{cache_active_uniforms, UniformCache};
+use state::{ShadowState, Slot};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::mem;
+use std::panic::Location;
+use std::rc::Rc;
+use tracking::{
+    buffer_binding, image_size, renderbuffer_size, texture_binding, LiveObject, ObjectKind,
+    ObjectRef, ObjectTracker,
+};
+
+use js_sys::{Array, Float32Array, Int32Array, Reflect, Uint32Array};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{Element,WebGlProgram, WebGlRenderingContext, 
-WebGlShader,HtmlCanvasElement,WebGlBuffer};
+use web_sys::{
+    AngleInstancedArrays, Event, ExtDisjointTimerQuery, HtmlCanvasElement, OesVertexArrayObject,
+    WebGl2RenderingContext, WebGlActiveInfo, WebGlBuffer, WebGlFramebuffer, WebGlProgram,
+    WebGlQuery, WebGlRenderbuffer, WebGlRenderingContext, WebGlShader, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject, WebglDrawBuffers,
+};
+
+#[derive(Debug, Clone)]
+/// the underlying browser context, WebGL 2.0 when available, else WebGL 1.0
+pub enum Reference {
+    WebGl2(WebGl2RenderingContext),
+    WebGl1(WebGlRenderingContext),
+}
 
-pub type Reference = WebGlRenderingContext;
+/// call a method existing with the same signature on both WebGL 1.0 and 2.0 contexts
+macro_rules! gl_call {
+    ($ctx:expr, $method:ident($($arg:expr),* $(,)?)) => {
+        match $ctx.reference {
+            Reference::WebGl2(ref gl) => gl.$method($($arg),*),
+            Reference::WebGl1(ref gl) => gl.$method($($arg),*),
+        }
+    };
+}
 
 #[derive(Debug, Clone)]
+/// uni-gl internal WebGL context.
+///
+/// You shouldn't use this struct directly. Instead, call the [`GlBackend`] methods on [`WebGLRenderingContext`]
+/// as it automatically dereferences into a [`GLContext`].
 pub struct GLContext {
+    /// WebGL internal reference
     pub reference: Reference,
+    /// whether this context is a WebGL 2.0 context
     pub is_webgl2: bool,
+    /// always true, WebGL being based on OpenGL ES
+    pub is_gles: bool,
+    extensions: Rc<RefCell<Extensions>>,
+    errors: Rc<ErrorState>,
+    /// set by the webglcontextlost event, cleared by webglcontextrestored
+    lost: Rc<Cell<bool>>,
+    callbacks: Rc<RefCell<LossCallbacks>>,
+    _listeners: Rc<LossListeners>,
+    objects: Rc<ObjectTracker>,
+    uniforms: Rc<UniformCache<WebGlUniformLocation>>,
+    state: Rc<ShadowState>,
+    pipeline: Rc<PipelineCache>,
+    framebuffers: Rc<FramebufferValidator>,
+    capabilities: Rc<Capabilities>,
+    gl_extensions: Rc<GlExtensions>,
+}
+
+/// extensions needed on WebGL 1.0 for what is core in WebGL 2.0, fetched again on context restoration
+#[derive(Debug, Default)]
+struct Extensions {
+    /// OES_vertex_array_object, used for vertex array objects on WebGL 1.0
+    vao: Option<OesVertexArrayObject>,
+    /// WEBGL_draw_buffers, used for multiple render targets on WebGL 1.0
+    draw_buffers: Option<WebglDrawBuffers>,
+    /// ANGLE_instanced_arrays, used for instanced rendering on WebGL 1.0
+    instanced_arrays: Option<AngleInstancedArrays>,
+    /// EXT_disjoint_timer_query, used for timer queries on WebGL 1.0
+    timer_query: Option<ExtDisjointTimerQuery>,
+    /// extensions enabled for [`GlBackend::extensions`], enabled again on restoration
+    enabled: Vec<&'static str>,
+}
+
+impl Extensions {
+    fn new(context: &Reference) -> Extensions {
+        match *context {
+            Reference::WebGl2(_) => Extensions::default(),
+            Reference::WebGl1(_) => Extensions {
+                vao: get_extension(context, "OES_vertex_array_object"),
+                draw_buffers: get_extension(context, "WEBGL_draw_buffers"),
+                instanced_arrays: get_extension(context, "ANGLE_instanced_arrays"),
+                timer_query: get_extension(context, "EXT_disjoint_timer_query"),
+                enabled: Vec::new(),
+            },
+        }
+    }
+}
+
+/// application callbacks run on context loss and restoration
+#[derive(Default)]
+struct LossCallbacks {
+    lost: Vec<Box<dyn FnMut()>>,
+    restored: Vec<Box<dyn FnMut()>>,
+}
+
+impl fmt::Debug for LossCallbacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LossCallbacks")
+            .field("lost", &self.lost.len())
+            .field("restored", &self.restored.len())
+            .finish()
+    }
+}
+
+/// canvas event listeners, removed once the last clone of the context is dropped
+#[derive(Debug)]
+struct LossListeners {
+    canvas: HtmlCanvasElement,
+    lost: Closure<dyn FnMut(Event)>,
+    restored: Closure<dyn FnMut(Event)>,
+}
+
+impl Drop for LossListeners {
+    fn drop(&mut self) {
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextlost",
+            self.lost.as_ref().unchecked_ref(),
+        );
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextrestored",
+            self.restored.as_ref().unchecked_ref(),
+        );
+    }
 }
 
 pub type WebGLContext<'a> = &'a HtmlCanvasElement;
@@ -21,192 +147,616 @@ pub type WebGLContext<'a> = &'a HtmlCanvasElement;
 impl WebGLRenderingContext {
     pub fn new(canvas: &WebGLContext) -> WebGLRenderingContext {
         WebGLRenderingContext {
-            common: GLContext::new(&canvas),
+            common: GLContext::new(canvas),
+            deletions: DeletionQueue::default(),
         }
     }
 }
 
+/// property holding the tracking id of a WebGL object
+const KEY_PROPERTY: &str = "__uni_gl_id";
+
+thread_local! {
+    static KEY_NAME: JsValue = JsValue::from_str(KEY_PROPERTY);
+}
+
+/// the tracking id of a WebGL object, 0 if untracked
+fn object_key(object: &JsValue) -> u64 {
+    KEY_NAME
+        .with(|name| Reflect::get(object, name))
+        .ok()
+        .and_then(|key| key.as_f64())
+        .unwrap_or(0.0) as u64
+}
+
+/// the key of a WebGL object in the state cache, `None` if untracked
+fn state_key(object: &JsValue) -> Option<u64> {
+    match object_key(object) {
+        0 => None,
+        key => Some(key),
+    }
+}
+
+/// convert a `WebGLActiveInfo`, `None` for an unknown type
+fn active_info(info: WebGlActiveInfo) -> Option<WebGLActiveInfo> {
+    Some(WebGLActiveInfo {
+        name: info.name(),
+        size: info.size() as u32,
+        kind: UniformType::from_code(info.type_())?,
+    })
+}
+
+/// reported by the vertex array calls when neither WebGL 2.0 nor the extension is available
+const NO_VERTEX_ARRAYS: &str = "vertex array objects: WebGL 1.0 without OES_vertex_array_object";
+
+/// reported by the instancing calls when neither WebGL 2.0 nor the extension is available
+const NO_INSTANCING: &str = "instanced rendering: WebGL 1.0 without ANGLE_instanced_arrays";
+
+/// reported by the timer query calls when the extension of the WebGL version is not available
+const NO_TIMER_QUERIES: &str = "timer queries: no EXT_disjoint_timer_query(_webgl2)";
+
+/// fetch a WebGL extension object, if the browser supports it
+fn get_extension<T: JsCast>(context: &Reference, name: &str) -> Option<T> {
+    let ext = match *context {
+        Reference::WebGl2(ref gl) => gl.get_extension(name),
+        Reference::WebGl1(ref gl) => gl.get_extension(name),
+    };
+    ext.ok()
+        .and_then(|ext| ext)
+        .map(|ext| ext.unchecked_into::<T>())
+}
+
 impl GLContext {
     #[inline]
     pub fn log<T: Into<JsValue>>(&self, _msg: T) {
-        // js!{ console.log(@{msg.into()})};
-        web_sys::console::log_1(&_msg.into());
+        // web_sys::console::log_1(&_msg.into());
     }
 
     pub fn print<T: Into<JsValue>>(msg: T) {
         web_sys::console::log_1(&msg.into());
     }
 
-    pub fn new<'a>(canvas: &WebGLContext) -> GLContext {
-        let context = canvas
-           .get_context("webgl")
-           .unwrap()
-           .unwrap()
-           .dyn_into::<WebGlRenderingContext>().unwrap();
-        GLContext {
+    /// the reason a create_* call returned null
+    fn create_error(&self) -> GlError {
+        if self.is_context_lost() {
+            return GlError::ContextLost;
+        }
+        self.get_error().err().unwrap_or(GlError::ContextLost)
+    }
+
+    /// the error flag raised by a call that threw a javascript exception
+    fn thrown_error(&self) -> GlError {
+        self.get_error()
+            .err()
+            .unwrap_or(GlError::Gl(Error::InvalidOperation))
+    }
+
+    pub fn new(canvas: &WebGLContext) -> GLContext {
+        let webgl2 = canvas
+            .get_context("webgl2")
+            .ok()
+            .and_then(|context| context)
+            .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok());
+
+        let context = match webgl2 {
+            Some(context) => Reference::WebGl2(context),
+            None => Reference::WebGl1(
+                canvas
+                    .get_context("webgl")
+                    .unwrap()
+                    .expect("neither WebGL 2.0 nor WebGL 1.0 is available")
+                    .dyn_into::<WebGlRenderingContext>()
+                    .unwrap(),
+            ),
+        };
+
+        let extensions = Rc::new(RefCell::new(Extensions::new(&context)));
+        let lost = Rc::new(Cell::new(false));
+        let callbacks = Rc::new(RefCell::new(LossCallbacks::default()));
+        let objects = Rc::new(ObjectTracker::new(true));
+        let uniforms = Rc::new(UniformCache::default());
+        let state = Rc::new(ShadowState::default());
+        let pipeline = Rc::new(PipelineCache::default());
+        let framebuffers = Rc::new(FramebufferValidator::default());
+
+        let on_lost = {
+            let lost = lost.clone();
+            let callbacks = callbacks.clone();
+            Closure::wrap(Box::new(move |event: Event| {
+                // without this the browser never restores the context
+                event.prevent_default();
+                lost.set(true);
+                for f in callbacks.borrow_mut().lost.iter_mut() {
+                    f();
+                }
+            }) as Box<dyn FnMut(Event)>)
+        };
+        let on_restored = {
+            let context = context.clone();
+            let extensions = extensions.clone();
+            let lost = lost.clone();
+            let callbacks = callbacks.clone();
+            let objects = objects.clone();
+            let uniforms = uniforms.clone();
+            let state = state.clone();
+            let pipeline = pipeline.clone();
+            let framebuffers = framebuffers.clone();
+            Closure::wrap(Box::new(move |_: Event| {
+                let enabled = mem::take(&mut extensions.borrow_mut().enabled);
+                for name in &enabled {
+                    let _: Option<JsValue> = get_extension(&context, name);
+                }
+                *extensions.borrow_mut() = Extensions {
+                    enabled,
+                    ..Extensions::new(&context)
+                };
+                // objects of the lost context are gone, they are not leaks
+                objects.clear();
+                uniforms.clear_all();
+                state.invalidate();
+                pipeline.invalidate();
+                framebuffers.clear();
+                lost.set(false);
+                for f in callbacks.borrow_mut().restored.iter_mut() {
+                    f();
+                }
+            }) as Box<dyn FnMut(Event)>)
+        };
+        canvas
+            .add_event_listener_with_callback("webglcontextlost", on_lost.as_ref().unchecked_ref())
+            .unwrap();
+        canvas
+            .add_event_listener_with_callback(
+                "webglcontextrestored",
+                on_restored.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+
+        let mut context = GLContext {
+            is_webgl2: match context {
+                Reference::WebGl2(_) => true,
+                Reference::WebGl1(_) => false,
+            },
+            is_gles: true,
             reference: context,
-            is_webgl2: true,
+            extensions,
+            errors: Rc::new(ErrorState::default()),
+            lost,
+            callbacks,
+            objects,
+            uniforms,
+            state,
+            pipeline,
+            framebuffers,
+            _listeners: Rc::new(LossListeners {
+                canvas: (*canvas).clone(),
+                lost: on_lost,
+                restored: on_restored,
+            }),
+            capabilities: Rc::new(Capabilities::default()),
+            gl_extensions: Rc::new(GlExtensions::default()),
+        };
+        let extensions = context.supported_extensions();
+        context.capabilities = Rc::new(Capabilities::query(&context, Api::WebGl, extensions));
+        let gl_extensions = GlExtensions::detect(&context, |name| {
+            get_extension::<JsValue>(&context.reference, name).is_some()
+        });
+        context.extensions.borrow_mut().enabled = gl_extensions.names();
+        context.gl_extensions = Rc::new(gl_extensions);
+        context
+    }
+
+    /// names of the extensions supported by the context
+    fn supported_extensions(&self) -> HashSet<String> {
+        gl_call!(self, get_supported_extensions())
+            .map(|names| names.iter().filter_map(|name| name.as_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// register a callback run when the browser loses the context.
+    ///
+    /// Every object created with the context is invalid from there,
+    /// and calls do nothing until the context is restored.
+    /// Callbacks must not register other callbacks.
+    pub fn on_context_lost<F: FnMut() + 'static>(&self, f: F) {
+        self.callbacks.borrow_mut().lost.push(Box::new(f));
+    }
+
+    /// register a callback run when the browser restores a lost context.
+    ///
+    /// The context is then blank: buffers, textures, shaders, programs... must be created again,
+    /// typically by flagging the application to reload its resources on next frame.
+    /// Callbacks must not register other callbacks.
+    pub fn on_context_restored<F: FnMut() + 'static>(&self, f: F) {
+        self.callbacks.borrow_mut().restored.push(Box::new(f));
+    }
+
+    /// how calls not returning a [`Result`] deal with WebGL errors
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.errors.policy()
+    }
+
+    /// change how calls not returning a [`Result`] deal with WebGL errors
+    pub fn set_error_policy(&self, policy: ErrorPolicy) {
+        self.errors.set_policy(policy);
+    }
+
+    /// return and forget the errors kept under [`ErrorPolicy::Collect`], typically once per frame
+    pub fn take_errors(&self) -> Vec<CallError> {
+        self.errors.take_errors()
+    }
+
+    /// whether redundant state changes are skipped
+    pub fn state_cache(&self) -> bool {
+        self.state.enabled()
+    }
+
+    /// skip calls setting bindings or fixed-function state to their current value,
+    /// saving a crossing of the wasm/javascript boundary each.
+    ///
+    /// Off by default. Call [`GLContext::invalidate_state`] after any WebGL call made outside uni-gl.
+    pub fn set_state_cache(&self, enabled: bool) {
+        self.state.set_enabled(enabled);
+    }
+
+    /// forget the state known by the state cache and the last applied pipeline,
+    /// e.g. after external code made WebGL calls
+    pub fn invalidate_state(&self) {
+        self.state.invalidate();
+        self.objects.forget_bindings();
+        self.pipeline.invalidate();
+    }
+
+    /// whether draws check the bound framebuffer after its attachments change
+    pub fn framebuffer_validation(&self) -> bool {
+        self.framebuffers.enabled()
+    }
+
+    /// check the bound framebuffer on the first draw after its attachments change,
+    /// reporting an incomplete one as [`GlError::IncompleteFramebuffer`] through the error policy.
+    ///
+    /// Off by default. Framebuffers and images set up with WebGL calls made outside uni-gl are not known.
+    pub fn set_framebuffer_validation(&self, enabled: bool) {
+        self.framebuffers.set_enabled(enabled);
+    }
+
+    /// report the bound framebuffer if incomplete, see [`GLContext::set_framebuffer_validation`]
+    fn validate_framebuffer(&self, call: &str) {
+        if self.lost.get() {
+            return;
+        }
+        self.errors.check(call, || {
+            self.framebuffers.validate(&self.capabilities, || {
+                self.check_framebuffer_status(Buffers::Framebuffer)
+            })
+        });
+    }
+
+    /// start tracking a new object
+    fn track(&self, kind: ObjectKind, object: &JsValue, location: &'static Location<'static>) {
+        let key = self.objects.next_key();
+        let _ = Reflect::set(
+            object,
+            &JsValue::from_str(KEY_PROPERTY),
+            &JsValue::from_f64(key as f64),
+        );
+        self.objects.created(kind, key, location);
+    }
+
+    /// getParameter wrapper, `null` on error
+    /// or when a getter of `len` values queries a state with more, reporting `InvalidEnum`
+    fn get_parameter(&self, pname: Parameter, len: usize, call: &str) -> JsValue {
+        self.log(call);
+        let count = pname.value_count();
+        if count == 0 || count > len {
+            self.errors
+                .check(call, || Err(GlError::Gl(Error::InvalidEnum)));
+            return JsValue::NULL;
         }
+        let res = gl_call!(self, get_parameter(pname as u32)).unwrap_or(JsValue::NULL);
+        self.check_error(call);
+        res
     }
 
-    pub fn create_buffer(&self) -> WebGLBuffer<WebGlBuffer> {
-        self.log("create_buffer");
-        let k:&WebGlRenderingContext = &self.reference;
-        WebGLBuffer(k.create_buffer().unwrap())
+    fn check_error(&self, call: &str) {
+        // calls on a lost context are expected to fail
+        if !self.lost.get() {
+            self.errors.check(call, || self.get_error());
+        }
     }
 
-    pub fn delete_buffer(&self, buffer: &WebGLBuffer<WebGlBuffer>) {
-        self.log("delete_buffer");
-        let k:&WebGlRenderingContext = &self.reference;
-        let b2:&WebGlBuffer = buffer.deref();
-        k.delete_buffer(Some(b2));
+    /// an error unless instancing is available, so that draws fail before framebuffer validation
+    fn check_instancing(&self) -> Result<(), GlError> {
+        if !self.is_webgl2 && self.extensions.borrow().instanced_arrays.is_none() {
+            return Err(GlError::Unsupported(NO_INSTANCING));
+        }
+        Ok(())
     }
 
-    pub fn buffer_data(&self, kind: BufferKind, data: &mut [u8], draw: DrawMode) {
-        self.log("buffer_data");
-        let k:&WebGlRenderingContext = &self.reference;
-        k.buffer_data_with_u8_array(kind as u32, data,draw as u32 )
+    /// whether timer queries are available on WebGL 2.0, through EXT_disjoint_timer_query_webgl2
+    fn webgl2_timer_queries(&self) -> bool {
+        self.gl_extensions.disjoint_timer_query.is_some()
+    }
+
+    /// report a call this context cannot make through the error policy
+    fn unsupported(&self, call: &str, what: &'static str) {
+        self.errors.check(call, || Err(GlError::Unsupported(what)));
+    }
+}
+
+impl GlBackend for GLContext {
+    type Buffer = WebGlBuffer;
+    type Shader = WebGlShader;
+    type Program = WebGlProgram;
+    type Texture = WebGlTexture;
+    type VertexArray = WebGlVertexArrayObject;
+    type UniformLocation = WebGlUniformLocation;
+    type FrameBuffer = WebGlFramebuffer;
+    type Renderbuffer = WebGlRenderbuffer;
+    type Query = WebGlQuery;
+
+    fn get_error(&self) -> Result<(), GlError> {
+        match GlError::from_code(gl_call!(self, get_error())) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn is_context_lost(&self) -> bool {
+        gl_call!(self, is_context_lost())
     }
 
-    pub fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<WebGlBuffer>) {
+    fn set_object_name<'a, O: Into<ObjectRef<'a, Self>>>(&self, object: O, name: &str) {
+        let object = object.into();
+        let key = match object {
+            ObjectRef::Buffer(o) => object_key(&o.0),
+            ObjectRef::Shader(o) => object_key(&o.0),
+            ObjectRef::Program(o) => object_key(&o.0),
+            ObjectRef::Texture(o) => object_key(&o.0),
+            ObjectRef::VertexArray(o) => object_key(&o.0),
+            ObjectRef::FrameBuffer(o) => object_key(&o.0),
+            ObjectRef::Renderbuffer(o) => object_key(&o.0),
+        };
+        self.objects.set_name(object.kind(), key, name);
+    }
+
+    fn live_objects(&self) -> Vec<LiveObject> {
+        self.objects.live()
+    }
+
+    fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    fn get_supported_extensions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.supported_extensions().into_iter().collect();
+        names.sort();
+        names
+    }
+
+    fn extensions(&self) -> &GlExtensions {
+        &self.gl_extensions
+    }
+
+    fn create_buffer(&self) -> Result<WebGLBuffer<WebGlBuffer>, GlError> {
+        self.log("create_buffer");
+        let object = gl_call!(self, create_buffer())
+            .map(WebGLBuffer)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::Buffer, &object.0, Location::caller());
+        Ok(object)
+    }
+
+    fn delete_buffer(&self, buffer: &WebGLBuffer<WebGlBuffer>) {
+        self.log("delete_buffer");
+        gl_call!(self, delete_buffer(Some(&buffer.0)));
+        self.check_error("delete_buffer");
+        let key = object_key(&buffer.0);
+        self.objects.deleted(ObjectKind::Buffer, key);
+        self.state.deleted(ObjectKind::Buffer, key);
+    }
+
+    fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<WebGlBuffer>) {
+        self.objects
+            .bind(buffer_binding(kind), object_key(&buffer.0));
+        if !self
+            .state
+            .bind(Slot::Buffer(kind as u32), state_key(&buffer.0))
+        {
+            return;
+        }
         self.log("bind_buffer");
-        let k:&WebGlRenderingContext = &self.reference;
-        k.bind_buffer(kind as u32,Some(buffer.deref()));
+        gl_call!(self, bind_buffer(kind as u32, Some(&buffer.0)));
+        self.check_error("bind_buffer");
     }
 
-    pub fn unbind_buffer(&self, kind: BufferKind) {
+    fn buffer_data(&self, kind: BufferKind, data: &[u8], draw: DrawMode) -> Result<(), GlError> {
+        self.log("buffer_data");
+        gl_call!(
+            self,
+            buffer_data_with_u8_array(kind as u32, data, draw as u32)
+        );
+        self.get_error()?;
+        self.objects
+            .set_buffer_size(self.objects.bound(buffer_binding(kind)), data.len());
+        Ok(())
+    }
+
+    fn buffer_sub_data(&self, kind: BufferKind, offset: u32, data: &[u8]) {
+        self.log("buffer_sub_data");
+        gl_call!(
+            self,
+            buffer_sub_data_with_i32_and_u8_array(kind as u32, offset as i32, data)
+        );
+        self.check_error("buffer_sub_data");
+    }
+
+    fn unbind_buffer(&self, kind: BufferKind) {
+        self.objects.bind(buffer_binding(kind), 0);
+        if !self.state.bind(Slot::Buffer(kind as u32), Some(0)) {
+            return;
+        }
         self.log("unbind_buffer");
-        let k:&WebGlRenderingContext = &self.reference;
-        k.bind_buffer(kind as u32,None);
+        gl_call!(self, bind_buffer(kind as u32, None));
+        self.check_error("unbind_buffer");
     }
 
-    pub fn create_shader(&self, kind: ShaderKind) -> WebGLShader<WebGlShader> {
+    fn create_shader(&self, kind: ShaderKind) -> Result<WebGLShader<WebGlShader>, GlError> {
         self.log("create_shader");
-        let k:&WebGlRenderingContext = &self.reference;
-        let value = k.create_shader(kind as u32).unwrap();
-        WebGLShader(value)
+        let object = gl_call!(self, create_shader(kind as u32))
+            .map(WebGLShader)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::Shader, &object.0, Location::caller());
+        Ok(object)
     }
-/*
-    pub fn shader_source(&self, shader: &WebGLShader, code: &str) {
+
+    fn shader_source(&self, shader: &WebGLShader<WebGlShader>, source: &str) {
         self.log("shader_source");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            var shader = Module.gl.get(@{shader.deref()});
-            ctx.shaderSource(shader,@{ code })
-        };
+        gl_call!(self, shader_source(&shader.0, source));
+        self.check_error("shader_source");
+        self.objects.set_header_lines(object_key(&shader.0), 0);
+    }
+
+    fn shader_source_with_header(
+        &self,
+        shader: &WebGLShader<WebGlShader>,
+        header: &str,
+        source: &str,
+    ) {
+        let (source, header_lines) = with_header(header, source);
+        self.shader_source(shader, &source);
+        self.objects
+            .set_header_lines(object_key(&shader.0), header_lines);
     }
 
-    pub fn compile_shader(&self, shader: &WebGLShader) {
+    fn compile_shader(&self, shader: &WebGLShader<WebGlShader>) -> Result<(), GlError> {
         self.log("compile_shader");
-        js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var shader = Module.gl.get(@{shader.deref()});
-            ctx.compileShader(shader);
-
-            var compiled = ctx.getShaderParameter(shader, 0x8B81);
-            if (!compiled ) {
-                console.log("ERROR in shader compilation:");
-                console.log( ctx.getShaderInfoLog(shader));
-            }
-        };
+        gl_call!(self, compile_shader(&shader.0));
+
+        let compiled = gl_call!(
+            self,
+            get_shader_parameter(&shader.0, ShaderParameter::CompileStatus as u32)
+        )
+        .as_bool()
+        .unwrap_or(false);
+        if !compiled {
+            return match gl_call!(self, get_shader_info_log(&shader.0)) {
+                Some(log) => Err(GlError::ShaderCompile {
+                    log,
+                    header_lines: self.objects.header_lines(object_key(&shader.0)),
+                }),
+                None => Err(self.create_error()),
+            };
+        }
+        Ok(())
     }
 
-    pub fn create_program(&self) -> WebGLProgram {
+    fn create_program(&self) -> Result<WebGLProgram<WebGlProgram>, GlError> {
         self.log("create_program");
-        let value = js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = {};
-            h.prog = ctx.createProgram();
-            h.uniform_names = {};
-            return Module.gl.add(h);
-        };
-        WebGLProgram(value.try_into().unwrap())
+        let object = gl_call!(self, create_program())
+            .map(WebGLProgram)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::Program, &object.0, Location::caller());
+        Ok(object)
     }
 
-    pub fn link_program(&self, program: &WebGLProgram) {
+    fn link_program(&self, program: &WebGLProgram<WebGlProgram>) -> Result<(), GlError> {
         self.log("link_program");
-        js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-            ctx.linkProgram(h.prog);
-            var result=ctx.getProgramParameter(h.prog, ctx.LINK_STATUS);
-            if (! result) {
-                console.log("ERROR while linking program :");
-                console.log(ctx.getProgramInfoLog(h.prog));
-            }
-        };
+        self.uniforms.clear(object_key(&program.0));
+        gl_call!(self, link_program(&program.0));
+
+        let linked = gl_call!(
+            self,
+            get_program_parameter(&program.0, ShaderParameter::LinkStatus as u32)
+        )
+        .as_bool()
+        .unwrap_or(false);
+        if !linked {
+            return match gl_call!(self, get_program_info_log(&program.0)) {
+                Some(log) => Err(GlError::ProgramLink { log }),
+                None => Err(self.create_error()),
+            };
+        }
+        cache_active_uniforms(self, program);
+        Ok(())
     }
 
-    pub fn use_program(&self, program: &WebGLProgram) {
+    fn delete_shader(&self, shader: &WebGLShader<WebGlShader>) {
+        self.log("delete_shader");
+        gl_call!(self, delete_shader(Some(&shader.0)));
+        self.check_error("delete_shader");
+        self.objects
+            .deleted(ObjectKind::Shader, object_key(&shader.0));
+    }
+
+    fn delete_program(&self, program: &WebGLProgram<WebGlProgram>) {
+        self.log("delete_program");
+        gl_call!(self, delete_program(Some(&program.0)));
+        self.check_error("delete_program");
+        let key = object_key(&program.0);
+        self.objects.deleted(ObjectKind::Program, key);
+        self.state.deleted(ObjectKind::Program, key);
+        self.uniforms.clear(key);
+    }
+
+    fn use_program(&self, program: &WebGLProgram<WebGlProgram>) {
+        if !self.state.bind(Slot::Program, state_key(&program.0)) {
+            return;
+        }
         self.log("use_program");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-            ctx.useProgram(h.prog)
-        };
+        gl_call!(self, use_program(Some(&program.0)));
+        self.check_error("use_program");
     }
 
-    pub fn attach_shader(&self, program: &WebGLProgram, shader: &WebGLShader) {
+    fn attach_shader(
+        &self,
+        program: &WebGLProgram<WebGlProgram>,
+        shader: &WebGLShader<WebGlShader>,
+    ) {
         self.log("attach_shader");
-        js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-            var shader = Module.gl.get(@{shader.deref()});
-            ctx.attachShader(h.prog, shader)
-        };
+        gl_call!(self, attach_shader(&program.0, &shader.0));
+        self.check_error("attach_shader");
     }
 
-    pub fn bind_attrib_location(&self, program: &WebGLProgram, name: &str, loc: u32) {
+    fn bind_attrib_location(&self, program: &WebGLProgram<WebGlProgram>, name: &str, loc: u32) {
         self.log("bind_attrib_location");
-        js! {
-            @(no_return)
-
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-            ctx.bindAttribLocation(h.prog,@{loc}, @{name});
-        };
+        gl_call!(self, bind_attrib_location(&program.0, loc, name));
+        self.check_error("bind_attrib_location");
     }
 
-    pub fn get_attrib_location(&self, program: &WebGLProgram, name: &str) -> Option<u32> {
+    fn get_attrib_location(&self, program: &WebGLProgram<WebGlProgram>, name: &str) -> Option<u32> {
         self.log("get_attrib_location");
-        let value = js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-            var r = ctx.getAttribLocation(h.prog,@{name});
-            return r >= 0 ? r : null;
-        };
-        value.try_into().ok() as _
+        let location = gl_call!(self, get_attrib_location(&program.0, name));
+        self.check_error("get_attrib_location");
+        if location < 0 {
+            return None;
+        }
+        Some(location as u32)
     }
 
-    pub fn get_uniform_location(
+    fn get_uniform_location(
         &self,
-        program: &WebGLProgram,
+        program: &WebGLProgram<WebGlProgram>,
         name: &str,
-    ) -> Option<WebGLUniformLocation> {
+    ) -> Option<WebGLUniformLocation<WebGlUniformLocation>> {
         self.log("get_uniform_location");
-        let value = js! {
-            var ctx = Module.gl.get(@{&self.reference});
-            var h = Module.gl.get(@{program.deref()});
-
-            var name = @{name};
-            var uniform = h.uniform_names[name];
-            if(name in h.uniform_names) return h.uniform_names[name];
-
-            uniform = Module.gl.add(ctx.getUniformLocation(h.prog,name));
-            h.uniform_names[name] = uniform;
-
-            return uniform;
-        };
-
-        value.try_into().ok().map(|uni| WebGLUniformLocation {
-            reference: uni,
-            name: name.into(),
-        })
+        // programs not created by this context have no key to cache their locations with
+        let key = object_key(&program.0);
+        if let Some(location) = self.uniforms.get(key, name) {
+            return location;
+        }
+        let location = gl_call!(self, get_uniform_location(&program.0, name));
+        self.check_error("get_uniform_location");
+        if key == 0 || self.lost.get() {
+            return location.map(|location| WebGLUniformLocation {
+                reference: location,
+                name: name.into(),
+            });
+        }
+        self.uniforms.insert(key, name, location)
     }
 
-    pub fn vertex_attrib_pointer(
+    fn vertex_attrib_pointer(
         &self,
         location: u32,
         size: AttributeSize,
@@ -215,119 +765,176 @@ impl GLContext {
         stride: u32,
         offset: u32,
     ) {
-        self.log("vertex_attribute_pointer");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-
-            ctx.vertexAttribPointer(@{location},@{size as u16},@{kind as i32},@{normalized},@{stride},@{offset});
-        };
+        self.log("vertex_attrib_pointer");
+        gl_call!(
+            self,
+            vertex_attrib_pointer_with_i32(
+                location,
+                size as i32,
+                kind as u32,
+                normalized,
+                stride as i32,
+                offset as i32,
+            )
+        );
+        self.check_error("vertex_attrib_pointer");
+    }
+
+    fn enable_vertex_attrib_array(&self, location: u32) {
+        self.log("enable_vertex_attrib_array");
+        gl_call!(self, enable_vertex_attrib_array(location));
+        self.check_error("enable_vertex_attrib_array");
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32) -> Result<(), GlError> {
+        self.log("vertex_attrib_divisor");
+        match (&self.reference, &self.extensions.borrow().instanced_arrays) {
+            (Reference::WebGl2(gl), _) => gl.vertex_attrib_divisor(location, divisor),
+            (_, Some(ext)) => ext.vertex_attrib_divisor_angle(location, divisor),
+            _ => return Err(GlError::Unsupported(NO_INSTANCING)),
+        }
+        self.get_error()
     }
 
-    pub fn enable_vertex_attrib_array(&self, location: u32) {
-        self.log("enabled_vertex_attrib_array");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.enableVertexAttribArray(@{location})
-        };
-    }
-*/
-    pub fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.clear_color, [r, g, b, a]) {
+            return;
+        }
         self.log("clear_color");
-        let k:&WebGlRenderingContext = &self.reference;
-        k.clear_color(r,g,b,a);
+        gl_call!(self, clear_color(r, g, b, a));
+        self.check_error("clear_color");
     }
-/*
-    pub fn enable(&self, flag: i32) {
+
+    fn enable(&self, flag: i32) {
+        if !self.state.flag(flag, true) {
+            return;
+        }
         self.log("enable");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.enable(@{flag as i32});
-        };
+        gl_call!(self, enable(flag as u32));
+        self.check_error("enable");
     }
 
-    pub fn disable(&self, flag: i32) {
+    fn disable(&self, flag: i32) {
+        if !self.state.flag(flag, false) {
+            return;
+        }
         self.log("disable");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.disable(@{flag as i32});
-        };
+        gl_call!(self, disable(flag as u32));
+        self.check_error("disable");
     }
 
-    pub fn cull_face(&self, flag: Culling) {
+    fn cull_face(&self, flag: Culling) {
+        if !self.state.set(|s| &mut s.cull_face, flag) {
+            return;
+        }
         self.log("cull_face");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.cullFace(@{flag as i32});
-        };
+        gl_call!(self, cull_face(flag as u32));
+        self.check_error("cull_face");
     }
 
-    pub fn depth_mask(&self, b: bool) {
-        self.log("depth_mask");
-
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.depthMask(@{b});
+    fn depth_mask(&self, b: bool) {
+        if !self.state.set(|s| &mut s.depth_mask, b) {
+            return;
         }
+        self.log("depth_mask");
+        gl_call!(self, depth_mask(b));
+        self.check_error("depth_mask");
     }
 
-    pub fn depth_func(&self, d: DepthTest) {
-        self.log("depth_func");
-
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.depthFunc(@{d as i32});
+    fn depth_func(&self, d: DepthTest) {
+        if !self.state.set(|s| &mut s.depth_func, d) {
+            return;
         }
+        self.log("depth_func");
+        gl_call!(self, depth_func(d as u32));
+        self.check_error("depth_func");
     }
 
-    pub fn clear_depth(&self, value: f32) {
-        self.log("clear_depth");
-
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.clearDepth(@{value});
+    fn clear_depth(&self, value: f32) {
+        if !self.state.set(|s| &mut s.clear_depth, value) {
+            return;
         }
+        self.log("clear_depth");
+        gl_call!(self, clear_depth(value));
+        self.check_error("clear_depth");
     }
-*/
-    pub fn clear(&self, bit: BufferBit) {
+
+    fn clear(&self, bit: BufferBit) {
         self.log("clear");
-        let k:&WebGlRenderingContext = &self.reference;
-        k.clear(bit as u32);
+        gl_call!(self, clear(bit as u32));
+        self.check_error("clear");
     }
-/*
-    pub fn viewport(&self, x: i32, y: i32, width: u32, height: u32) {
+
+    fn viewport(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.viewport, (x, y, width, height)) {
+            return;
+        }
         self.log("viewport");
-        let params = js! { return [@{x},@{y},@{width},@{height}] };
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            var p = @{params};
-            ctx.viewport(p[0],p[1],p[2],p[3]);
-        };
+        gl_call!(self, viewport(x, y, width as i32, height as i32));
+        self.check_error("viewport");
     }
 
-    pub fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32) {
-        self.log("draw_elemnts");
-        self.reference.draw_elements_with_i32(mode as i32, count as i32, kind as i32, offset as i32)
+    fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32) {
+        self.log("draw_elements");
+        self.validate_framebuffer("draw_elements");
+        gl_call!(
+            self,
+            draw_elements_with_i32(mode as u32, count as i32, kind as u32, offset as i32)
+        );
+        self.check_error("draw_elements");
     }
 
-    pub fn draw_arrays(&self, mode: Primitives, count: usize) {
+    fn draw_arrays(&self, mode: Primitives, count: usize) {
         self.log("draw_arrays");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.drawArrays(@{mode as i32},0,@{count as i32});
-        };
+        self.validate_framebuffer("draw_arrays");
+        gl_call!(self, draw_arrays(mode as u32, 0, count as i32));
+        self.check_error("draw_arrays");
+    }
+
+    fn draw_elements_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.log("draw_elements_instanced");
+        let (mode, count, kind) = (mode as u32, count as i32, kind as u32);
+        let (offset, instances) = (offset as i32, instances as i32);
+        self.check_instancing()?;
+        self.validate_framebuffer("draw_elements_instanced");
+        match (&self.reference, &self.extensions.borrow().instanced_arrays) {
+            (Reference::WebGl2(gl), _) => {
+                gl.draw_elements_instanced_with_i32(mode, count, kind, offset, instances)
+            }
+            (_, Some(ext)) => {
+                ext.draw_elements_instanced_angle_with_i32(mode, count, kind, offset, instances)
+            }
+            _ => return Err(GlError::Unsupported(NO_INSTANCING)),
+        }
+        self.get_error()
     }
 
-    pub fn read_pixels(
+    fn draw_arrays_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.log("draw_arrays_instanced");
+        let (mode, count, instances) = (mode as u32, count as i32, instances as i32);
+        self.check_instancing()?;
+        self.validate_framebuffer("draw_arrays_instanced");
+        match (&self.reference, &self.extensions.borrow().instanced_arrays) {
+            (Reference::WebGl2(gl), _) => gl.draw_arrays_instanced(mode, 0, count, instances),
+            (_, Some(ext)) => ext.draw_arrays_instanced_angle(mode, 0, count, instances),
+            _ => return Err(GlError::Unsupported(NO_INSTANCING)),
+        }
+        self.get_error()
+    }
+
+    fn read_pixels(
         &self,
         x: u32,
         y: u32,
@@ -336,61 +943,31 @@ impl GLContext {
         format: PixelFormat,
         kind: PixelType,
         data: &mut [u8],
-    ) {
+    ) -> Result<(), GlError> {
         self.log("read_pixels");
-        let data_len = data.len();
-
-        let pixels = js!{
-            var ctx = Module.gl.get(@{&self.reference});
-
-            var pixelValues = new Uint8Array(@{data_len as u32});
-            ctx.readPixels(
-                @{x as i32},
-                @{y as i32},
-                @{width as i32},
-                @{height as i32},
-                @{format as u32},
-                @{kind as u32},
-                pixelValues,
-            );
-
-            return pixelValues;
-        };
-
-        let pixels: TypedArray<u8> = pixels.try_into().unwrap();
-        let pixels_arr: Vec<_> = pixels.into();
-
-        data.clone_from_slice(&pixels_arr);
-    }
-
-    pub fn pixel_storei(&self, storage: PixelStorageMode, value: i32) {
+        gl_call!(
+            self,
+            read_pixels_with_opt_u8_array(
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format as u32,
+                kind as u32,
+                Some(data),
+            )
+        )
+        .map_err(|_| self.thrown_error())?;
+        self.get_error()
+    }
+
+    fn pixel_storei(&self, storage: PixelStorageMode, value: i32) {
         self.log("pixel_storei");
-        js!{
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.pixelStorei(@{storage as i32},@{value});
-        }
+        gl_call!(self, pixel_storei(storage as u32, value));
+        self.check_error("pixel_storei");
     }
 
-    pub fn generate_mipmap(&self) {
-        self.log("generate_mipmap");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.generateMipmap(ctx.TEXTURE_2D);
-        }
-    }
-
-    pub fn generate_mipmap_cube(&self) {
-        self.log("generate_mipmap_cube");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.generateMipmap(ctx.TEXTURE_CUBE_MAP);
-        }
-    }
-
-    pub fn tex_image2d(
+    fn tex_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -399,46 +976,54 @@ impl GLContext {
         format: PixelFormat,
         kind: PixelType,
         pixels: &[u8],
-    ) {
-        self.log("tex_img2d");
-        let params1 = js! { return [@{target as u32},@{level as u32},@{format as u32}] };
-        let params2 =
-            js! { return [@{width as u32},@{height as u32},@{format as u32},@{kind as u32}] };
+    ) -> Result<(), GlError> {
+        self.log("tex_image2d");
 
-        // TODO: It is a strange bug !!!
         // According https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texImage2D
         // the format arg should be equal to internal format arg
         // however, only DEPTH_COMPONENT16 works but not DEPTH_COMPONENT
-
-        let is_depth = match format {
-            PixelFormat::DepthComponent => true,
-            _ => false,
-        };
-
-        if pixels.len() > 0 {
-            js!{
-                var p = @{params1}.concat(@{params2});
-                var ctx = Module.gl.get(@{&self.reference});
-
-                ctx.texImage2D(p[0],p[1], p[2] ,p[3],p[4],0,p[2],p[6],@{TypedArray::from(pixels)});
-            };
+        let (internal_format, pixels) = if !pixels.is_empty() {
+            (format as i32, Some(pixels))
         } else {
-            js!{
-                var p = @{params1}.concat(@{params2});
-                var ctx = Module.gl.get(@{&self.reference});
-
-                var internal_fmt =  @{format as u32};
-                var fmt = internal_fmt;
-                if ( @{is_depth}) {
-                    internal_fmt =  ctx.DEPTH_COMPONENT16;
-                }
-
-                ctx.texImage2D(p[0],p[1], internal_fmt ,p[3],p[4],0, fmt ,p[6],null);
-            };
-        }
-    }
+            match format {
+                PixelFormat::DepthComponent => (Buffers::DepthComponent16 as i32, None),
+                _ => (format as i32, None),
+            }
+        };
 
-    pub fn tex_sub_image2d(
+        gl_call!(
+            self,
+            tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                target as u32,
+                level as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+                0,
+                format as u32,
+                kind as u32,
+                pixels,
+            )
+        )
+        .map_err(|_| self.thrown_error())?;
+        self.get_error()?;
+        let texture = self.objects.bound(texture_binding(target));
+        self.objects.set_image_size(
+            texture,
+            target,
+            level,
+            image_size(width, height, format, kind),
+        );
+        self.framebuffers.image(
+            Image::Texture(texture, target as u32, level),
+            ImageFormat::of_pixels(format),
+            width,
+            height,
+        );
+        Ok(())
+    }
+
+    fn tex_sub_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -450,19 +1035,30 @@ impl GLContext {
         kind: PixelType,
         pixels: &[u8],
     ) {
-        self.log("sub_tex_img2d");
-        let params1 =
-            js! { return [@{target as u32},@{level as u32},@{xoffset as u32},@{yoffset as u32}] };
-        let params2 =
-            js! { return [@{width as u32},@{height as u32},@{format as u32},@{kind as u32}] };
-        js!{
-            var p = @{params1}.concat(@{params2});
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.texSubImage2D(p[0],p[1],p[2],p[3],p[4],p[5],p[6],p[7],@{TypedArray::from(pixels)});
-        };
+        self.log("tex_sub_image2d");
+        let result = gl_call!(
+            self,
+            tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+                target as u32,
+                level as i32,
+                xoffset as i32,
+                yoffset as i32,
+                width as i32,
+                height as i32,
+                format as u32,
+                kind as u32,
+                Some(pixels),
+            )
+        );
+        if !self.lost.get() {
+            self.errors.check("tex_sub_image2d", || {
+                result.map_err(|_| self.thrown_error())?;
+                self.get_error()
+            });
+        }
     }
 
-    pub fn compressed_tex_image2d(
+    fn compressed_tex_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -470,416 +1066,830 @@ impl GLContext {
         width: u16,
         height: u16,
         data: &[u8],
-    ) {
-        self.log("compressed_tex_img2d");
-        let params =
-            js! { return [@{target as u32},@{level as u32},@{width as u32},@{height as u32}] };
+    ) -> Result<(), GlError> {
+        self.log("compressed_tex_image2d");
+
         // for some reason this needs to be called otherwise invalid format error, extension initialization?
-        js! {
-            var ctx = Module.gl.get(@{&self.reference});
-
-            // for some reason this needs to be called otherwise invalid format error, extension initialization?
-            (ctx.getExtension("WEBGL_compressed_texture_s3tc") ||
-                ctx.getExtension("MOZ_WEBGL_compressed_texture_s3tc") ||
-                ctx.getExtension("WEBKIT_WEBGL_compressed_texture_s3tc"));
-
-            var p = @{params};
-
-            ctx.compressedTexImage2D(
-                p[0],
-                p[1],
-                @{compression as u16},
-                p[2],
-                p[3],
+        let _: Option<JsValue> = get_extension(&self.reference, "WEBGL_compressed_texture_s3tc")
+            .or_else(|| get_extension(&self.reference, "MOZ_WEBGL_compressed_texture_s3tc"))
+            .or_else(|| get_extension(&self.reference, "WEBKIT_WEBGL_compressed_texture_s3tc"));
+
+        gl_call!(
+            self,
+            compressed_tex_image_2d_with_u8_array(
+                target as u32,
+                level as i32,
+                compression as u32,
+                width as i32,
+                height as i32,
                 0,
-                @{TypedArray::from(data)}
-            );
-
-            return 0;
+                data,
+            )
+        );
+        self.get_error()?;
+        let texture = self.objects.bound(texture_binding(target));
+        self.objects
+            .set_image_size(texture, target, level, data.len());
+        self.framebuffers.image(
+            Image::Texture(texture, target as u32, level),
+            ImageFormat::Color,
+            width,
+            height,
+        );
+        Ok(())
+    }
+
+    fn get_program_parameter(
+        &self,
+        program: &WebGLProgram<WebGlProgram>,
+        pname: ShaderParameter,
+    ) -> i32 {
+        self.log("get_program_parameter");
+        let res = gl_call!(self, get_program_parameter(&program.0, pname as u32));
+        self.check_error("get_program_parameter");
+        match res.as_bool() {
+            Some(b) => b as i32,
+            None => res.as_f64().unwrap_or(0.0) as i32,
         }
-        self.log("compressed_tex_img2d end");
     }
 
-    ///
-    pub fn create_texture(&self) -> WebGLTexture {
-        self.log("create_tex");
-        let handle = js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            return Module.gl.add(ctx.createTexture()) ;
-        };
-        WebGLTexture(handle.try_into().unwrap())
+    fn get_active_uniform(
+        &self,
+        program: &WebGLProgram<WebGlProgram>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.log("get_active_uniform");
+        let info = gl_call!(self, get_active_uniform(&program.0, index));
+        self.check_error("get_active_uniform");
+        info.and_then(active_info)
     }
 
-    pub fn delete_texture(&self, texture: &WebGLTexture) {
-        self.log("delete_tex");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            var tex = Module.gl.get(@{&texture.0});
-            ctx.deleteTexture(tex);
-            Module.gl.remove(tex);
+    fn get_active_attrib(
+        &self,
+        program: &WebGLProgram<WebGlProgram>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.log("get_active_attrib");
+        let info = gl_call!(self, get_active_attrib(&program.0, index));
+        self.check_error("get_active_attrib");
+        info.and_then(active_info)
+    }
+
+    fn get_parameter_i32(&self, pname: Parameter) -> i32 {
+        let res = self.get_parameter(pname, 1, "get_parameter_i32");
+        match res.as_f64() {
+            Some(v) => v as i32,
+            None if res.is_object() => object_key(&res) as i32,
+            None => res.as_bool().unwrap_or(false) as i32,
         }
     }
 
-    pub fn active_texture(&self, active: u32) {
-        self.log("active_texture");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.activeTexture(ctx.TEXTURE0 + @{active})
-        }
+    fn get_parameter_f32(&self, pname: Parameter) -> f32 {
+        self.get_parameter(pname, 1, "get_parameter_f32")
+            .as_f64()
+            .unwrap_or(0.0) as f32
     }
 
-    pub fn bind_texture(&self, texture: &WebGLTexture) {
-        self.log("bind_tex");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            var tex = Module.gl.get(@{&texture.0});
-            ctx.bindTexture(@{TextureKind::Texture2d as u32 }, tex)
-        }
+    fn get_parameter_bool(&self, pname: Parameter) -> bool {
+        let res = self.get_parameter(pname, 1, "get_parameter_bool");
+        res.as_bool()
+            .unwrap_or_else(|| res.as_f64().is_some_and(|v| v != 0.0))
     }
 
-    pub fn unbind_texture(&self) {
-        self.log("unbind_tex");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.bindTexture(@{TextureKind::Texture2d as u32 },null)
+    fn get_parameter_i32x4(&self, pname: Parameter) -> [i32; 4] {
+        let res = self.get_parameter(pname, 4, "get_parameter_i32x4");
+        let mut values = [0; 4];
+        if res.is_object() {
+            for (value, v) in values.iter_mut().zip(Int32Array::new(&res).to_vec()) {
+                *value = v;
+            }
         }
+        values
     }
 
-    pub fn bind_texture_cube(&self, texture: &WebGLTexture) {
-        self.log("bind_tex_cube");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            var tex = Module.gl.get(@{&texture.0});
-            ctx.bindTexture(@{TextureKind::TextureCubeMap as u32 }, tex)
+    fn get_parameter_f32x4(&self, pname: Parameter) -> [f32; 4] {
+        let res = self.get_parameter(pname, 4, "get_parameter_f32x4");
+        let mut values = [0.0; 4];
+        if res.is_object() {
+            for (value, v) in values.iter_mut().zip(Float32Array::new(&res).to_vec()) {
+                *value = v;
+            }
         }
+        values
     }
 
-    pub fn unbind_texture_cube(&self) {
-        self.log("unbind_tex_cube");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.bindTexture(@{TextureKind::TextureCubeMap as u32 },null)
-        }
+    fn get_parameter_string(&self, pname: Parameter) -> String {
+        self.get_parameter(pname, 1, "get_parameter_string")
+            .as_string()
+            .unwrap_or_default()
     }
 
-    pub fn blend_equation(&self, eq: BlendEquation) {
-        self.log("blend_equation");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.blendEquation(@{eq as u32});
+    fn get_compressed_texture_formats(&self) -> Vec<u32> {
+        self.log("get_compressed_texture_formats");
+        let res = gl_call!(
+            self,
+            get_parameter(Parameter::CompressedTextureFormats as u32)
+        )
+        .unwrap_or(JsValue::NULL);
+        self.check_error("get_compressed_texture_formats");
+        if res.is_object() {
+            Uint32Array::new(&res).to_vec()
+        } else {
+            Vec::new()
         }
     }
 
-    pub fn blend_func(&self, b1: BlendMode, b2: BlendMode) {
-        self.log("blend_func");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.blendFunc(@{b1 as u32},@{b2 as u32})
-        }
+    fn create_texture(&self) -> Result<WebGLTexture<WebGlTexture>, GlError> {
+        self.log("create_texture");
+        let object = gl_call!(self, create_texture())
+            .map(WebGLTexture)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::Texture, &object.0, Location::caller());
+        Ok(object)
     }
 
-    pub fn blend_color(&self, r: f32, g: f32, b: f32, a: f32) {
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.blendColor(@{r}, @{g}, @{b}, @{a});
-        }
+    fn delete_texture(&self, texture: &WebGLTexture<WebGlTexture>) {
+        self.log("delete_texture");
+        gl_call!(self, delete_texture(Some(&texture.0)));
+        self.check_error("delete_texture");
+        let key = object_key(&texture.0);
+        self.objects.deleted(ObjectKind::Texture, key);
+        self.state.deleted(ObjectKind::Texture, key);
+        self.framebuffers.deleted(ObjectKind::Texture, key);
     }
 
-    pub fn uniform_matrix_4fv(&self, location: &WebGLUniformLocation, value: &[[f32; 4]; 4]) {
-        self.log("uniform_matrix_4fv");
-        use std::mem;
-        let array = unsafe { mem::transmute::<&[[f32; 4]; 4], &[f32; 16]>(value) as &[f32] };
+    fn generate_mipmap(&self) {
+        self.log("generate_mipmap");
+        gl_call!(self, generate_mipmap(TextureKind::Texture2d as u32));
+        self.check_error("generate_mipmap");
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::Texture2d)),
+            TextureKind::Texture2d,
+        );
+    }
 
-        unsafe {
-            __js_uniform4vf(
-                self.reference,
-                *location.deref(),
-                array[0],
-                array[1],
-                array[2],
-                array[3],
-                array[4],
-                array[5],
-                array[6],
-                array[7],
-                array[8],
-                array[9],
-                array[10],
-                array[11],
-                array[12],
-                array[13],
-                array[14],
-                array[15],
-                r###"
-            var ctx = Module.gl.get($0);
-            var loc = Module.gl.get($1);
-            var m = Module.gl.matrix4x4;
-            m[0] = $2;
-            m[1] = $3;
-            m[2] = $4;
-            m[3] = $5;
-            m[4] = $6;
-            m[5] = $7;
-            m[6] = $8;
-            m[7] = $9;
-            m[8] = $10;
-            m[9] = $11;
-            m[10] = $12;
-            m[11] = $13;
-            m[12] = $14;
-            m[13] = $15;
-            m[14] = $16;
-            m[15] = $17;
-
-            return ctx.uniformMatrix4fv(loc,false, m);
-        "### as *const _ as *const u8,
-            );
-        }
-    }
-
-    pub fn uniform_matrix_3fv(&self, location: &WebGLUniformLocation, value: &[[f32; 3]; 3]) {
-        self.log("uniform_matrix_3fv");
-        use std::mem;
-        let array = unsafe { mem::transmute::<&[[f32; 3]; 3], &[f32; 9]>(value) as &[f32] };
-        js!{
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
-            ctx.uniformMatrix3fv(loc,false,@{&array})
+    fn generate_mipmap_cube(&self) {
+        self.log("generate_mipmap_cube");
+        gl_call!(self, generate_mipmap(TextureKind::TextureCubeMap as u32));
+        self.check_error("generate_mipmap_cube");
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::TextureCubeMapPositiveX)),
+            TextureKind::TextureCubeMap,
+        );
+    }
+
+    fn active_texture(&self, active: u32) {
+        self.objects.active_texture(active);
+        if !self.state.set(|s| &mut s.active_texture, active) {
+            return;
+        }
+        self.log("active_texture");
+        gl_call!(
+            self,
+            active_texture(WebGlRenderingContext::TEXTURE0 + active)
+        );
+        self.check_error("active_texture");
+    }
+
+    fn bind_texture(&self, texture: &WebGLTexture<WebGlTexture>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::Texture2d),
+            object_key(&texture.0),
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::Texture2d, state_key(&texture.0))
+        {
+            return;
+        }
+        self.log("bind_texture");
+        gl_call!(
+            self,
+            bind_texture(TextureKind::Texture2d as u32, Some(&texture.0))
+        );
+        self.check_error("bind_texture");
+    }
+
+    fn unbind_texture(&self) {
+        self.objects
+            .bind(texture_binding(TextureBindPoint::Texture2d), 0);
+        if !self.state.bind_texture(TextureKind::Texture2d, Some(0)) {
+            return;
+        }
+        self.log("unbind_texture");
+        gl_call!(self, bind_texture(TextureKind::Texture2d as u32, None));
+        self.check_error("unbind_texture");
+    }
+
+    fn bind_texture_cube(&self, texture: &WebGLTexture<WebGlTexture>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            object_key(&texture.0),
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, state_key(&texture.0))
+        {
+            return;
+        }
+        self.log("bind_texture_cube");
+        gl_call!(
+            self,
+            bind_texture(TextureKind::TextureCubeMap as u32, Some(&texture.0))
+        );
+        self.check_error("bind_texture_cube");
+    }
+
+    fn unbind_texture_cube(&self) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            0,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, Some(0))
+        {
+            return;
         }
+        self.log("unbind_texture_cube");
+        gl_call!(self, bind_texture(TextureKind::TextureCubeMap as u32, None));
+        self.check_error("unbind_texture_cube");
     }
 
-    pub fn uniform_matrix_2fv(&self, location: &WebGLUniformLocation, value: &[[f32; 2]; 2]) {
-        use std::mem;
-        let array = unsafe { mem::transmute::<&[[f32; 2]; 2], &[f32; 4]>(value) as &[f32] };
-        js!{
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
-            ctx.uniformMatrix2fv(loc,false,@{&array})
+    fn blend_equation(&self, eq: BlendEquation) {
+        if !self.state.set(|s| &mut s.blend_equation, eq) {
+            return;
         }
+        self.log("blend_equation");
+        gl_call!(self, blend_equation(eq as u32));
+        self.check_error("blend_equation");
     }
 
-    pub fn uniform_1i(&self, location: &WebGLUniformLocation, value: i32) {
-        js!{
-            @(no_return)
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
-            ctx.uniform1i(loc,@{value})
+    fn blend_func(&self, b1: BlendMode, b2: BlendMode) {
+        if !self.state.set(|s| &mut s.blend_func, (b1, b2)) {
+            return;
         }
+        self.log("blend_func");
+        gl_call!(self, blend_func(b1 as u32, b2 as u32));
+        self.check_error("blend_func");
     }
 
-    pub fn uniform_1f(&self, location: &WebGLUniformLocation, value: f32) {
-        js!{
-            @(no_return)
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
-            ctx.uniform1f(loc,@{value});
+    fn blend_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.blend_color, [r, g, b, a]) {
+            return;
         }
+        self.log("blend_color");
+        gl_call!(self, blend_color(r, g, b, a));
+        self.check_error("blend_color");
+    }
+
+    fn stencil_func(&self, func: StencilTest, reference: i32, mask: u32) {
+        self.log("stencil_func");
+        gl_call!(self, stencil_func(func as u32, reference, mask));
+        self.check_error("stencil_func");
+    }
+
+    fn stencil_op(&self, fail: StencilAction, depth_fail: StencilAction, pass: StencilAction) {
+        self.log("stencil_op");
+        gl_call!(
+            self,
+            stencil_op(fail as u32, depth_fail as u32, pass as u32)
+        );
+        self.check_error("stencil_op");
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        self.log("stencil_mask");
+        gl_call!(self, stencil_mask(mask));
+        self.check_error("stencil_mask");
+    }
+
+    fn stencil_func_separate(&self, face: Culling, func: StencilTest, reference: i32, mask: u32) {
+        self.log("stencil_func_separate");
+        gl_call!(
+            self,
+            stencil_func_separate(face as u32, func as u32, reference, mask)
+        );
+        self.check_error("stencil_func_separate");
     }
 
-    pub fn uniform_2f(&self, location: &WebGLUniformLocation, value: (f32, f32)) {
-        js!{
-            @(no_return)
-            var p = [@{value.0},@{value.1}];
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
+    fn stencil_op_separate(
+        &self,
+        face: Culling,
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    ) {
+        self.log("stencil_op_separate");
+        gl_call!(
+            self,
+            stencil_op_separate(face as u32, fail as u32, depth_fail as u32, pass as u32)
+        );
+        self.check_error("stencil_op_separate");
+    }
+
+    fn stencil_mask_separate(&self, face: Culling, mask: u32) {
+        self.log("stencil_mask_separate");
+        gl_call!(self, stencil_mask_separate(face as u32, mask));
+        self.check_error("stencil_mask_separate");
+    }
+
+    fn clear_stencil(&self, value: i32) {
+        self.log("clear_stencil");
+        gl_call!(self, clear_stencil(value));
+        self.check_error("clear_stencil");
+    }
+
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool) {
+        self.log("color_mask");
+        gl_call!(self, color_mask(r, g, b, a));
+        self.check_error("color_mask");
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        self.log("polygon_offset");
+        gl_call!(self, polygon_offset(factor, units));
+        self.check_error("polygon_offset");
+    }
 
-            ctx.uniform2f(loc,p[0],p[1])
+    fn scissor(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.scissor, (x, y, width, height)) {
+            return;
         }
+        self.log("scissor");
+        gl_call!(self, scissor(x, y, width as i32, height as i32));
+        self.check_error("scissor");
     }
 
-    pub fn uniform_3f(&self, location: &WebGLUniformLocation, value: (f32, f32, f32)) {
-        js!{
-            @(no_return)
-            var p = [@{value.0},@{value.1},@{value.2}];
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
+    fn front_face(&self, mode: FrontFaceDirection) {
+        if !self.state.set(|s| &mut s.front_face, mode) {
+            return;
+        }
+        self.log("front_face");
+        gl_call!(self, front_face(mode as u32));
+        self.check_error("front_face");
+    }
 
-            ctx.uniform3f(loc,p[0],p[1],p[2])
+    fn line_width(&self, width: f32) {
+        if !self.state.set(|s| &mut s.line_width, width) {
+            return;
         }
+        self.log("line_width");
+        gl_call!(self, line_width(width));
+        self.check_error("line_width");
     }
 
-    pub fn uniform_4f(&self, location: &WebGLUniformLocation, value: (f32, f32, f32, f32)) {
-        js!{
-            @(no_return)
-            var p = [@{value.0},@{value.1},@{value.2},@{value.3}];
-            var ctx = Module.gl.get(@{self.reference});
-            var loc = Module.gl.get(@{location.reference});
+    fn hint(&self, target: Hint, mode: Hint) {
+        self.log("hint");
+        gl_call!(self, hint(target as u32, mode as u32));
+        self.check_error("hint");
+    }
 
-            ctx.uniform4f(loc,p[0],p[1],p[2],p[3])
+    fn depth_range(&self, near: f32, far: f32) {
+        if !self.state.set(|s| &mut s.depth_range, (near, far)) {
+            return;
         }
+        self.log("depth_range");
+        gl_call!(self, depth_range(near, far));
+        self.check_error("depth_range");
     }
 
-    pub fn create_vertex_array(&self) -> WebGLVertexArray {
-        self.log("create_vertex_array");
-        let val = js! {
-            var ctx = Module.gl.get(@{self.reference});
-            if (ctx.createVertexArray) {
-                return Module.gl.add(ctx.createVertexArray());
-            } else {
-                return 0;
-            }
-        };
-        WebGLVertexArray(val.try_into().unwrap())
+    fn sample_coverage(&self, value: f32, invert: bool) {
+        self.log("sample_coverage");
+        gl_call!(self, sample_coverage(value, invert));
+        self.check_error("sample_coverage");
     }
 
-    pub fn delete_vertex_array(&self, vao: &WebGLVertexArray) {
-        self.log("delete_vertex_array");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{self.reference});
-            if (ctx.deleteVertexArray) {
-                var vao = Module.gl.get(@{vao.0});
-                ctx.deleteVertexArray(vao);
-            }
-        };
+    fn apply_pipeline(&self, state: &PipelineState) {
+        self.pipeline.apply(self, state);
     }
 
-    pub fn bind_vertex_array(&self, vao: &WebGLVertexArray) {
-        self.log("bind_vertex_array");
-        self.reference.bind_vertex_array(Some(vao));
+    fn uniform_matrix_4fv(
+        &self,
+        location: &WebGLUniformLocation<WebGlUniformLocation>,
+        value: &[[f32; 4]; 4],
+    ) {
+        self.log("uniform_matrix_4fv");
+        let array = unsafe { mem::transmute::<&[[f32; 4]; 4], &[f32; 16]>(value) as &[f32] };
+        gl_call!(
+            self,
+            uniform_matrix4fv_with_f32_array(Some(&location.reference), false, array)
+        );
+        self.check_error("uniform_matrix_4fv");
     }
 
-    pub fn unbind_vertex_array(&self, vao: &WebGLVertexArray) {
-        self.log("unbind_vertex_array");
-        js! {
-            @(no_return)
-            var ctx = Module.gl.get(@{self.reference});
-            if (ctx.unbindVertexArray) {
-                var vao = Module.gl.get(@{vao.0});
-                ctx.unbindVertexArray(vao);
-            }
-        }
+    fn uniform_matrix_3fv(
+        &self,
+        location: &WebGLUniformLocation<WebGlUniformLocation>,
+        value: &[[f32; 3]; 3],
+    ) {
+        self.log("uniform_matrix_3fv");
+        let array = unsafe { mem::transmute::<&[[f32; 3]; 3], &[f32; 9]>(value) as &[f32] };
+        gl_call!(
+            self,
+            uniform_matrix3fv_with_f32_array(Some(&location.reference), false, array)
+        );
+        self.check_error("uniform_matrix_3fv");
+    }
+
+    fn uniform_matrix_2fv(
+        &self,
+        location: &WebGLUniformLocation<WebGlUniformLocation>,
+        value: &[[f32; 2]; 2],
+    ) {
+        self.log("uniform_matrix_2fv");
+        let array = unsafe { mem::transmute::<&[[f32; 2]; 2], &[f32; 4]>(value) as &[f32] };
+        gl_call!(
+            self,
+            uniform_matrix2fv_with_f32_array(Some(&location.reference), false, array)
+        );
+        self.check_error("uniform_matrix_2fv");
     }
 
-    pub fn get_program_parameter(&self, program: &WebGLProgram, pname: ShaderParameter) -> i32 {
-        let res = js! {
-            var h = Module.gl.get(@{program.deref()});
-            var ctx = Module.gl.get(@{self.reference});
+    fn uniform_1i(&self, location: &WebGLUniformLocation<WebGlUniformLocation>, value: i32) {
+        self.log("uniform_1i");
+        gl_call!(self, uniform1i(Some(&location.reference), value));
+        self.check_error("uniform_1i");
+    }
 
-            return ctx.getProgramParameter(h.prog,@{pname as u32});
-        };
+    fn uniform_1f(&self, location: &WebGLUniformLocation<WebGlUniformLocation>, value: f32) {
+        self.log("uniform_1f");
+        gl_call!(self, uniform1f(Some(&location.reference), value));
+        self.check_error("uniform_1f");
+    }
+
+    fn uniform_2f(&self, location: &WebGLUniformLocation<WebGlUniformLocation>, value: (f32, f32)) {
+        self.log("uniform_2f");
+        gl_call!(self, uniform2f(Some(&location.reference), value.0, value.1));
+        self.check_error("uniform_2f");
+    }
+
+    fn uniform_3f(
+        &self,
+        location: &WebGLUniformLocation<WebGlUniformLocation>,
+        value: (f32, f32, f32),
+    ) {
+        self.log("uniform_3f");
+        gl_call!(
+            self,
+            uniform3f(Some(&location.reference), value.0, value.1, value.2)
+        );
+        self.check_error("uniform_3f");
+    }
 
-        res.try_into().unwrap()
-    }
-
-    // pub fn get_active_uniform(&self, program: &WebGLProgram, location: u32) -> WebGLActiveInfo {
-    //     let res = js! {
-    //         var h = Module.gl.get(@{program.deref()});
-    //         var ctx = Module.gl.get(@{self.reference});
-
-    //         return ctx.getActiveUniform(h.prog,@{location})
-    //     };
-
-    //     let name = js! { return @{&res}.name };
-    //     let size = js!{ return @{&res}.size };
-    //     let kind = js!{ return @{&res}.type };
-    //     let k: u32 = kind.try_into().unwrap();
-    //     use std::mem;
-    //     WebGLActiveInfo::new(
-    //         name.into_string().unwrap(),
-    //         size.try_into().unwrap(),
-    //         unsafe { mem::transmute::<u16, UniformType>(k as _) },
-    //         res.into_reference().unwrap(),
-    //     )
-    // }
-
-    // pub fn get_active_attrib(&self, program: &WebGLProgram, location: u32) -> WebGLActiveInfo {
-    //     let res = js! {
-    //         var h = Module.gl.programs[@{program.deref()}];
-    //         return @{self.reference}.getActiveAttrib(h.prog,@{location})
-    //     };
-    //     let name = js! { return @{&res}.name };
-    //     let size = js!{ return @{&res}.size };
-    //     let kind = js!{ return @{&res}.type };
-    //     let k: u32 = kind.try_into().unwrap();
-    //     use std::mem;
-    //     WebGLActiveInfo::new(
-    //         name.into_string().unwrap(),
-    //         size.try_into().unwrap(),
-    //         unsafe { mem::transmute::<u16, UniformType>(k as _) },
-    //         res.into_reference().unwrap(),
-    //     )
-    // }
-
-    pub fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32) {
+    fn uniform_4f(
+        &self,
+        location: &WebGLUniformLocation<WebGlUniformLocation>,
+        value: (f32, f32, f32, f32),
+    ) {
+        self.log("uniform_4f");
+        gl_call!(
+            self,
+            uniform4f(
+                Some(&location.reference),
+                value.0,
+                value.1,
+                value.2,
+                value.3,
+            )
+        );
+        self.check_error("uniform_4f");
+    }
+
+    fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32) {
+        self.log("tex_parameteri");
         // skip not supported flag in for webgl 1 context
         if !self.is_webgl2 {
             if let TextureParameter::TextureWrapR = pname {
                 return;
             }
         }
+        gl_call!(self, tex_parameteri(kind as u32, pname as u32, param));
+        self.check_error("tex_parameteri");
+    }
 
-        js! {
-            var ctx = Module.gl.get(@{self.reference});
-            return ctx.texParameteri(@{kind as u32},@{pname as u32},@{param})
-        };
+    fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32) {
+        self.log("tex_parameterfv");
+        gl_call!(self, tex_parameterf(kind as u32, pname as u32, param));
+        self.check_error("tex_parameterfv");
     }
 
-    pub fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32) {
-        js! {
-            var ctx = Module.gl.get(@{self.reference});
-            return ctx.texParameterf(@{kind as u32},@{pname as u32},@{param})
+    fn create_vertex_array(&self) -> Result<WebGLVertexArray<WebGlVertexArrayObject>, GlError> {
+        self.log("create_vertex_array");
+        let vao = match (&self.reference, &self.extensions.borrow().vao) {
+            (Reference::WebGl2(gl), _) => gl.create_vertex_array(),
+            (_, Some(ext)) => ext.create_vertex_array_oes(),
+            _ => return Err(GlError::Unsupported(NO_VERTEX_ARRAYS)),
         };
+        let vao = vao
+            .map(WebGLVertexArray)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::VertexArray, &vao.0, Location::caller());
+        Ok(vao)
     }
 
-    pub fn draw_buffer(&self, buffers: &[ColorBuffer]) {
-        self.log("draw_buffer");
-
-        let color_enums: Vec<i32> = buffers.iter().map(|c| *c as i32).collect();
-
-        js! {
-            @(no_return)
-
-            var ctx = Module.gl.get(@{self.reference});
-            ctx.drawBuffers(@{color_enums});
-        };
+    fn delete_vertex_array(&self, vao: &WebGLVertexArray<WebGlVertexArrayObject>) {
+        self.log("delete_vertex_array");
+        match (&self.reference, &self.extensions.borrow().vao) {
+            (Reference::WebGl2(gl), _) => gl.delete_vertex_array(Some(&vao.0)),
+            (_, Some(ext)) => ext.delete_vertex_array_oes(Some(&vao.0)),
+            // a handle made up by the caller or kept from before a context restoration
+            _ => return self.unsupported("delete_vertex_array", NO_VERTEX_ARRAYS),
+        }
+        self.check_error("delete_vertex_array");
+        let key = object_key(&vao.0);
+        self.objects.deleted(ObjectKind::VertexArray, key);
+        self.state.deleted(ObjectKind::VertexArray, key);
     }
 
-    pub fn create_framebuffer(&self) -> WebGLFrameBuffer {
-        let val = js! {
-            var ctx = Module.gl.get(@{self.reference});
-            return Module.gl.add(ctx.createFramebuffer());
-        };
-        WebGLFrameBuffer(val.try_into().unwrap())
+    fn bind_vertex_array(&self, vao: &WebGLVertexArray<WebGlVertexArrayObject>) {
+        self.objects.bind_vertex_array(object_key(&vao.0));
+        if !self.state.bind(Slot::VertexArray, state_key(&vao.0)) {
+            return;
+        }
+        self.log("bind_vertex_array");
+        match (&self.reference, &self.extensions.borrow().vao) {
+            (Reference::WebGl2(gl), _) => gl.bind_vertex_array(Some(&vao.0)),
+            (_, Some(ext)) => ext.bind_vertex_array_oes(Some(&vao.0)),
+            // a handle made up by the caller or kept from before a context restoration
+            _ => return self.unsupported("bind_vertex_array", NO_VERTEX_ARRAYS),
+        }
+        self.check_error("bind_vertex_array");
     }
 
-    pub fn delete_framebuffer(&self, fb: &WebGLFrameBuffer) {
-        js! {
-            var ctx = Module.gl.get(@{self.reference});
-            var fb = Module.gl.get(@{fb.deref()});
-            ctx.deleteFramebuffer(fb);
+    fn unbind_vertex_array(&self, _vao: &WebGLVertexArray<WebGlVertexArrayObject>) {
+        self.objects.bind_vertex_array(0);
+        if !self.state.bind(Slot::VertexArray, Some(0)) {
+            return;
+        }
+        self.log("unbind_vertex_array");
+        match (&self.reference, &self.extensions.borrow().vao) {
+            (Reference::WebGl2(gl), _) => gl.bind_vertex_array(None),
+            (_, Some(ext)) => ext.bind_vertex_array_oes(None),
+            // a handle made up by the caller or kept from before a context restoration
+            _ => return self.unsupported("unbind_vertex_array", NO_VERTEX_ARRAYS),
         }
+        self.check_error("unbind_vertex_array");
     }
 
-    pub fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer) {
-        js! {
-            var ctx = Module.gl.get(@{self.reference});
-            var fb = Module.gl.get(@{fb.deref()});
-            ctx.bindFramebuffer(@{buffer as u32}, fb);
+    fn draw_buffer(&self, buffers: &[ColorBuffer]) -> Result<(), GlError> {
+        self.log("draw_buffer");
+        let color_enums: Array = buffers.iter().map(|c| JsValue::from(*c as u32)).collect();
+        match (&self.reference, &self.extensions.borrow().draw_buffers) {
+            (Reference::WebGl2(gl), _) => gl.draw_buffers(&color_enums),
+            (_, Some(ext)) => ext.draw_buffers_webgl(&color_enums),
+            _ => {
+                return Err(GlError::Unsupported(
+                    "multiple render targets: WebGL 1.0 without WEBGL_draw_buffers",
+                ))
+            }
         }
+        self.get_error()
+    }
+
+    fn create_framebuffer(&self) -> Result<WebGLFrameBuffer<WebGlFramebuffer>, GlError> {
+        self.log("create_framebuffer");
+        let object = gl_call!(self, create_framebuffer())
+            .map(WebGLFrameBuffer)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::FrameBuffer, &object.0, Location::caller());
+        Ok(object)
+    }
+
+    fn delete_framebuffer(&self, fb: &WebGLFrameBuffer<WebGlFramebuffer>) {
+        self.log("delete_framebuffer");
+        gl_call!(self, delete_framebuffer(Some(&fb.0)));
+        self.check_error("delete_framebuffer");
+        let key = object_key(&fb.0);
+        self.objects.deleted(ObjectKind::FrameBuffer, key);
+        self.state.deleted(ObjectKind::FrameBuffer, key);
+        self.framebuffers.deleted(ObjectKind::FrameBuffer, key);
+    }
+
+    fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer<WebGlFramebuffer>) {
+        self.framebuffers.bind(object_key(&fb.0));
+        if !self
+            .state
+            .bind(Slot::FrameBuffer(buffer as u32), state_key(&fb.0))
+        {
+            return;
+        }
+        self.log("bind_framebuffer");
+        gl_call!(self, bind_framebuffer(buffer as u32, Some(&fb.0)));
+        self.check_error("bind_framebuffer");
     }
 
-    pub fn framebuffer_texture2d(
+    fn framebuffer_texture2d(
         &self,
         target: Buffers,
         attachment: Buffers,
         textarget: TextureBindPoint,
-        texture: &WebGLTexture,
+        texture: &WebGLTexture<WebGlTexture>,
         level: i32,
     ) {
-        js! {
-            var ctx = Module.gl.get(@{self.reference});
-            var tex = Module.gl.get(@{&texture.0});
-            ctx.framebufferTexture2D(@{target as u32},@{attachment as u32},@{textarget as u32},tex,@{level});
+        // GL rejects negative levels, and no texture has 256 levels
+        if level < 0 || level > u8::MAX as i32 {
+            self.errors.check("framebuffer_texture2d", || {
+                Err(GlError::Gl(Error::InvalidValue))
+            });
+            return;
+        }
+        self.log("framebuffer_texture2d");
+        gl_call!(
+            self,
+            framebuffer_texture_2d(
+                target as u32,
+                attachment as u32,
+                textarget as u32,
+                Some(&texture.0),
+                level,
+            )
+        );
+        self.check_error("framebuffer_texture2d");
+        self.framebuffers.attach(
+            attachment,
+            Image::Texture(object_key(&texture.0), textarget as u32, level as u8),
+        );
+    }
+
+    fn unbind_framebuffer(&self, buffer: Buffers) {
+        self.framebuffers.bind(0);
+        if !self.state.bind(Slot::FrameBuffer(buffer as u32), Some(0)) {
+            return;
+        }
+        self.log("unbind_framebuffer");
+        gl_call!(self, bind_framebuffer(buffer as u32, None));
+        self.check_error("unbind_framebuffer");
+    }
+
+    fn create_renderbuffer(&self) -> Result<WebGLRenderbuffer<WebGlRenderbuffer>, GlError> {
+        self.log("create_renderbuffer");
+        let object = gl_call!(self, create_renderbuffer())
+            .map(WebGLRenderbuffer)
+            .ok_or_else(|| self.create_error())?;
+        self.track(ObjectKind::Renderbuffer, &object.0, Location::caller());
+        Ok(object)
+    }
+
+    fn delete_renderbuffer(&self, rb: &WebGLRenderbuffer<WebGlRenderbuffer>) {
+        self.log("delete_renderbuffer");
+        gl_call!(self, delete_renderbuffer(Some(&rb.0)));
+        self.check_error("delete_renderbuffer");
+        let key = object_key(&rb.0);
+        self.objects.deleted(ObjectKind::Renderbuffer, key);
+        self.state.deleted(ObjectKind::Renderbuffer, key);
+        self.framebuffers.deleted(ObjectKind::Renderbuffer, key);
+    }
+
+    fn bind_renderbuffer(&self, target: Buffers, rb: &WebGLRenderbuffer<WebGlRenderbuffer>) {
+        self.objects
+            .bind(Buffers::RenderbufferBinding as u32, object_key(&rb.0));
+        if !self.state.bind(Slot::Renderbuffer, state_key(&rb.0)) {
+            return;
         }
+        self.log("bind_renderbuffer");
+        gl_call!(self, bind_renderbuffer(target as u32, Some(&rb.0)));
+        self.check_error("bind_renderbuffer");
     }
 
-    pub fn unbind_framebuffer(&self, buffer: Buffers) {
-        self.log("unbind_framebuffer");
-        js!{
-            var ctx = Module.gl.get(@{&self.reference});
-            ctx.bindFramebuffer(@{buffer as u32},null)
+    fn unbind_renderbuffer(&self, target: Buffers) {
+        self.objects.bind(Buffers::RenderbufferBinding as u32, 0);
+        if !self.state.bind(Slot::Renderbuffer, Some(0)) {
+            return;
+        }
+        self.log("unbind_renderbuffer");
+        gl_call!(self, bind_renderbuffer(target as u32, None));
+        self.check_error("unbind_renderbuffer");
+    }
+
+    fn renderbuffer_storage(
+        &self,
+        target: Buffers,
+        format: Buffers,
+        width: u16,
+        height: u16,
+    ) -> Result<(), GlError> {
+        self.log("renderbuffer_storage");
+        gl_call!(
+            self,
+            renderbuffer_storage(target as u32, format as u32, width as i32, height as i32)
+        );
+        self.get_error()?;
+        let rb = self.objects.bound(Buffers::RenderbufferBinding as u32);
+        self.objects
+            .set_renderbuffer_size(rb, renderbuffer_size(width, height, format));
+        self.framebuffers.image(
+            Image::Renderbuffer(rb),
+            ImageFormat::of_renderbuffer(format),
+            width,
+            height,
+        );
+        Ok(())
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        renderbuffertarget: Buffers,
+        rb: &WebGLRenderbuffer<WebGlRenderbuffer>,
+    ) {
+        self.log("framebuffer_renderbuffer");
+        gl_call!(
+            self,
+            framebuffer_renderbuffer(
+                target as u32,
+                attachment as u32,
+                renderbuffertarget as u32,
+                Some(&rb.0),
+            )
+        );
+        self.check_error("framebuffer_renderbuffer");
+        self.framebuffers
+            .attach(attachment, Image::Renderbuffer(object_key(&rb.0)));
+    }
+
+    fn get_renderbuffer_parameter(&self, target: Buffers, pname: Buffers) -> i32 {
+        self.log("get_renderbuffer_parameter");
+        let res = gl_call!(
+            self,
+            get_renderbuffer_parameter(target as u32, pname as u32)
+        );
+        self.check_error("get_renderbuffer_parameter");
+        res.as_f64().unwrap_or(0.0) as i32
+    }
+
+    fn check_framebuffer_status(&self, target: Buffers) -> Result<FramebufferStatus, GlError> {
+        self.log("check_framebuffer_status");
+        let code = gl_call!(self, check_framebuffer_status(target as u32));
+        FramebufferStatus::from_code(code)
+            .ok_or_else(|| self.get_error().err().unwrap_or(GlError::Unknown(code)))
+    }
+
+    fn create_timer_query(&self) -> Result<WebGLQuery<WebGlQuery>, GlError> {
+        self.log("create_timer_query");
+        let query = match (&self.reference, &self.extensions.borrow().timer_query) {
+            (Reference::WebGl2(gl), _) if self.webgl2_timer_queries() => gl.create_query(),
+            (Reference::WebGl1(_), Some(ext)) => ext.create_query_ext(),
+            _ => return Err(GlError::Unsupported(NO_TIMER_QUERIES)),
+        };
+        query.map(WebGLQuery).ok_or_else(|| self.create_error())
+    }
+
+    fn delete_timer_query(&self, query: &WebGLQuery<WebGlQuery>) {
+        self.log("delete_timer_query");
+        match (&self.reference, &self.extensions.borrow().timer_query) {
+            (Reference::WebGl2(gl), _) => gl.delete_query(Some(&query.0)),
+            (_, Some(ext)) => ext.delete_query_ext(Some(&query.0)),
+            _ => return,
+        }
+        self.check_error("delete_timer_query");
+    }
+
+    fn begin_time_elapsed(&self, query: &WebGLQuery<WebGlQuery>) -> Result<(), GlError> {
+        self.log("begin_time_elapsed");
+        let target = ExtDisjointTimerQuery::TIME_ELAPSED_EXT;
+        match (&self.reference, &self.extensions.borrow().timer_query) {
+            (Reference::WebGl2(gl), _) if self.webgl2_timer_queries() => {
+                gl.begin_query(target, &query.0)
+            }
+            (_, Some(ext)) => ext.begin_query_ext(target, &query.0),
+            _ => return Err(GlError::Unsupported(NO_TIMER_QUERIES)),
+        }
+        self.get_error()
+    }
+
+    fn end_time_elapsed(&self) -> Result<(), GlError> {
+        self.log("end_time_elapsed");
+        let target = ExtDisjointTimerQuery::TIME_ELAPSED_EXT;
+        match (&self.reference, &self.extensions.borrow().timer_query) {
+            (Reference::WebGl2(gl), _) if self.webgl2_timer_queries() => gl.end_query(target),
+            (_, Some(ext)) => ext.end_query_ext(target),
+            _ => return Err(GlError::Unsupported(NO_TIMER_QUERIES)),
+        }
+        self.get_error()
+    }
+
+    fn get_time_elapsed(&self, query: &WebGLQuery<WebGlQuery>) -> Result<Option<u64>, GlError> {
+        self.log("get_time_elapsed");
+        let get = |pname| match (&self.reference, &self.extensions.borrow().timer_query) {
+            (Reference::WebGl2(gl), _) if self.webgl2_timer_queries() => {
+                Ok(gl.get_query_parameter(&query.0, pname))
+            }
+            (_, Some(ext)) => Ok(ext.get_query_object_ext(&query.0, pname)),
+            _ => Err(GlError::Unsupported(NO_TIMER_QUERIES)),
+        };
+        let available = get(ExtDisjointTimerQuery::QUERY_RESULT_AVAILABLE_EXT)?;
+        if !available.as_bool().unwrap_or(false) {
+            return Ok(None);
+        }
+        let disjoint = self.get_parameter(Parameter::GpuDisjoint, 1, "get_time_elapsed");
+        if disjoint.as_bool().unwrap_or(false) {
+            return Err(GlError::TimerDisjoint);
         }
+        let elapsed = get(ExtDisjointTimerQuery::QUERY_RESULT_EXT)?;
+        Ok(elapsed.as_f64().map(|ns| ns as u64))
     }
-*/
 }
@@ -3,18 +3,35 @@ use glenum::*;
 use std::ops::Deref;
 use std::os::raw::c_void;
 
+use backend::GlBackend;
+use capabilities::{Api, Capabilities};
 use common::*;
+use diagnostic::with_header;
+use error::{CallError, ErrorPolicy, ErrorState, GlError};
+use extensions::GlExtensions;
+use framebuffer::{FramebufferValidator, Image, ImageFormat};
+use owned::DeletionQueue;
+use pipeline::{PipelineCache, PipelineState};
+use reflection::{cache_active_uniforms, UniformCache};
+use state::{ShadowState, Slot};
+use std::collections::HashSet;
 use std::ffi::CStr;
 use std::ffi::CString;
+use std::panic::Location;
 use std::ptr;
+use std::rc::Rc;
 use std::str;
+use tracking::{
+    buffer_binding, image_size, renderbuffer_size, texture_binding, LiveObject, ObjectKind,
+    ObjectRef, ObjectTracker,
+};
 
 pub type Reference = u32;
 
 #[derive(Debug, PartialEq, Clone)]
 /// uni-gl internal OpenGL context.
 ///
-/// You shouldn't use this struct directly. Instead, call the methods on [`WebGLRenderingContext`]
+/// You shouldn't use this struct directly. Instead, call the [`GlBackend`] methods on [`WebGLRenderingContext`]
 /// as it automatically dereferences into a [`GLContext`].
 ///
 /// This doc is not intended to cover all OpenGL API in depth.
@@ -24,40 +41,73 @@ pub struct GLContext {
     pub reference: Reference,
     /// whether this context is a WebGL 2.0 context
     pub is_webgl2: bool,
+    /// whether this context is OpenGL ES rather than desktop OpenGL, detected from `GL_VERSION`
+    pub is_gles: bool,
+    errors: Rc<ErrorState>,
+    objects: Rc<ObjectTracker>,
+    uniforms: Rc<UniformCache<u32>>,
+    state: Rc<ShadowState>,
+    pipeline: Rc<PipelineCache>,
+    framebuffers: Rc<FramebufferValidator>,
+    capabilities: Rc<Capabilities>,
+    extensions: Rc<GlExtensions>,
 }
 
-/// panics with a proper message if the last OpenGL call returned an error
-pub fn check_gl_error(msg: &str) {
+/// return the error flag raised by the last OpenGL calls, if any
+pub fn check_gl_error() -> Result<(), GlError> {
+    match GlError::from_code(unsafe { gl::GetError() }) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// turn a nul-terminated info log into a String
+fn info_log(mut buf: Vec<u8>) -> String {
+    if let Some(end) = buf.iter().position(|&c| c == 0) {
+        buf.truncate(end);
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// gl::GetString convenient wrapper, empty for an invalid parameter
+fn get_string(param: u32) -> String {
     unsafe {
-        use gl;
-        let err = gl::GetError();
-        if err != gl::NO_ERROR {
-            panic!(
-                "GLError: {} {} ({})",
-                msg,
-                err,
-                match err {
-                    gl::INVALID_ENUM => "invalid enum",
-                    gl::INVALID_OPERATION => "invalid operation",
-                    gl::INVALID_VALUE => "invalid value",
-                    gl::OUT_OF_MEMORY => "out of memory",
-                    gl::STACK_OVERFLOW => "stack overflow",
-                    gl::STACK_UNDERFLOW => "stack underflow",
-                    _ => "unknown error",
-                }
-            );
+        let data = gl::GetString(param);
+        if data.is_null() {
+            return String::new();
         }
+        CStr::from_ptr(data as *const _)
+            .to_string_lossy()
+            .into_owned()
     }
 }
 
-/// gl::GetString convenient wrapper
-fn get_string(param: u32) -> String {
-    return unsafe {
-        let data = CStr::from_ptr(gl::GetString(param) as *const _)
-            .to_bytes()
-            .to_vec();
-        String::from_utf8(data).unwrap()
-    };
+/// names of the extensions supported by the current context
+fn supported_extensions() -> HashSet<String> {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        if gl::GetError() != gl::NO_ERROR {
+            // before OpenGL 3.0 / OpenGL ES 3.0
+            return get_string(gl::EXTENSIONS)
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+        }
+        (0..count.max(0) as u32)
+            .filter_map(|i| {
+                let name = gl::GetStringi(gl::EXTENSIONS, i);
+                if name.is_null() {
+                    return None;
+                }
+                Some(
+                    CStr::from_ptr(name as *const _)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect()
+    }
 }
 
 pub type WebGLContext<'p> = Box<'p + for<'a> FnMut(&'a str) -> *const c_void>;
@@ -67,7 +117,7 @@ impl WebGLRenderingContext {
     ///
     /// uni-gl should be used with the uni-app crate.
     /// You can create a [`WebGLRenderingContext`] with following code :
-    /// ```
+    /// ```ignore
     /// let app = uni_app::App::new(...);
     /// let gl = uni_gl::WebGLRenderingContext::new(app.canvas());
     /// ```
@@ -76,6 +126,7 @@ impl WebGLRenderingContext {
 
         WebGLRenderingContext {
             common: GLContext::new(),
+            deletions: DeletionQueue::default(),
         }
     }
 }
@@ -83,98 +134,318 @@ impl WebGLRenderingContext {
 impl GLContext {
     pub fn new() -> GLContext {
         //  unsafe { gl::Enable(gl::DEPTH_TEST) };
-        println!("opengl {}", get_string(gl::VERSION));
-        println!(
-            "shading language {}",
-            get_string(gl::SHADING_LANGUAGE_VERSION)
-        );
-        println!("vendor {}", get_string(gl::VENDOR));
-        GLContext {
+        let is_gles = get_string(gl::VERSION).starts_with("OpenGL ES");
+        let mut context = GLContext {
             reference: 0,
             is_webgl2: true,
+            is_gles,
+            errors: Rc::new(ErrorState::default()),
+            objects: Rc::new(ObjectTracker::new(true)),
+            uniforms: Rc::new(UniformCache::default()),
+            state: Rc::new(ShadowState::default()),
+            pipeline: Rc::new(PipelineCache::default()),
+            framebuffers: Rc::new(FramebufferValidator::default()),
+            capabilities: Rc::new(Capabilities::default()),
+            extensions: Rc::new(GlExtensions::default()),
+        };
+        let api = if is_gles { Api::OpenGlEs } else { Api::OpenGl };
+        context.capabilities = Rc::new(Capabilities::query(&context, api, supported_extensions()));
+        // nothing to enable natively
+        context.extensions = Rc::new(GlExtensions::detect(&context, |_| true));
+        context
+    }
+
+    /// how calls not returning a [`Result`] deal with OpenGL errors
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.errors.policy()
+    }
+
+    /// change how calls not returning a [`Result`] deal with OpenGL errors
+    pub fn set_error_policy(&self, policy: ErrorPolicy) {
+        self.errors.set_policy(policy);
+    }
+
+    /// return and forget the errors kept under [`ErrorPolicy::Collect`], typically once per frame
+    pub fn take_errors(&self) -> Vec<CallError> {
+        self.errors.take_errors()
+    }
+
+    /// whether redundant state changes are skipped
+    pub fn state_cache(&self) -> bool {
+        self.state.enabled()
+    }
+
+    /// skip calls setting bindings or fixed-function state to their current value.
+    ///
+    /// Off by default. Call [`GLContext::invalidate_state`] after any OpenGL call made outside uni-gl.
+    pub fn set_state_cache(&self, enabled: bool) {
+        self.state.set_enabled(enabled);
+    }
+
+    /// forget the state known by the state cache and the last applied pipeline,
+    /// e.g. after external code made OpenGL calls
+    pub fn invalidate_state(&self) {
+        self.state.invalidate();
+        self.objects.forget_bindings();
+        self.pipeline.invalidate();
+    }
+
+    /// whether draws check the bound framebuffer after its attachments change
+    pub fn framebuffer_validation(&self) -> bool {
+        self.framebuffers.enabled()
+    }
+
+    /// check the bound framebuffer on the first draw after its attachments change,
+    /// reporting an incomplete one as [`GlError::IncompleteFramebuffer`] through the error policy.
+    ///
+    /// Off by default. Framebuffers and images set up with OpenGL calls made outside uni-gl are not known.
+    pub fn set_framebuffer_validation(&self, enabled: bool) {
+        self.framebuffers.set_enabled(enabled);
+    }
+
+    /// report the bound framebuffer if incomplete, see [`GLContext::set_framebuffer_validation`]
+    fn validate_framebuffer(&self, call: &str) {
+        self.errors.check(call, || {
+            self.framebuffers.validate(&self.capabilities, || {
+                self.check_framebuffer_status(Buffers::Framebuffer)
+            })
+        });
+    }
+
+    /// an error unless instancing is available, through the core entry points or their
+    /// ARB / EXT / ANGLE equivalents loaded in their place
+    fn check_instancing(&self) -> Result<(), GlError> {
+        let loaded = gl::VertexAttribDivisor::is_loaded()
+            && gl::DrawArraysInstanced::is_loaded()
+            && gl::DrawElementsInstanced::is_loaded();
+        if self.extensions.instanced_arrays.is_none() || !loaded {
+            return Err(GlError::Unsupported(
+                "instanced rendering: OpenGL 3.3 / OpenGL ES 3.0 or ARB_instanced_arrays",
+            ));
+        }
+        Ok(())
+    }
+
+    /// an error unless timer queries are available, through the core entry points or their
+    /// EXT equivalents loaded in their place
+    fn check_timer_queries(&self) -> Result<(), GlError> {
+        if self.extensions.disjoint_timer_query.is_none() || !gl::GetQueryObjectui64v::is_loaded() {
+            return Err(GlError::Unsupported(
+                "timer queries: OpenGL 3.3 or ARB_timer_query",
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_error(&self, call: &str) {
+        self.errors.check(call, check_gl_error);
+    }
+
+    /// whether a getter writing `len` values can query `pname`, reporting `InvalidEnum` otherwise
+    fn check_parameter(&self, pname: Parameter, len: usize, call: &str) -> bool {
+        let count = pname.value_count();
+        if count == 0 || count > len {
+            self.errors
+                .check(call, || Err(GlError::Gl(Error::InvalidEnum)));
+            return false;
         }
+        true
+    }
+
+    /// glGetActiveUniform / glGetActiveAttrib wrapper
+    fn get_active(
+        &self,
+        program: &WebGLProgram<u32>,
+        index: u32,
+        max_length: u32,
+        get: unsafe fn(u32, u32, i32, *mut i32, *mut i32, *mut u32, *mut gl::types::GLchar),
+    ) -> Option<WebGLActiveInfo> {
+        let mut len = 0;
+        unsafe {
+            gl::GetProgramiv(program.0, max_length, &mut len);
+        }
+        let mut name = vec![0u8; len.max(1) as usize];
+        let mut size = 0;
+        let mut kind = 0;
+        unsafe {
+            get(
+                program.0,
+                index,
+                name.len() as _,
+                ptr::null_mut(),
+                &mut size,
+                &mut kind,
+                name.as_mut_ptr() as *mut gl::types::GLchar,
+            );
+        }
+        check_gl_error().ok()?;
+        Some(WebGLActiveInfo {
+            name: info_log(name),
+            size: size as u32,
+            kind: UniformType::from_code(kind)?,
+        })
     }
 
     pub fn print<T: Into<String>>(msg: T) {
         print!("{}", msg.into());
     }
+}
+
+impl GlBackend for GLContext {
+    type Buffer = u32;
+    type Shader = u32;
+    type Program = u32;
+    type Texture = u32;
+    type VertexArray = u32;
+    type UniformLocation = u32;
+    type FrameBuffer = u32;
+    type Renderbuffer = u32;
+    type Query = u32;
+
+    fn get_error(&self) -> Result<(), GlError> {
+        check_gl_error()
+    }
+
+    fn is_context_lost(&self) -> bool {
+        false
+    }
+
+    fn set_object_name<'a, O: Into<ObjectRef<'a, Self>>>(&self, object: O, name: &str) {
+        let object = object.into();
+        let key = match object {
+            ObjectRef::Buffer(o) => o.0,
+            ObjectRef::Shader(o) => o.0,
+            ObjectRef::Program(o) => o.0,
+            ObjectRef::Texture(o) => o.0,
+            ObjectRef::VertexArray(o) => o.0,
+            ObjectRef::FrameBuffer(o) => o.0,
+            ObjectRef::Renderbuffer(o) => o.0,
+        };
+        if self.extensions.debug_output.is_some() && gl::ObjectLabel::is_loaded() {
+            let identifier = match object.kind() {
+                ObjectKind::Buffer => gl::BUFFER,
+                ObjectKind::Shader => gl::SHADER,
+                ObjectKind::Program => gl::PROGRAM,
+                ObjectKind::Texture => gl::TEXTURE,
+                ObjectKind::VertexArray => gl::VERTEX_ARRAY,
+                ObjectKind::FrameBuffer => gl::FRAMEBUFFER,
+                ObjectKind::Renderbuffer => gl::RENDERBUFFER,
+            };
+            let label = CString::new(name).unwrap_or_default();
+            unsafe {
+                gl::ObjectLabel(identifier, key, -1, label.as_ptr());
+            }
+            self.check_error("set_object_name");
+        }
+        self.objects.set_name(object.kind(), key as u64, name);
+    }
+
+    fn live_objects(&self) -> Vec<LiveObject> {
+        self.objects.live()
+    }
 
-    /// create a new OpenGL buffer
-    pub fn create_buffer(&self) -> WebGLBuffer<u32> {
+    fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    fn get_supported_extensions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.capabilities.extensions.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn extensions(&self) -> &GlExtensions {
+        &self.extensions
+    }
+
+    fn create_buffer(&self) -> Result<WebGLBuffer<u32>, GlError> {
         let mut buffer = WebGLBuffer(0);
         unsafe {
             gl::GenBuffers(1, &mut buffer.0);
         }
-        check_gl_error("create_buffer");
-        buffer
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::Buffer, buffer.0 as u64, Location::caller());
+        Ok(buffer)
     }
 
-    /// delete an existing buffer
-    pub fn delete_buffer(&self, buffer: &WebGLBuffer<u32>) {
+    fn delete_buffer(&self, buffer: &WebGLBuffer<u32>) {
         unsafe {
             gl::DeleteBuffers(1, &buffer.0);
         }
-        check_gl_error("delete_buffer");
+        self.check_error("delete_buffer");
+        self.objects.deleted(ObjectKind::Buffer, buffer.0 as u64);
+        self.state.deleted(ObjectKind::Buffer, buffer.0 as u64);
     }
 
-    /// bind a buffer to current state.
-    pub fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<u32>) {
+    fn bind_buffer(&self, kind: BufferKind, buffer: &WebGLBuffer<u32>) {
+        self.objects.bind(buffer_binding(kind), buffer.0 as u64);
+        if !self
+            .state
+            .bind(Slot::Buffer(kind as u32), Some(buffer.0 as u64))
+        {
+            return;
+        }
         unsafe {
             gl::BindBuffer(kind as _, buffer.0);
         }
-        check_gl_error("bind_buffer");
+        self.check_error("bind_buffer");
     }
 
-    /// fills a buffer with data.
-    ///
-    /// kind : see [`GLContext::bind_buffer`].
-    pub fn buffer_data(&self, kind: BufferKind, data: &[u8], draw: DrawMode) {
+    fn buffer_data(&self, kind: BufferKind, data: &[u8], draw: DrawMode) -> Result<(), GlError> {
         unsafe {
             gl::BufferData(kind as _, data.len() as _, data.as_ptr() as _, draw as _);
         }
-        check_gl_error("buffer_data");
+        check_gl_error()?;
+        self.objects
+            .set_buffer_size(self.objects.bound(buffer_binding(kind)), data.len());
+        Ok(())
     }
 
-    /// update a subset of a buffer
-    ///
-    /// kind : see [`GLContext::bind_buffer`].
-    ///
-    /// offset : offset in the buffer where data replacement will begin
-    pub fn buffer_sub_data(&self, kind: BufferKind, offset: u32, data: &[u8]) {
+    fn buffer_sub_data(&self, kind: BufferKind, offset: u32, data: &[u8]) {
         unsafe {
             gl::BufferSubData(kind as _, offset as _, data.len() as _, data.as_ptr() as _);
         }
-        check_gl_error("buffer_sub_data");
+        self.check_error("buffer_sub_data");
     }
 
-    /// this buffer is not bound to the current state anymore.
-    pub fn unbind_buffer(&self, kind: BufferKind) {
+    fn unbind_buffer(&self, kind: BufferKind) {
+        self.objects.bind(buffer_binding(kind), 0);
+        if !self.state.bind(Slot::Buffer(kind as u32), Some(0)) {
+            return;
+        }
         unsafe {
             gl::BindBuffer(kind as _, 0);
         }
-        check_gl_error("unbind_buffer");
+        self.check_error("unbind_buffer");
     }
 
-    /// create a new shader.
-    pub fn create_shader(&self, kind: ShaderKind) -> WebGLShader<u32> {
+    fn create_shader(&self, kind: ShaderKind) -> Result<WebGLShader<u32>, GlError> {
         let shader = unsafe { WebGLShader(gl::CreateShader(kind as _)) };
-        check_gl_error("create_shader");
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::Shader, shader.0 as u64, Location::caller());
 
-        return shader;
+        Ok(shader)
     }
 
-    /// set or replace the source code in a shader
-    pub fn shader_source(&self, shader: &WebGLShader<u32>, source: &str) {
+    fn shader_source(&self, shader: &WebGLShader<u32>, source: &str) {
         let src = CString::new(source).unwrap();
         unsafe {
             use std::ptr;
             gl::ShaderSource(shader.0, 1, &src.as_ptr(), ptr::null());
         }
-        check_gl_error("shader_source");
+        self.check_error("shader_source");
+        self.objects.set_header_lines(shader.0 as u64, 0);
     }
 
-    /// compile a shader
-    pub fn compile_shader(&self, shader: &WebGLShader<u32>) {
+    fn shader_source_with_header(&self, shader: &WebGLShader<u32>, header: &str, source: &str) {
+        let (source, header_lines) = with_header(header, source);
+        self.shader_source(shader, &source);
+        self.objects.set_header_lines(shader.0 as u64, header_lines);
+    }
+
+    fn compile_shader(&self, shader: &WebGLShader<u32>) -> Result<(), GlError> {
         unsafe {
             gl::CompileShader(shader.0);
 
@@ -186,8 +457,7 @@ impl GLContext {
             if status != (gl::TRUE as gl::types::GLint) {
                 let mut len = 0;
                 gl::GetShaderiv(shader.0, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buf = Vec::with_capacity(len as usize);
-                buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+                let mut buf = vec![0u8; len.max(1) as usize];
                 gl::GetShaderInfoLog(
                     shader.0,
                     len,
@@ -195,25 +465,26 @@ impl GLContext {
                     buf.as_mut_ptr() as *mut gl::types::GLchar,
                 );
 
-                match String::from_utf8(buf) {
-                    Ok(s) => panic!(s),
-                    Err(_) => panic!("Compile shader fail, reason unknown"),
-                }
+                return Err(GlError::ShaderCompile {
+                    log: info_log(buf),
+                    header_lines: self.objects.header_lines(shader.0 as u64),
+                });
             }
         }
 
-        check_gl_error("compile_shader");
+        check_gl_error()
     }
 
-    /// create a program
-    pub fn create_program(&self) -> WebGLProgram<u32> {
+    fn create_program(&self) -> Result<WebGLProgram<u32>, GlError> {
         let p = unsafe { WebGLProgram(gl::CreateProgram()) };
-        check_gl_error("create_program");
-        p
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::Program, p.0 as u64, Location::caller());
+        Ok(p)
     }
 
-    /// link a program
-    pub fn link_program(&self, program: &WebGLProgram<u32>) {
+    fn link_program(&self, program: &WebGLProgram<u32>) -> Result<(), GlError> {
+        self.uniforms.clear(program.0 as u64);
         unsafe {
             gl::LinkProgram(program.0);
             // Get the link status
@@ -224,8 +495,7 @@ impl GLContext {
             if status != (gl::TRUE as gl::types::GLint) {
                 let mut len = 0;
                 gl::GetProgramiv(program.0, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buf = Vec::with_capacity(len as usize);
-                buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+                let mut buf = vec![0u8; len.max(1) as usize];
                 gl::GetProgramInfoLog(
                     program.0,
                     len,
@@ -233,46 +503,62 @@ impl GLContext {
                     buf.as_mut_ptr() as *mut gl::types::GLchar,
                 );
 
-                match String::from_utf8(buf) {
-                    Ok(s) => panic!(s),
-                    Err(_) => panic!("Link program fail, reason unknown"),
-                }
+                return Err(GlError::ProgramLink { log: info_log(buf) });
             }
         }
-        check_gl_error("link_program");
+        check_gl_error()?;
+        cache_active_uniforms(self, program);
+        Ok(())
     }
 
-    /// bind a program to the current state.
-    pub fn use_program(&self, program: &WebGLProgram<u32>) {
+    fn delete_shader(&self, shader: &WebGLShader<u32>) {
+        unsafe {
+            gl::DeleteShader(shader.0);
+        }
+        self.check_error("delete_shader");
+        self.objects.deleted(ObjectKind::Shader, shader.0 as u64);
+    }
+
+    fn delete_program(&self, program: &WebGLProgram<u32>) {
+        unsafe {
+            gl::DeleteProgram(program.0);
+        }
+        self.check_error("delete_program");
+        self.objects.deleted(ObjectKind::Program, program.0 as u64);
+        self.state.deleted(ObjectKind::Program, program.0 as u64);
+        self.uniforms.clear(program.0 as u64);
+    }
+
+    fn use_program(&self, program: &WebGLProgram<u32>) {
+        if !self.state.bind(Slot::Program, Some(program.0 as u64)) {
+            return;
+        }
         unsafe {
             gl::UseProgram(program.0);
         }
-        check_gl_error("use_program");
+        self.check_error("use_program");
     }
 
-    /// attach a shader to a program. A program must have two shaders : vertex and fragment shader.
-    pub fn attach_shader(&self, program: &WebGLProgram<u32>, shader: &WebGLShader<u32>) {
+    fn attach_shader(&self, program: &WebGLProgram<u32>, shader: &WebGLShader<u32>) {
         unsafe {
             gl::AttachShader(program.0, shader.0);
         }
-        check_gl_error("attach_shader");
+        self.check_error("attach_shader");
     }
 
-    /// associate a generic vertex attribute index with a named attribute
-    pub fn bind_attrib_location(&self, program: &WebGLProgram<u32>, name: &str, loc: u32) {
+    fn bind_attrib_location(&self, program: &WebGLProgram<u32>, name: &str, loc: u32) {
         let c_name = CString::new(name).unwrap();
         unsafe {
             gl::BindAttribLocation(program.0 as _, loc as _, c_name.as_ptr());
-            check_gl_error("bind_attrib_location");
+            self.check_error("bind_attrib_location");
         }
     }
 
-    /// return the location of an attribute variable
-    pub fn get_attrib_location(&self, program: &WebGLProgram<u32>, name: &str) -> Option<u32> {
+    fn get_attrib_location(&self, program: &WebGLProgram<u32>, name: &str) -> Option<u32> {
         let c_name = CString::new(name).unwrap();
         unsafe {
             let location = gl::GetAttribLocation(program.0 as _, c_name.as_ptr());
-            check_gl_error("get_attrib_location");
+            self.check_error("get_attrib_location");
             if location == -1 {
                 return None;
             }
@@ -280,28 +566,26 @@ impl GLContext {
         }
     }
 
-    /// return the location of a uniform variable
-    pub fn get_uniform_location(
+    fn get_uniform_location(
         &self,
         program: &WebGLProgram<u32>,
         name: &str,
     ) -> Option<WebGLUniformLocation<u32>> {
-        let c_name = CString::new(name).unwrap();
-        unsafe {
-            let location = gl::GetUniformLocation(program.0 as _, c_name.as_ptr());
-            check_gl_error(&format!("get_uniform_location {}", name));
-            if location == -1 {
-                return None;
-            }
-            return Some(WebGLUniformLocation<u32> {
-                reference: location as _,
-                name: name.into(),
-            });
+        if let Some(location) = self.uniforms.get(program.0 as u64, name) {
+            return location;
         }
+        let c_name = CString::new(name).unwrap();
+        let location = unsafe { gl::GetUniformLocation(program.0 as _, c_name.as_ptr()) };
+        self.check_error("get_uniform_location");
+        let location = if location == -1 {
+            None
+        } else {
+            Some(location as u32)
+        };
+        self.uniforms.insert(program.0 as u64, name, location)
     }
 
-    /// define an array of generic vertex attribute data
-    pub fn vertex_attrib_pointer(
+    fn vertex_attrib_pointer(
         &self,
         location: u32,
         size: AttributeSize,
@@ -324,112 +608,169 @@ impl GLContext {
         //     "{:?} {:?} {:?} {:?} {:?} {:?} {:?}",
         //     location, size, kind, kind as u32, normalized, stride, offset
         // );
-        check_gl_error("vertex_attrib_pointer");
+        self.check_error("vertex_attrib_pointer");
     }
 
-    /// enable a generic vertex attribute array
-    pub fn enable_vertex_attrib_array(&self, location: u32) {
+    fn enable_vertex_attrib_array(&self, location: u32) {
         unsafe {
             gl::EnableVertexAttribArray(location as _);
         }
-        check_gl_error("enable_vertex_attrib_array");
+        self.check_error("enable_vertex_attrib_array");
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32) -> Result<(), GlError> {
+        self.check_instancing()?;
+        unsafe {
+            gl::VertexAttribDivisor(location, divisor);
+        }
+        check_gl_error()
     }
 
-    /// specify clear values for the color buffers
-    pub fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.clear_color, [r, g, b, a]) {
+            return;
+        }
         unsafe {
             gl::ClearColor(r, g, b, a);
         }
-        check_gl_error("clear_color");
+        self.check_error("clear_color");
     }
 
-    /// enable GL capabilities.
-    ///
-    /// flag should be one of [`Flag`]
-    pub fn enable(&self, flag: i32) {
+    fn enable(&self, flag: i32) {
+        if !self.state.flag(flag, true) {
+            return;
+        }
         unsafe {
             gl::Enable(flag as _);
         }
-        check_gl_error("enable");
+        self.check_error("enable");
     }
 
-    /// disable GL capabilities.
-    ///
-    /// flag should be one of [`Flag`]
-    pub fn disable(&self, flag: i32) {
+    fn disable(&self, flag: i32) {
+        if !self.state.flag(flag, false) {
+            return;
+        }
         unsafe {
             gl::Disable(flag as _);
         }
-        check_gl_error("disable");
+        self.check_error("disable");
     }
 
-    /// specify whether front- or back-facing polygons can be culled
-    pub fn cull_face(&self, flag: Culling) {
+    fn cull_face(&self, flag: Culling) {
+        if !self.state.set(|s| &mut s.cull_face, flag) {
+            return;
+        }
         unsafe {
             gl::CullFace(flag as _);
         }
-        check_gl_error("cullface");
+        self.check_error("cullface");
     }
 
-    /// enable or disable writing into the depth buffer
-    pub fn depth_mask(&self, b: bool) {
+    fn depth_mask(&self, b: bool) {
+        if !self.state.set(|s| &mut s.depth_mask, b) {
+            return;
+        }
         unsafe {
             gl::DepthMask(b as _);
         }
-        check_gl_error("depth_mask");
+        self.check_error("depth_mask");
     }
 
-    /// specify the value used for depth buffer comparisons
-    pub fn depth_func(&self, d: DepthTest) {
+    fn depth_func(&self, d: DepthTest) {
+        if !self.state.set(|s| &mut s.depth_func, d) {
+            return;
+        }
         unsafe {
             gl::DepthFunc(d as _);
         }
 
-        check_gl_error("depth_func");
+        self.check_error("depth_func");
     }
 
-    /// specify the clear value for the depth buffer
-    pub fn clear_depth(&self, value: f32) {
+    fn clear_depth(&self, value: f32) {
+        if !self.state.set(|s| &mut s.clear_depth, value) {
+            return;
+        }
         unsafe {
-            gl::ClearDepth(value as _);
+            if self.is_gles {
+                gl::ClearDepthf(value);
+            } else {
+                gl::ClearDepth(value as _);
+            }
         }
-        check_gl_error("clear_depth");
+        self.check_error("clear_depth");
     }
 
-    /// clear buffers to preset values
-    pub fn clear(&self, bit: BufferBit) {
+    fn clear(&self, bit: BufferBit) {
         unsafe {
             gl::Clear(bit as _);
         }
-        check_gl_error("clear");
+        self.check_error("clear");
     }
 
-    /// set the viewport
-    pub fn viewport(&self, x: i32, y: i32, width: u32, height: u32) {
+    fn viewport(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.viewport, (x, y, width, height)) {
+            return;
+        }
         unsafe {
             gl::Viewport(x, y, width as _, height as _);
         };
-        check_gl_error("viewport");
+        self.check_error("viewport");
     }
 
-    /// render primitives from indexed array data
-    pub fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32) {
+    fn draw_elements(&self, mode: Primitives, count: usize, kind: DataType, offset: u32) {
+        self.validate_framebuffer("draw_elements");
         unsafe {
             gl::DrawElements(mode as _, count as _, kind as _, offset as _);
         };
-        check_gl_error("draw_elements");
+        self.check_error("draw_elements");
     }
 
-    /// render primitives from array data
-    pub fn draw_arrays(&self, mode: Primitives, count: usize) {
+    fn draw_arrays(&self, mode: Primitives, count: usize) {
+        self.validate_framebuffer("draw_arrays");
         unsafe {
             gl::DrawArrays(mode as _, 0, count as _);
         };
-        check_gl_error("draw_arrays");
+        self.check_error("draw_arrays");
     }
 
-    /// read a block of pixels from the frame buffer
-    pub fn read_pixels(
+    fn draw_elements_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        kind: DataType,
+        offset: u32,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.check_instancing()?;
+        self.validate_framebuffer("draw_elements_instanced");
+        unsafe {
+            gl::DrawElementsInstanced(
+                mode as _,
+                count as _,
+                kind as _,
+                offset as _,
+                instances as _,
+            );
+        }
+        check_gl_error()
+    }
+
+    fn draw_arrays_instanced(
+        &self,
+        mode: Primitives,
+        count: usize,
+        instances: usize,
+    ) -> Result<(), GlError> {
+        self.check_instancing()?;
+        self.validate_framebuffer("draw_arrays_instanced");
+        unsafe {
+            gl::DrawArraysInstanced(mode as _, 0, count as _, instances as _);
+        }
+        check_gl_error()
+    }
+
+    fn read_pixels(
         &self,
         x: u32,
         y: u32,
@@ -438,7 +779,7 @@ impl GLContext {
         format: PixelFormat,
         kind: PixelType,
         data: &mut [u8],
-    ) {
+    ) -> Result<(), GlError> {
         unsafe {
             gl::ReadPixels(
                 x as _,
@@ -449,20 +790,18 @@ impl GLContext {
                 kind as _,
                 data.as_mut_ptr() as _,
             );
-            check_gl_error("read_pixels");
         }
+        check_gl_error()
     }
 
-    /// set pixel storage modes
-    pub fn pixel_storei(&self, storage: PixelStorageMode, value: i32) {
+    fn pixel_storei(&self, storage: PixelStorageMode, value: i32) {
         unsafe {
             gl::PixelStorei(storage as _, value);
-            check_gl_error("pixel_storei");
+            self.check_error("pixel_storei");
         }
     }
 
-    /// specify a two-dimensional texture image
-    pub fn tex_image2d(
+    fn tex_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -471,7 +810,7 @@ impl GLContext {
         format: PixelFormat,
         kind: PixelType,
         pixels: &[u8],
-    ) {
+    ) -> Result<(), GlError> {
         let p: *const c_void;
 
         if pixels.len() > 0 {
@@ -494,11 +833,24 @@ impl GLContext {
             );
         }
 
-        check_gl_error("tex_image2d");
+        check_gl_error()?;
+        let texture = self.objects.bound(texture_binding(target));
+        self.objects.set_image_size(
+            texture,
+            target,
+            level,
+            image_size(width, height, format, kind),
+        );
+        self.framebuffers.image(
+            Image::Texture(texture, target as u32, level),
+            ImageFormat::of_pixels(format),
+            width,
+            height,
+        );
+        Ok(())
     }
 
-    /// update a part of a two-dimensional texture subimage
-    pub fn tex_sub_image2d(
+    fn tex_sub_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -524,11 +876,10 @@ impl GLContext {
             );
         }
 
-        check_gl_error("tex_sub_image2d");
+        self.check_error("tex_sub_image2d");
     }
 
-    /// specify a two-dimensional texture image in a compressed format
-    pub fn compressed_tex_image2d(
+    fn compressed_tex_image2d(
         &self,
         target: TextureBindPoint,
         level: u8,
@@ -536,7 +887,7 @@ impl GLContext {
         width: u16,
         height: u16,
         data: &[u8],
-    ) {
+    ) -> Result<(), GlError> {
         unsafe {
             gl::CompressedTexImage2D(
                 target as _,
@@ -550,352 +901,609 @@ impl GLContext {
             );
         }
 
-        check_gl_error("compressed_tex_image2d");
+        check_gl_error()?;
+        let texture = self.objects.bound(texture_binding(target));
+        self.objects
+            .set_image_size(texture, target, level, data.len());
+        self.framebuffers.image(
+            Image::Texture(texture, target as u32, level),
+            ImageFormat::Color,
+            width,
+            height,
+        );
+        Ok(())
     }
 
-    /// return informations about current program
-    pub fn get_program_parameter(&self, program: &WebGLProgram, pname: ShaderParameter) -> i32 {
+    fn get_program_parameter(&self, program: &WebGLProgram<u32>, pname: ShaderParameter) -> i32 {
         let mut res = 0;
         unsafe {
             gl::GetProgramiv(program.0, pname as _, &mut res);
         }
 
-        check_gl_error("get_program_parameter");
+        self.check_error("get_program_parameter");
+        res
+    }
+
+    fn get_active_uniform(
+        &self,
+        program: &WebGLProgram<u32>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.get_active(
+            program,
+            index,
+            gl::ACTIVE_UNIFORM_MAX_LENGTH,
+            gl::GetActiveUniform,
+        )
+    }
+
+    fn get_active_attrib(
+        &self,
+        program: &WebGLProgram<u32>,
+        index: u32,
+    ) -> Option<WebGLActiveInfo> {
+        self.get_active(
+            program,
+            index,
+            gl::ACTIVE_ATTRIBUTE_MAX_LENGTH,
+            gl::GetActiveAttrib,
+        )
+    }
+
+    fn get_parameter_i32(&self, pname: Parameter) -> i32 {
+        if !self.check_parameter(pname, 1, "get_parameter_i32") {
+            return 0;
+        }
+        let mut res = 0;
+        unsafe {
+            gl::GetIntegerv(pname as _, &mut res);
+        }
+
+        self.check_error("get_parameter_i32");
+        res
+    }
+
+    fn get_parameter_f32(&self, pname: Parameter) -> f32 {
+        if !self.check_parameter(pname, 1, "get_parameter_f32") {
+            return 0.0;
+        }
+        let mut res = 0.0;
+        unsafe {
+            gl::GetFloatv(pname as _, &mut res);
+        }
+
+        self.check_error("get_parameter_f32");
+        res
+    }
+
+    fn get_parameter_bool(&self, pname: Parameter) -> bool {
+        if !self.check_parameter(pname, 1, "get_parameter_bool") {
+            return false;
+        }
+        let mut res = 0;
+        unsafe {
+            gl::GetBooleanv(pname as _, &mut res);
+        }
+
+        self.check_error("get_parameter_bool");
+        res != 0
+    }
+
+    fn get_parameter_i32x4(&self, pname: Parameter) -> [i32; 4] {
+        let mut res = [0; 4];
+        if !self.check_parameter(pname, 4, "get_parameter_i32x4") {
+            return res;
+        }
+        unsafe {
+            gl::GetIntegerv(pname as _, res.as_mut_ptr());
+        }
+
+        self.check_error("get_parameter_i32x4");
+        res
+    }
+
+    fn get_parameter_f32x4(&self, pname: Parameter) -> [f32; 4] {
+        let mut res = [0.0; 4];
+        if !self.check_parameter(pname, 4, "get_parameter_f32x4") {
+            return res;
+        }
+        unsafe {
+            gl::GetFloatv(pname as _, res.as_mut_ptr());
+        }
+
+        self.check_error("get_parameter_f32x4");
         res
     }
 
-    // pub fn get_active_uniform(&self, program: &WebGLProgram, location: u32) -> WebGLActiveInfo {
-    //     let mut name: Vec<u8> = Vec::with_capacity(NAME_SIZE);
-    //     let mut size = 0i32;
-    //     let mut len = 0i32;
-    //     let mut kind = 0u32;
-
-    //     unsafe {
-    //         gl::GetActiveUniform(
-    //             program.0,
-    //             location as _,
-    //             NAME_SIZE as _,
-    //             &mut len,
-    //             &mut size,
-    //             &mut kind,
-    //             name.as_mut_ptr() as _,
-    //         );
-    //         name.set_len(len as _);
-    //     };
-
-    //     use std::mem;
-
-    //     WebGLActiveInfo::new(
-    //         String::from_utf8(name).unwrap(),
-    //         //location as _,
-    //         size as _,
-    //         unsafe { mem::transmute::<u16, UniformType>(kind as _) },
-    //         0
-    //         //unsafe { mem::transmute::<u16, DataType>(kind as _) },
-    //     )
-    // }
-
-    // pub fn get_active_attrib(&self, program: &WebGLProgram, location: u32) -> WebGLActiveInfo {
-    //     let mut name: Vec<u8> = Vec::with_capacity(NAME_SIZE);
-    //     let mut size = 0i32;
-    //     let mut len = 0i32;
-    //     let mut kind = 0u32;
-
-    //     unsafe {
-    //         gl::GetActiveAttrib(
-    //             program.0,
-    //             location as _,
-    //             NAME_SIZE as _,
-    //             &mut len,
-    //             &mut size,
-    //             &mut kind,
-    //             name.as_mut_ptr() as _,
-    //         );
-    //         name.set_len(len as _);
-    //     }
-    //     println!("name {:?}", name);
-    //     use std::mem;
-    //     //let c_name = unsafe { CString::from_raw(name[0..(len+1)].as_mut_ptr())};
-    //     WebGLActiveInfo::new(
-    //         String::from_utf8(name).expect("utf8 parse failed"),
-    //         //location,
-    //         size as _,
-    //         //DataType::Float
-    //         unsafe { mem::transmute::<u16, UniformType>(kind as _) },
-    //         0,
-    //     )
-    // }
-
-    /// create a new texture object
-    pub fn create_texture(&self) -> WebGLTexture {
+    fn get_parameter_string(&self, pname: Parameter) -> String {
+        let res = get_string(pname as _);
+        self.check_error("get_parameter_string");
+        res
+    }
+
+    fn get_compressed_texture_formats(&self) -> Vec<u32> {
+        let mut count = 0;
+        unsafe {
+            gl::GetIntegerv(gl::NUM_COMPRESSED_TEXTURE_FORMATS, &mut count);
+        }
+        let mut formats = vec![0; count.max(0) as usize];
+        if !formats.is_empty() {
+            unsafe {
+                gl::GetIntegerv(gl::COMPRESSED_TEXTURE_FORMATS, formats.as_mut_ptr());
+            }
+        }
+        self.check_error("get_compressed_texture_formats");
+        formats.into_iter().map(|f| f as u32).collect()
+    }
+
+    fn create_texture(&self) -> Result<WebGLTexture<u32>, GlError> {
         let mut handle = WebGLTexture(0);
         unsafe {
             gl::GenTextures(1, &mut handle.0);
         }
-        check_gl_error("create_texture");
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::Texture, handle.0 as u64, Location::caller());
 
-        handle
+        Ok(handle)
     }
 
-    /// destroy a texture object
-    pub fn delete_texture(&self, texture: &WebGLTexture) {
+    fn delete_texture(&self, texture: &WebGLTexture<u32>) {
         unsafe {
-            gl::DeleteTextures(1, texture.0 as _);
+            gl::DeleteTextures(1, &texture.0);
         }
 
-        check_gl_error("delete_texture");
+        self.check_error("delete_texture");
+        self.objects.deleted(ObjectKind::Texture, texture.0 as u64);
+        self.state.deleted(ObjectKind::Texture, texture.0 as u64);
+        self.framebuffers
+            .deleted(ObjectKind::Texture, texture.0 as u64);
     }
 
-    /// generate mipmaps for current 2D texture
-    pub fn generate_mipmap(&self) {
+    fn generate_mipmap(&self) {
         unsafe {
             gl::GenerateMipmap(gl::TEXTURE_2D);
         }
 
-        check_gl_error("generate_mipmap");
+        self.check_error("generate_mipmap");
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::Texture2d)),
+            TextureKind::Texture2d,
+        );
     }
 
-    /// generate mipmaps for current cube map texture
-    pub fn generate_mipmap_cube(&self) {
+    fn generate_mipmap_cube(&self) {
         unsafe {
             gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
         }
 
-        check_gl_error("generate_mipmap_cube");
+        self.check_error("generate_mipmap_cube");
+        self.objects.generate_mipmap(
+            self.objects
+                .bound(texture_binding(TextureBindPoint::TextureCubeMapPositiveX)),
+            TextureKind::TextureCubeMap,
+        );
     }
 
-    /// select active texture unit
-    pub fn active_texture(&self, active: u32) {
+    fn active_texture(&self, active: u32) {
+        self.objects.active_texture(active);
+        if !self.state.set(|s| &mut s.active_texture, active) {
+            return;
+        }
         unsafe {
             gl::ActiveTexture(gl::TEXTURE0 + active);
         }
 
-        check_gl_error("active_texture");
+        self.check_error("active_texture");
     }
 
-    /// bind a named 2D texture to a texturing target
-    pub fn bind_texture(&self, texture: &WebGLTexture) {
+    fn bind_texture(&self, texture: &WebGLTexture<u32>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::Texture2d),
+            texture.0 as u64,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::Texture2d, Some(texture.0 as u64))
+        {
+            return;
+        }
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, texture.0);
         }
 
-        check_gl_error("bind_texture");
+        self.check_error("bind_texture");
     }
 
-    /// current 2D texture is not bound to current state anymore
-    pub fn unbind_texture(&self) {
+    fn unbind_texture(&self) {
+        self.objects
+            .bind(texture_binding(TextureBindPoint::Texture2d), 0);
+        if !self.state.bind_texture(TextureKind::Texture2d, Some(0)) {
+            return;
+        }
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, 0);
         }
 
-        check_gl_error("unbind_texture");
+        self.check_error("unbind_texture");
     }
 
-    /// bind a named cube map texture to a texturing target
-    pub fn bind_texture_cube(&self, texture: &WebGLTexture) {
+    fn bind_texture_cube(&self, texture: &WebGLTexture<u32>) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            texture.0 as u64,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, Some(texture.0 as u64))
+        {
+            return;
+        }
         unsafe {
             gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture.0);
         }
 
-        check_gl_error("bind_texture_cube");
+        self.check_error("bind_texture_cube");
     }
 
-    /// current cube map texture is not bound to current state anymore
-    pub fn unbind_texture_cube(&self) {
+    fn unbind_texture_cube(&self) {
+        self.objects.bind(
+            texture_binding(TextureBindPoint::TextureCubeMapPositiveX),
+            0,
+        );
+        if !self
+            .state
+            .bind_texture(TextureKind::TextureCubeMap, Some(0))
+        {
+            return;
+        }
         unsafe {
             gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
         }
 
-        check_gl_error("unbind_texture_cube");
+        self.check_error("unbind_texture_cube");
     }
 
-    /// set the RGB alpha blend equation
-    pub fn blend_equation(&self, eq: BlendEquation) {
+    fn blend_equation(&self, eq: BlendEquation) {
+        if !self.state.set(|s| &mut s.blend_equation, eq) {
+            return;
+        }
         unsafe {
             gl::BlendEquation(eq as _);
         }
 
-        check_gl_error("blend_equation");
+        self.check_error("blend_equation");
     }
 
-    /// specify pixel arithmetic for RGB and alpha components separately
-    pub fn blend_func(&self, b1: BlendMode, b2: BlendMode) {
+    fn blend_func(&self, b1: BlendMode, b2: BlendMode) {
+        if !self.state.set(|s| &mut s.blend_func, (b1, b2)) {
+            return;
+        }
         unsafe {
             gl::BlendFunc(b1 as _, b2 as _);
         }
 
-        check_gl_error("blend_func");
+        self.check_error("blend_func");
     }
 
-    /// set the blend color
-    pub fn blend_color(&self, r: f32, g: f32, b: f32, a: f32) {
+    fn blend_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        if !self.state.set(|s| &mut s.blend_color, [r, g, b, a]) {
+            return;
+        }
         unsafe {
             gl::BlendColor(r, g, b, a);
         }
 
-        check_gl_error("blend_color");
+        self.check_error("blend_color");
+    }
+
+    fn stencil_func(&self, func: StencilTest, reference: i32, mask: u32) {
+        unsafe {
+            gl::StencilFunc(func as _, reference, mask);
+        }
+        self.check_error("stencil_func");
+    }
+
+    fn stencil_op(&self, fail: StencilAction, depth_fail: StencilAction, pass: StencilAction) {
+        unsafe {
+            gl::StencilOp(fail as _, depth_fail as _, pass as _);
+        }
+        self.check_error("stencil_op");
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        unsafe {
+            gl::StencilMask(mask);
+        }
+        self.check_error("stencil_mask");
+    }
+
+    fn stencil_func_separate(&self, face: Culling, func: StencilTest, reference: i32, mask: u32) {
+        unsafe {
+            gl::StencilFuncSeparate(face as _, func as _, reference, mask);
+        }
+        self.check_error("stencil_func_separate");
+    }
+
+    fn stencil_op_separate(
+        &self,
+        face: Culling,
+        fail: StencilAction,
+        depth_fail: StencilAction,
+        pass: StencilAction,
+    ) {
+        unsafe {
+            gl::StencilOpSeparate(face as _, fail as _, depth_fail as _, pass as _);
+        }
+        self.check_error("stencil_op_separate");
+    }
+
+    fn stencil_mask_separate(&self, face: Culling, mask: u32) {
+        unsafe {
+            gl::StencilMaskSeparate(face as _, mask);
+        }
+        self.check_error("stencil_mask_separate");
+    }
+
+    fn clear_stencil(&self, value: i32) {
+        unsafe {
+            gl::ClearStencil(value);
+        }
+        self.check_error("clear_stencil");
+    }
+
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool) {
+        unsafe {
+            gl::ColorMask(r as _, g as _, b as _, a as _);
+        }
+        self.check_error("color_mask");
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        unsafe {
+            gl::PolygonOffset(factor, units);
+        }
+        self.check_error("polygon_offset");
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: u32, height: u32) {
+        if !self.state.set(|s| &mut s.scissor, (x, y, width, height)) {
+            return;
+        }
+        unsafe {
+            gl::Scissor(x, y, width as _, height as _);
+        }
+        self.check_error("scissor");
+    }
+
+    fn front_face(&self, mode: FrontFaceDirection) {
+        if !self.state.set(|s| &mut s.front_face, mode) {
+            return;
+        }
+        unsafe {
+            gl::FrontFace(mode as _);
+        }
+        self.check_error("front_face");
+    }
+
+    fn line_width(&self, width: f32) {
+        if !self.state.set(|s| &mut s.line_width, width) {
+            return;
+        }
+        unsafe {
+            gl::LineWidth(width);
+        }
+        self.check_error("line_width");
+    }
+
+    fn hint(&self, target: Hint, mode: Hint) {
+        unsafe {
+            gl::Hint(target as _, mode as _);
+        }
+        self.check_error("hint");
+    }
+
+    fn depth_range(&self, near: f32, far: f32) {
+        if !self.state.set(|s| &mut s.depth_range, (near, far)) {
+            return;
+        }
+        unsafe {
+            if self.is_gles {
+                gl::DepthRangef(near, far);
+            } else {
+                gl::DepthRange(near as _, far as _);
+            }
+        }
+        self.check_error("depth_range");
+    }
+
+    fn sample_coverage(&self, value: f32, invert: bool) {
+        unsafe {
+            gl::SampleCoverage(value, invert as _);
+        }
+        self.check_error("sample_coverage");
+    }
+
+    fn apply_pipeline(&self, state: &PipelineState) {
+        self.pipeline.apply(self, state);
     }
 
-    /// specify the value of a mat4 uniform variable for the current program object
-    pub fn uniform_matrix_4fv(&self, location: &WebGLUniformLocation, value: &[[f32; 4]; 4]) {
+    fn uniform_matrix_4fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 4]; 4]) {
         unsafe {
             gl::UniformMatrix4fv(*location.deref() as i32, 1, false as _, &value[0] as _);
         }
-        check_gl_error("uniform_matrix_4fv");
+        self.check_error("uniform_matrix_4fv");
     }
 
-    /// specify the value of a mat3 uniform variable for the current program object
-    pub fn uniform_matrix_3fv(&self, location: &WebGLUniformLocation, value: &[[f32; 3]; 3]) {
+    fn uniform_matrix_3fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 3]; 3]) {
         unsafe {
             gl::UniformMatrix3fv(*location.deref() as i32, 1, false as _, &value[0] as _);
         }
-        check_gl_error("uniform_matrix_3fv");
+        self.check_error("uniform_matrix_3fv");
     }
 
-    /// specify the value of a mat2 uniform variable for the current program object
-    pub fn uniform_matrix_2fv(&self, location: &WebGLUniformLocation, value: &[[f32; 2]; 2]) {
+    fn uniform_matrix_2fv(&self, location: &WebGLUniformLocation<u32>, value: &[[f32; 2]; 2]) {
         unsafe {
             gl::UniformMatrix2fv(*location.deref() as i32, 1, false as _, &value[0] as _);
         }
-        check_gl_error("uniform_matrix_2fv");
+        self.check_error("uniform_matrix_2fv");
     }
 
-    /// specify the value of an int uniform variable for the current program object
-    pub fn uniform_1i(&self, location: &WebGLUniformLocation, value: i32) {
+    fn uniform_1i(&self, location: &WebGLUniformLocation<u32>, value: i32) {
         unsafe {
             gl::Uniform1i(*location.deref() as i32, value as _);
         }
-        check_gl_error("uniform_1i");
+        self.check_error("uniform_1i");
     }
 
-    /// specify the value of a float uniform variable for the current program object
-    pub fn uniform_1f(&self, location: &WebGLUniformLocation, value: f32) {
+    fn uniform_1f(&self, location: &WebGLUniformLocation<u32>, value: f32) {
         unsafe {
             gl::Uniform1f(*location.deref() as i32, value as _);
         }
-        check_gl_error("uniform_1f");
+        self.check_error("uniform_1f");
     }
 
-    /// specify the value of a vec2 uniform variable for the current program object
-    pub fn uniform_2f(&self, location: &WebGLUniformLocation, value: (f32, f32)) {
+    fn uniform_2f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32)) {
         unsafe {
             gl::Uniform2f(*location.deref() as _, value.0, value.1);
         }
-        check_gl_error("uniform_2f");
+        self.check_error("uniform_2f");
     }
 
-    /// specify the value of a vec3 uniform variable for the current program object
-    pub fn uniform_3f(&self, location: &WebGLUniformLocation, value: (f32, f32, f32)) {
+    fn uniform_3f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32, f32)) {
         unsafe {
             gl::Uniform3f(*location.deref() as _, value.0, value.1, value.2);
         }
-        check_gl_error("uniform_3f");
+        self.check_error("uniform_3f");
     }
 
-    /// specify the value of a vec4 uniform variable for the current program object
-    pub fn uniform_4f(&self, location: &WebGLUniformLocation, value: (f32, f32, f32, f32)) {
+    fn uniform_4f(&self, location: &WebGLUniformLocation<u32>, value: (f32, f32, f32, f32)) {
         unsafe {
             gl::Uniform4f(*location.deref() as _, value.0, value.1, value.2, value.3);
         }
-        check_gl_error("uniform_4f");
+        self.check_error("uniform_4f");
     }
 
-    /// set texture integer parameters
-    pub fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32) {
+    fn tex_parameteri(&self, kind: TextureKind, pname: TextureParameter, param: i32) {
         unsafe {
             gl::TexParameteri(kind as _, pname as _, param);
         }
-        check_gl_error("tex_parameteri");
+        self.check_error("tex_parameteri");
     }
 
-    /// set texture float parameters
-    pub fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32) {
+    fn tex_parameterfv(&self, kind: TextureKind, pname: TextureParameter, param: f32) {
         unsafe {
             gl::TexParameterfv(kind as _, pname as _, &param);
         }
-        check_gl_error("tex_parameterfv");
+        self.check_error("tex_parameterfv");
     }
 
-    /// create a vertex array object
-    pub fn create_vertex_array(&self) -> WebGLVertexArray {
+    fn create_vertex_array(&self) -> Result<WebGLVertexArray<u32>, GlError> {
         let mut vao = WebGLVertexArray(0);
         unsafe {
             gl::GenVertexArrays(1, &mut vao.0);
         }
-        check_gl_error("create_vertex_array");
-        vao
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::VertexArray, vao.0 as u64, Location::caller());
+        Ok(vao)
     }
 
-    /// destroy a vertex array object
-    pub fn delete_vertex_array(&self, vao: &WebGLVertexArray) {
+    fn delete_vertex_array(&self, vao: &WebGLVertexArray<u32>) {
         unsafe {
             gl::DeleteVertexArrays(1, &vao.0);
         }
-        check_gl_error("delete_vertex_array");
+        self.check_error("delete_vertex_array");
+        self.objects.deleted(ObjectKind::VertexArray, vao.0 as u64);
+        self.state.deleted(ObjectKind::VertexArray, vao.0 as u64);
     }
 
-    /// bind a vertex array object to current state
-    pub fn bind_vertex_array(&self, vao: &WebGLVertexArray) {
+    fn bind_vertex_array(&self, vao: &WebGLVertexArray<u32>) {
+        self.objects.bind_vertex_array(vao.0 as u64);
+        if !self.state.bind(Slot::VertexArray, Some(vao.0 as u64)) {
+            return;
+        }
         unsafe {
             gl::BindVertexArray(vao.0);
         }
-        check_gl_error("bind_vertex_array");
+        self.check_error("bind_vertex_array");
     }
 
-    /// current vertex array object is not bound to the current state anymore
-    pub fn unbind_vertex_array(&self, _vao: &WebGLVertexArray) {
+    fn unbind_vertex_array(&self, _vao: &WebGLVertexArray<u32>) {
+        self.objects.bind_vertex_array(0);
+        if !self.state.bind(Slot::VertexArray, Some(0)) {
+            return;
+        }
         unsafe {
             gl::BindVertexArray(0);
         }
-        check_gl_error("unbind_vertex_array");
+        self.check_error("unbind_vertex_array");
     }
 
-    /// specify which color buffers are to be drawn into
-    pub fn draw_buffer(&self, buffers: &[ColorBuffer]) {
+    fn draw_buffer(&self, buffers: &[ColorBuffer]) -> Result<(), GlError> {
+        if self.is_gles && self.capabilities.version.0 < 3 {
+            return Err(GlError::Unsupported("draw buffers: OpenGL ES 2.0"));
+        }
+        let buffers: Vec<u32> = buffers.iter().map(|b| *b as u32).collect();
         unsafe {
-            for value in buffers {
-                gl::DrawBuffer(*value as _);
-            }
+            gl::DrawBuffers(buffers.len() as _, buffers.as_ptr());
         }
-        check_gl_error("draw_buffer");
+        check_gl_error()
     }
 
-    /// create a new framebuffer
-    pub fn create_framebuffer(&self) -> WebGLFrameBuffer {
+    fn create_framebuffer(&self) -> Result<WebGLFrameBuffer<u32>, GlError> {
         let mut fb = WebGLFrameBuffer(0);
         unsafe {
             gl::GenFramebuffers(1, &mut fb.0);
         }
-        check_gl_error("create_framebuffer");
-        fb
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::FrameBuffer, fb.0 as u64, Location::caller());
+        Ok(fb)
     }
 
-    /// destroy a framebuffer
-    pub fn delete_framebuffer(&self, fb: &WebGLFrameBuffer) {
+    fn delete_framebuffer(&self, fb: &WebGLFrameBuffer<u32>) {
         unsafe {
             gl::DeleteFramebuffers(1, &fb.0);
         }
-        check_gl_error("delete_framebuffer");
+        self.check_error("delete_framebuffer");
+        self.objects.deleted(ObjectKind::FrameBuffer, fb.0 as u64);
+        self.state.deleted(ObjectKind::FrameBuffer, fb.0 as u64);
+        self.framebuffers
+            .deleted(ObjectKind::FrameBuffer, fb.0 as u64);
     }
 
-    /// bind a framebuffer to the current state
-    pub fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer) {
+    fn bind_framebuffer(&self, buffer: Buffers, fb: &WebGLFrameBuffer<u32>) {
+        self.framebuffers.bind(fb.0 as u64);
+        if !self
+            .state
+            .bind(Slot::FrameBuffer(buffer as u32), Some(fb.0 as u64))
+        {
+            return;
+        }
         unsafe {
             gl::BindFramebuffer(buffer as u32, fb.0);
         }
 
-        check_gl_error("bind_framebuffer");
+        self.check_error("bind_framebuffer");
     }
 
-    /// attach a texture to a framebuffer
-    pub fn framebuffer_texture2d(
+    fn framebuffer_texture2d(
         &self,
         target: Buffers,
         attachment: Buffers,
         textarget: TextureBindPoint,
-        texture: &WebGLTexture,
+        texture: &WebGLTexture<u32>,
         level: i32,
     ) {
+        // GL rejects negative levels, and no texture has 256 levels
+        if level < 0 || level > u8::MAX as i32 {
+            self.errors.check("framebuffer_texture2d", || {
+                Err(GlError::Gl(Error::InvalidValue))
+            });
+            return;
+        }
         unsafe {
             gl::FramebufferTexture2D(
                 target as u32,
@@ -906,15 +1514,184 @@ impl GLContext {
             );
         }
 
-        check_gl_error("framebuffer_texture2d");
+        self.check_error("framebuffer_texture2d");
+        self.framebuffers.attach(
+            attachment,
+            Image::Texture(texture.0 as u64, textarget as u32, level as u8),
+        );
     }
 
-    /// unbind a framebuffer
-    pub fn unbind_framebuffer(&self, buffer: Buffers) {
+    fn unbind_framebuffer(&self, buffer: Buffers) {
+        self.framebuffers.bind(0);
+        if !self.state.bind(Slot::FrameBuffer(buffer as u32), Some(0)) {
+            return;
+        }
         unsafe {
             gl::BindFramebuffer(buffer as u32, 0);
         }
 
-        check_gl_error("unbind_framebuffer");
+        self.check_error("unbind_framebuffer");
+    }
+
+    fn create_renderbuffer(&self) -> Result<WebGLRenderbuffer<u32>, GlError> {
+        let mut rb = WebGLRenderbuffer(0);
+        unsafe {
+            gl::GenRenderbuffers(1, &mut rb.0);
+        }
+        check_gl_error()?;
+        self.objects
+            .created(ObjectKind::Renderbuffer, rb.0 as u64, Location::caller());
+        Ok(rb)
+    }
+
+    fn delete_renderbuffer(&self, rb: &WebGLRenderbuffer<u32>) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &rb.0);
+        }
+        self.check_error("delete_renderbuffer");
+        self.objects.deleted(ObjectKind::Renderbuffer, rb.0 as u64);
+        self.state.deleted(ObjectKind::Renderbuffer, rb.0 as u64);
+        self.framebuffers
+            .deleted(ObjectKind::Renderbuffer, rb.0 as u64);
+    }
+
+    fn bind_renderbuffer(&self, target: Buffers, rb: &WebGLRenderbuffer<u32>) {
+        self.objects
+            .bind(Buffers::RenderbufferBinding as u32, rb.0 as u64);
+        if !self.state.bind(Slot::Renderbuffer, Some(rb.0 as u64)) {
+            return;
+        }
+        unsafe {
+            gl::BindRenderbuffer(target as u32, rb.0);
+        }
+
+        self.check_error("bind_renderbuffer");
+    }
+
+    fn unbind_renderbuffer(&self, target: Buffers) {
+        self.objects.bind(Buffers::RenderbufferBinding as u32, 0);
+        if !self.state.bind(Slot::Renderbuffer, Some(0)) {
+            return;
+        }
+        unsafe {
+            gl::BindRenderbuffer(target as u32, 0);
+        }
+
+        self.check_error("unbind_renderbuffer");
+    }
+
+    fn renderbuffer_storage(
+        &self,
+        target: Buffers,
+        format: Buffers,
+        width: u16,
+        height: u16,
+    ) -> Result<(), GlError> {
+        unsafe {
+            gl::RenderbufferStorage(target as u32, format as u32, width as i32, height as i32);
+        }
+        check_gl_error()?;
+        let rb = self.objects.bound(Buffers::RenderbufferBinding as u32);
+        self.objects
+            .set_renderbuffer_size(rb, renderbuffer_size(width, height, format));
+        self.framebuffers.image(
+            Image::Renderbuffer(rb),
+            ImageFormat::of_renderbuffer(format),
+            width,
+            height,
+        );
+        Ok(())
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: Buffers,
+        attachment: Buffers,
+        renderbuffertarget: Buffers,
+        rb: &WebGLRenderbuffer<u32>,
+    ) {
+        unsafe {
+            gl::FramebufferRenderbuffer(
+                target as u32,
+                attachment as u32,
+                renderbuffertarget as u32,
+                rb.0,
+            );
+        }
+
+        self.check_error("framebuffer_renderbuffer");
+        self.framebuffers
+            .attach(attachment, Image::Renderbuffer(rb.0 as u64));
+    }
+
+    fn get_renderbuffer_parameter(&self, target: Buffers, pname: Buffers) -> i32 {
+        let mut res = 0;
+        unsafe {
+            gl::GetRenderbufferParameteriv(target as u32, pname as u32, &mut res);
+        }
+
+        self.check_error("get_renderbuffer_parameter");
+        res
+    }
+
+    fn check_framebuffer_status(&self, target: Buffers) -> Result<FramebufferStatus, GlError> {
+        let code = unsafe { gl::CheckFramebufferStatus(target as u32) };
+        FramebufferStatus::from_code(code)
+            .ok_or_else(|| check_gl_error().err().unwrap_or(GlError::Unknown(code)))
+    }
+
+    fn create_timer_query(&self) -> Result<WebGLQuery<u32>, GlError> {
+        self.check_timer_queries()?;
+        let mut query = WebGLQuery(0);
+        unsafe {
+            gl::GenQueries(1, &mut query.0);
+        }
+        check_gl_error()?;
+        Ok(query)
+    }
+
+    fn delete_timer_query(&self, query: &WebGLQuery<u32>) {
+        unsafe {
+            gl::DeleteQueries(1, &query.0);
+        }
+        self.check_error("delete_timer_query");
+    }
+
+    fn begin_time_elapsed(&self, query: &WebGLQuery<u32>) -> Result<(), GlError> {
+        self.check_timer_queries()?;
+        unsafe {
+            gl::BeginQuery(gl::TIME_ELAPSED, query.0);
+        }
+        check_gl_error()
+    }
+
+    fn end_time_elapsed(&self) -> Result<(), GlError> {
+        self.check_timer_queries()?;
+        unsafe {
+            gl::EndQuery(gl::TIME_ELAPSED);
+        }
+        check_gl_error()
+    }
+
+    fn get_time_elapsed(&self, query: &WebGLQuery<u32>) -> Result<Option<u64>, GlError> {
+        self.check_timer_queries()?;
+        let mut available = 0;
+        unsafe {
+            gl::GetQueryObjectiv(query.0, gl::QUERY_RESULT_AVAILABLE, &mut available);
+        }
+        check_gl_error()?;
+        if available == 0 {
+            return Ok(None);
+        }
+        // only EXT_disjoint_timer_query reports disjoint operations
+        if self.is_gles && self.get_parameter_bool(Parameter::GpuDisjoint) {
+            return Err(GlError::TimerDisjoint);
+        }
+        let mut elapsed = 0;
+        unsafe {
+            gl::GetQueryObjectui64v(query.0, gl::QUERY_RESULT, &mut elapsed);
+        }
+        check_gl_error()?;
+        Ok(Some(elapsed))
     }
 }
@@ -0,0 +1,46 @@
+#![cfg(feature = "derive")]
+extern crate uni_gl;
+
+use uni_gl::*;
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct ColorVertex {
+    position: [f32; 3],
+    #[vertex(normalized)]
+    color: [u8; 4],
+    #[vertex(name = "a_uv")]
+    uv: [f32; 2],
+}
+
+#[test]
+fn derived_layout_matches_the_struct() {
+    let attribute = |name: &str, size, kind, normalized, offset| VertexAttribute {
+        name: name.into(),
+        size,
+        kind,
+        normalized,
+        offset,
+    };
+    assert_eq!(
+        ColorVertex::layout(),
+        VertexLayout {
+            attributes: vec![
+                attribute("position", AttributeSize::Three, DataType::Float, false, 0),
+                attribute("color", AttributeSize::Four, DataType::U8, true, 12),
+                attribute("a_uv", AttributeSize::Two, DataType::Float, false, 16),
+            ],
+            stride: 24,
+        }
+    );
+}
+
+#[test]
+fn derived_vertices_are_uploaded_as_bytes() {
+    let vertex = ColorVertex {
+        position: [1.0, 0.0, 0.0],
+        color: [255, 0, 0, 255],
+        uv: [0.0, 1.0],
+    };
+    assert_eq!(as_bytes(&[vertex]).len(), 24);
+}
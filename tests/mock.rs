@@ -0,0 +1,638 @@
+#![cfg(feature = "mock")]
+extern crate uni_gl;
+
+use uni_gl::mock::GlCommand;
+use uni_gl::*;
+
+#[test]
+fn buffer_upload_is_recorded_in_order() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.create_buffer().unwrap();
+    gl.bind_buffer(BufferKind::Array, &buffer);
+    gl.buffer_data(BufferKind::Array, &[1, 2, 3, 4], DrawMode::Static)
+        .unwrap();
+    gl.unbind_buffer(BufferKind::Array);
+
+    assert_eq!(
+        gl.commands(),
+        vec![
+            GlCommand::CreateBuffer(buffer.0),
+            GlCommand::BindBuffer {
+                kind: BufferKind::Array,
+                buffer: buffer.0,
+            },
+            GlCommand::BufferData {
+                kind: BufferKind::Array,
+                data: vec![1, 2, 3, 4],
+                draw: DrawMode::Static,
+            },
+            GlCommand::UnbindBuffer(BufferKind::Array),
+        ]
+    );
+}
+
+#[test]
+fn handles_are_distinct() {
+    let gl = WebGLRenderingContext::new_mock();
+    let a = gl.create_buffer().unwrap();
+    let b = gl.create_buffer().unwrap();
+    let program = gl.create_program().unwrap();
+    assert_ne!(a.0, b.0);
+    assert_ne!(b.0, program.0);
+}
+
+#[test]
+fn locations_are_assigned_per_program() {
+    let gl = WebGLRenderingContext::new_mock();
+    let first = gl.create_program().unwrap();
+    let second = gl.create_program().unwrap();
+
+    assert_eq!(gl.get_attrib_location(&first, "position"), Some(0));
+    assert_eq!(gl.get_attrib_location(&first, "normal"), Some(1));
+    // asking again returns the same location
+    assert_eq!(gl.get_attrib_location(&first, "position"), Some(0));
+    assert_eq!(gl.get_attrib_location(&second, "normal"), Some(0));
+
+    gl.bind_attrib_location(&second, "uv", 5);
+    assert_eq!(gl.get_attrib_location(&second, "uv"), Some(5));
+    assert_eq!(gl.get_attrib_location(&second, "color"), Some(6));
+
+    let mvp = gl.get_uniform_location(&first, "mvp").unwrap();
+    let tint = gl.get_uniform_location(&first, "tint").unwrap();
+    assert_eq!((mvp.reference, tint.reference), (0, 1));
+    assert_eq!(gl.get_uniform_location(&first, "mvp").unwrap().reference, 0);
+}
+
+#[test]
+fn uniform_locations_are_cached() {
+    let gl = WebGLRenderingContext::new_mock();
+    let program = gl.create_program().unwrap();
+    let first = gl.get_uniform_location(&program, "mvp").unwrap();
+    gl.take_commands();
+
+    // a second query makes no driver call and shares the cached name
+    let second = gl.get_uniform_location(&program, "mvp").unwrap();
+    assert!(gl.commands().is_empty());
+    assert_eq!(second, first);
+    assert!(std::rc::Rc::ptr_eq(&first.name, &second.name));
+
+    // linking again invalidates the cache
+    gl.link_program(&program).unwrap();
+    gl.get_uniform_location(&program, "mvp").unwrap();
+    assert_eq!(
+        gl.commands()[1],
+        GlCommand::GetUniformLocation {
+            program: program.0,
+            name: "mvp".into(),
+        }
+    );
+}
+
+#[test]
+fn take_commands_drains_the_log() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.clear_color(0.0, 0.0, 0.0, 1.0);
+    gl.clear(BufferBit::Color);
+
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::ClearColor(0.0, 0.0, 0.0, 1.0),
+            GlCommand::Clear(BufferBit::Color),
+        ]
+    );
+    assert!(gl.commands().is_empty());
+
+    gl.draw_arrays(Primitives::Triangles, 3);
+    assert_eq!(
+        gl.take_commands(),
+        vec![GlCommand::DrawArrays {
+            mode: Primitives::Triangles,
+            count: 3,
+        }]
+    );
+}
+
+#[test]
+fn set_error_fails_the_next_fallible_call_only() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.set_error(GlError::Gl(Error::OutOfMemory));
+    assert_eq!(
+        gl.create_texture().err(),
+        Some(GlError::Gl(Error::OutOfMemory))
+    );
+    // the call is still recorded, and the error is consumed
+    assert_eq!(gl.commands().len(), 1);
+    assert!(gl.create_texture().is_ok());
+    assert!(gl.get_error().is_ok());
+
+    gl.set_error(GlError::ContextLost);
+    assert_eq!(gl.get_error(), Err(GlError::ContextLost));
+}
+
+#[test]
+fn shader_diagnostics_skip_the_header() {
+    let gl = WebGLRenderingContext::new_mock();
+    let shader = gl.create_shader(ShaderKind::Fragment).unwrap();
+    gl.shader_source_with_header(
+        &shader,
+        "#version 300 es\nprecision mediump float;",
+        "void main() {",
+    );
+    assert_eq!(
+        gl.commands()[1],
+        GlCommand::ShaderSource {
+            shader: shader.0,
+            source: "#version 300 es\nprecision mediump float;\nvoid main() {".into(),
+        }
+    );
+
+    gl.set_error(GlError::ShaderCompile {
+        log: "ERROR: 0:3: '' : syntax error\n".into(),
+        header_lines: 0,
+    });
+    let diagnostics = gl.compile_shader(&shader).unwrap_err().shader_diagnostics();
+    assert_eq!(diagnostics[0].line, Some(1));
+
+    // a plain source has no header
+    gl.shader_source(&shader, "void main() {");
+    gl.set_error(GlError::ShaderCompile {
+        log: "ERROR: 0:1: '' : syntax error\n".into(),
+        header_lines: 0,
+    });
+    let diagnostics = gl.compile_shader(&shader).unwrap_err().shader_diagnostics();
+    assert_eq!(diagnostics[0].line, Some(1));
+}
+
+#[test]
+fn failed_creations_are_not_live() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.create_buffer().unwrap();
+    gl.set_error(GlError::Gl(Error::OutOfMemory));
+    assert!(gl.create_buffer().is_err());
+    assert_eq!(gl.live_objects().len(), 1);
+
+    gl.delete_buffer(&buffer);
+    assert!(gl.live_objects().is_empty());
+}
+
+#[test]
+fn uploads_are_sized_from_recorded_bindings() {
+    let gl = WebGLRenderingContext::new_mock();
+    let small = gl.create_texture().unwrap();
+    let large = gl.create_texture().unwrap();
+    gl.active_texture(0);
+    gl.bind_texture(&small);
+    gl.active_texture(1);
+    gl.bind_texture(&large);
+    gl.tex_image2d(
+        TextureBindPoint::Texture2d,
+        0,
+        2,
+        2,
+        PixelFormat::Rgba,
+        PixelType::UnsignedByte,
+        &[0; 16],
+    )
+    .unwrap();
+    gl.active_texture(0);
+    gl.tex_image2d(
+        TextureBindPoint::Texture2d,
+        0,
+        1,
+        1,
+        PixelFormat::Rgba,
+        PixelType::UnsignedByte,
+        &[0; 4],
+    )
+    .unwrap();
+
+    let mut sizes: Vec<usize> = gl.live_objects().iter().map(|o| o.size).collect();
+    sizes.sort();
+    assert_eq!(sizes, vec![4, 16]);
+
+    // deleting a bound texture unbinds it
+    gl.delete_texture(&small);
+    gl.tex_image2d(
+        TextureBindPoint::Texture2d,
+        0,
+        4,
+        4,
+        PixelFormat::Rgba,
+        PixelType::UnsignedByte,
+        &[0; 64],
+    )
+    .unwrap();
+    assert_eq!(gl.live_objects()[0].size, 16);
+}
+
+#[test]
+fn typed_buffer_update_out_of_bounds_fails() {
+    let gl = WebGLRenderingContext::new_mock();
+    let mut buffer: TypedBuffer<u16, _> =
+        TypedBuffer::new(&gl, BufferKind::ElementArray, VertexLayout::default()).unwrap();
+    buffer.upload(&gl, &[0, 1, 2], DrawMode::Static).unwrap();
+    gl.clear_commands();
+
+    assert!(buffer.update(&gl, 1, &[4, 5]).is_ok());
+    assert_eq!(
+        buffer.update(&gl, 2, &[4, 5]),
+        Err(GlError::Gl(Error::InvalidValue))
+    );
+    assert_eq!(
+        buffer.update(&gl, usize::MAX, &[4]),
+        Err(GlError::Gl(Error::InvalidValue))
+    );
+    // nothing is sent for the failed updates
+    assert_eq!(gl.commands().len(), 2);
+}
+
+#[test]
+fn dropped_owned_objects_are_deleted_on_flush() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.own(gl.create_buffer().unwrap());
+    let id = buffer.0;
+    drop(buffer);
+    assert_eq!(gl.commands(), vec![GlCommand::CreateBuffer(id)]);
+
+    gl.flush_deletions();
+    assert_eq!(
+        gl.commands(),
+        vec![GlCommand::CreateBuffer(id), GlCommand::DeleteBuffer(id)]
+    );
+    assert!(gl.live_objects().is_empty());
+
+    // the queue is drained
+    gl.take_commands();
+    gl.flush_deletions();
+    assert!(gl.commands().is_empty());
+}
+
+#[test]
+fn released_owned_objects_are_not_deleted() {
+    let gl = WebGLRenderingContext::new_mock();
+    let texture = gl.own(gl.create_texture().unwrap()).into_raw();
+    gl.flush_deletions();
+    assert_eq!(gl.commands(), vec![GlCommand::CreateTexture(texture.0)]);
+    assert_eq!(gl.live_objects().len(), 1);
+    gl.delete_texture(&texture);
+}
+
+#[test]
+fn dropping_the_context_keeps_the_queue() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.own(gl.create_buffer().unwrap());
+    let id = buffer.0;
+    drop(buffer);
+
+    // the backend can be moved out, without flushing
+    let common = gl.common;
+    assert_eq!(common.commands(), vec![GlCommand::CreateBuffer(id)]);
+}
+
+#[test]
+fn stencil_calls_are_recorded() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.clear_stencil(0);
+    gl.stencil_func(StencilTest::Always, 1, 0xff);
+    gl.stencil_op(
+        StencilAction::Keep,
+        StencilAction::Keep,
+        StencilAction::Replace,
+    );
+    gl.stencil_mask(0xff);
+    gl.stencil_func_separate(Culling::Back, StencilTest::Equal, 1, 0x0f);
+    gl.stencil_op_separate(
+        Culling::Front,
+        StencilAction::Keep,
+        StencilAction::IncrWrap,
+        StencilAction::DecrWrap,
+    );
+    gl.stencil_mask_separate(Culling::FrontAndBack, 0);
+
+    assert_eq!(
+        gl.commands(),
+        vec![
+            GlCommand::ClearStencil(0),
+            GlCommand::StencilFunc {
+                func: StencilTest::Always,
+                reference: 1,
+                mask: 0xff,
+            },
+            GlCommand::StencilOp {
+                fail: StencilAction::Keep,
+                depth_fail: StencilAction::Keep,
+                pass: StencilAction::Replace,
+            },
+            GlCommand::StencilMask(0xff),
+            GlCommand::StencilFuncSeparate {
+                face: Culling::Back,
+                func: StencilTest::Equal,
+                reference: 1,
+                mask: 0x0f,
+            },
+            GlCommand::StencilOpSeparate {
+                face: Culling::Front,
+                fail: StencilAction::Keep,
+                depth_fail: StencilAction::IncrWrap,
+                pass: StencilAction::DecrWrap,
+            },
+            GlCommand::StencilMaskSeparate(Culling::FrontAndBack, 0),
+        ]
+    );
+}
+
+#[test]
+fn renderbuffer_storage_is_recorded_and_sized() {
+    let gl = WebGLRenderingContext::new_mock();
+    let fb = gl.create_framebuffer().unwrap();
+    let rb = gl.create_renderbuffer().unwrap();
+    gl.bind_framebuffer(Buffers::Framebuffer, &fb);
+    gl.bind_renderbuffer(Buffers::Renderbuffer, &rb);
+    gl.renderbuffer_storage(Buffers::Renderbuffer, Buffers::DepthComponent16, 64, 32)
+        .unwrap();
+    gl.framebuffer_renderbuffer(
+        Buffers::Framebuffer,
+        Buffers::DepthAttachment,
+        Buffers::Renderbuffer,
+        &rb,
+    );
+
+    assert_eq!(
+        gl.commands()[3..],
+        [
+            GlCommand::BindRenderbuffer {
+                target: Buffers::Renderbuffer,
+                rb: rb.0,
+            },
+            GlCommand::RenderbufferStorage {
+                target: Buffers::Renderbuffer,
+                format: Buffers::DepthComponent16,
+                width: 64,
+                height: 32,
+            },
+            GlCommand::FramebufferRenderbuffer {
+                target: Buffers::Framebuffer,
+                attachment: Buffers::DepthAttachment,
+                renderbuffertarget: Buffers::Renderbuffer,
+                rb: rb.0,
+            },
+        ]
+    );
+    assert_eq!(
+        gl.get_renderbuffer_parameter(Buffers::Renderbuffer, Buffers::RenderbufferWidth),
+        64
+    );
+    let live = gl.live_objects();
+    let rb_size = live
+        .iter()
+        .find(|o| o.kind == ObjectKind::Renderbuffer)
+        .unwrap()
+        .size;
+    assert_eq!(rb_size, 64 * 32 * 2);
+
+    // a failed allocation keeps the previous storage
+    gl.set_error(GlError::Gl(Error::OutOfMemory));
+    assert!(gl
+        .renderbuffer_storage(Buffers::Renderbuffer, Buffers::Depth24Stencil8, 4096, 4096)
+        .is_err());
+    assert_eq!(
+        gl.get_renderbuffer_parameter(Buffers::Renderbuffer, Buffers::RenderbufferWidth),
+        64
+    );
+
+    gl.delete_renderbuffer(&rb);
+    assert_eq!(
+        gl.commands().last(),
+        Some(&GlCommand::DeleteRenderbuffer(rb.0))
+    );
+    gl.delete_framebuffer(&fb);
+    assert!(gl.live_objects().is_empty());
+}
+
+#[test]
+fn rasterizer_calls_are_recorded() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.scissor(10, 20, 300, 200);
+    gl.front_face(FrontFaceDirection::CW);
+    gl.polygon_offset(1.0, 2.0);
+    gl.color_mask(true, true, true, false);
+    gl.line_width(2.0);
+    gl.hint(Hint::GenerateMipmapHint, Hint::Nicest);
+    gl.depth_range(0.0, 0.5);
+    gl.sample_coverage(0.5, true);
+
+    assert_eq!(
+        gl.commands(),
+        vec![
+            GlCommand::Scissor {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 200,
+            },
+            GlCommand::FrontFace(FrontFaceDirection::CW),
+            GlCommand::PolygonOffset(1.0, 2.0),
+            GlCommand::ColorMask(true, true, true, false),
+            GlCommand::LineWidth(2.0),
+            GlCommand::Hint(Hint::GenerateMipmapHint, Hint::Nicest),
+            GlCommand::DepthRange(0.0, 0.5),
+            GlCommand::SampleCoverage(0.5, true),
+        ]
+    );
+}
+
+#[test]
+fn parameters_are_queried_with_their_value_count() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.set_parameter(Parameter::Viewport, &[0.0, 0.0, 800.0, 600.0]);
+    gl.set_parameter(Parameter::MaxTextureSize, &[4096.0]);
+    gl.set_parameter(Parameter::DepthWritemask, &[1.0]);
+    gl.set_parameter_string(Parameter::Renderer, "mock");
+
+    assert_eq!(
+        gl.get_parameter_i32x4(Parameter::Viewport),
+        [0, 0, 800, 600]
+    );
+    assert_eq!(gl.get_parameter_i32(Parameter::MaxTextureSize), 4096);
+    assert!(gl.get_parameter_bool(Parameter::DepthWritemask));
+    assert_eq!(gl.get_parameter_string(Parameter::Renderer), "mock");
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::GetParameter(Parameter::Viewport),
+            GlCommand::GetParameter(Parameter::MaxTextureSize),
+            GlCommand::GetParameter(Parameter::DepthWritemask),
+            GlCommand::GetParameter(Parameter::Renderer),
+        ]
+    );
+
+    // the scalar getters reject multi-value parameters without querying them
+    assert_eq!(gl.get_parameter_i32(Parameter::Viewport), 0);
+    assert!(gl.commands().is_empty());
+    assert_eq!(gl.get_error(), Err(GlError::Gl(Error::InvalidEnum)));
+    assert_eq!(gl.get_parameter_f32(Parameter::DepthRange), 0.0);
+    assert_eq!(gl.get_error(), Err(GlError::Gl(Error::InvalidEnum)));
+
+    // the compressed formats have their own getter
+    assert_eq!(
+        gl.get_parameter_i32x4(Parameter::CompressedTextureFormats),
+        [0; 4]
+    );
+    assert_eq!(gl.get_error(), Err(GlError::Gl(Error::InvalidEnum)));
+    assert!(gl.commands().is_empty());
+}
+
+#[test]
+fn draw_buffer_needs_gles_3() {
+    let mut gl = WebGLRenderingContext::new_mock();
+    gl.common.set_capabilities(Capabilities {
+        api: Api::OpenGlEs,
+        version: (2, 0),
+        ..Capabilities::default()
+    });
+    assert_eq!(
+        gl.draw_buffer(&[ColorBuffer::ColorAttachment0]),
+        Err(GlError::Unsupported("draw buffers: OpenGL ES 2.0"))
+    );
+    assert!(gl.commands().is_empty());
+
+    gl.common.set_capabilities(Capabilities {
+        api: Api::OpenGlEs,
+        version: (3, 0),
+        ..Capabilities::default()
+    });
+    gl.draw_buffer(&[ColorBuffer::ColorAttachment0, ColorBuffer::ColorAttachment1])
+        .unwrap();
+    assert_eq!(
+        gl.commands(),
+        vec![GlCommand::DrawBuffer(vec![
+            ColorBuffer::ColorAttachment0,
+            ColorBuffer::ColorAttachment1,
+        ])]
+    );
+}
+
+#[test]
+fn supported_extensions_come_from_the_capabilities() {
+    let mut gl = WebGLRenderingContext::new_mock();
+    gl.common.set_capabilities(Capabilities {
+        extensions: ["OES_texture_float", "ANGLE_instanced_arrays"]
+            .iter()
+            .map(|&name| name.into())
+            .collect(),
+        ..Capabilities::default()
+    });
+    assert_eq!(
+        gl.get_supported_extensions(),
+        vec!["ANGLE_instanced_arrays", "OES_texture_float"]
+    );
+    assert!(gl.commands().is_empty());
+}
+
+#[test]
+fn timer_queries_need_the_extension() {
+    let mut gl = WebGLRenderingContext::new_mock();
+    assert_eq!(
+        gl.create_timer_query().err(),
+        Some(GlError::Unsupported("timer queries"))
+    );
+    assert!(gl.end_time_elapsed().is_err());
+    assert!(gl.commands().is_empty());
+
+    gl.common.set_extensions(GlExtensions {
+        disjoint_timer_query: Some(Support::Extension("EXT_disjoint_timer_query_webgl2")),
+        ..GlExtensions::default()
+    });
+    let query = gl.create_timer_query().unwrap();
+    gl.begin_time_elapsed(&query).unwrap();
+    gl.draw_arrays(Primitives::Triangles, 3);
+    gl.end_time_elapsed().unwrap();
+    // the GPU is not done yet
+    assert_eq!(gl.get_time_elapsed(&query), Ok(None));
+
+    gl.set_time_elapsed(&query, 1_500_000);
+    assert_eq!(gl.get_time_elapsed(&query), Ok(Some(1_500_000)));
+    gl.set_error(GlError::TimerDisjoint);
+    assert_eq!(gl.get_time_elapsed(&query), Err(GlError::TimerDisjoint));
+    gl.delete_timer_query(&query);
+
+    assert_eq!(
+        gl.commands(),
+        vec![
+            GlCommand::CreateQuery(query.0),
+            GlCommand::BeginTimeElapsed(query.0),
+            GlCommand::DrawArrays {
+                mode: Primitives::Triangles,
+                count: 3,
+            },
+            GlCommand::EndTimeElapsed,
+            GlCommand::GetTimeElapsed(query.0),
+            GlCommand::GetTimeElapsed(query.0),
+            GlCommand::GetTimeElapsed(query.0),
+            GlCommand::DeleteQuery(query.0),
+        ]
+    );
+}
+
+#[test]
+fn object_names_become_labels_with_debug_output() {
+    let mut gl = WebGLRenderingContext::new_mock();
+    let texture = gl.create_texture().unwrap();
+    gl.set_object_name(&texture, "albedo");
+    assert_eq!(gl.live_objects()[0].name, Some("albedo".into()));
+    assert_eq!(gl.commands(), vec![GlCommand::CreateTexture(texture.0)]);
+
+    gl.common.set_extensions(GlExtensions {
+        debug_output: Some(Support::Extension("GL_KHR_debug")),
+        ..GlExtensions::default()
+    });
+    gl.set_object_name(&texture, "normals");
+    assert_eq!(
+        gl.commands().last(),
+        Some(&GlCommand::ObjectLabel {
+            kind: ObjectKind::Texture,
+            object: texture.0,
+            label: "normals".into(),
+        })
+    );
+    gl.delete_texture(&texture);
+}
+
+#[test]
+fn instanced_calls_need_instancing() {
+    let mut gl = WebGLRenderingContext::new_mock();
+    let unsupported = Err(GlError::Unsupported("instanced rendering"));
+    assert_eq!(gl.vertex_attrib_divisor(1, 1), unsupported);
+    assert_eq!(
+        gl.draw_arrays_instanced(Primitives::Triangles, 3, 10),
+        unsupported
+    );
+    assert_eq!(
+        gl.draw_elements_instanced(Primitives::Triangles, 6, DataType::U16, 0, 10),
+        unsupported
+    );
+    assert!(gl.commands().is_empty());
+
+    gl.common.set_extensions(GlExtensions {
+        instanced_arrays: Some(Support::Core),
+        ..GlExtensions::default()
+    });
+    gl.vertex_attrib_divisor(1, 1).unwrap();
+    gl.draw_arrays_instanced(Primitives::Triangles, 3, 10)
+        .unwrap();
+    assert_eq!(
+        gl.commands(),
+        vec![
+            GlCommand::VertexAttribDivisor {
+                location: 1,
+                divisor: 1,
+            },
+            GlCommand::DrawArraysInstanced {
+                mode: Primitives::Triangles,
+                count: 3,
+                instances: 10,
+            },
+        ]
+    );
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "mock")]
+extern crate uni_gl;
+
+use uni_gl::mock::GlCommand;
+use uni_gl::*;
+
+const OPAQUE: PipelineState = PipelineState::new()
+    .depth(DepthState::LESS)
+    .cull(Culling::Back);
+const TRANSPARENT: PipelineState = OPAQUE.blend(BlendState::ALPHA);
+
+#[test]
+fn first_apply_sets_every_part() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.apply_pipeline(&PipelineState::new());
+
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::Disable(Flag::Blend as i32),
+            GlCommand::Disable(Flag::DepthTest as i32),
+            GlCommand::DepthMask(true),
+            GlCommand::Disable(Flag::StencilTest as i32),
+            GlCommand::StencilMask(!0),
+            GlCommand::Disable(Culling::CullFace as i32),
+            GlCommand::Disable(Flag::PolygonOffsetFill as i32),
+            GlCommand::ColorMask(true, true, true, true),
+            GlCommand::Disable(Flag::ScissorTest as i32),
+        ]
+    );
+}
+
+#[test]
+fn only_changed_parts_are_applied() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.apply_pipeline(&OPAQUE);
+    gl.clear_commands();
+
+    gl.apply_pipeline(&OPAQUE);
+    assert!(gl.take_commands().is_empty());
+
+    gl.apply_pipeline(&TRANSPARENT);
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::Enable(Flag::Blend as i32),
+            GlCommand::BlendEquation(BlendEquation::FuncAdd),
+            GlCommand::BlendFunc(BlendMode::SrcAlpha, BlendMode::OneMinusSrcAlpha),
+        ]
+    );
+
+    // only the blend function differs between the two blend states
+    gl.apply_pipeline(&OPAQUE.blend(BlendState::PREMULTIPLIED));
+    assert_eq!(
+        gl.take_commands(),
+        vec![GlCommand::BlendFunc(
+            BlendMode::One,
+            BlendMode::OneMinusSrcAlpha
+        )]
+    );
+
+    gl.apply_pipeline(&OPAQUE.depth(DepthState::READ_ONLY));
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::Disable(Flag::Blend as i32),
+            GlCommand::DepthFunc(DepthTest::Lequal),
+            GlCommand::DepthMask(false),
+        ]
+    );
+}
+
+#[test]
+fn disabling_a_test_restores_its_write_mask() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.apply_pipeline(&PipelineState::new().depth(DepthState::READ_ONLY));
+    gl.clear_commands();
+
+    gl.apply_pipeline(&PipelineState::new());
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::Disable(Flag::DepthTest as i32),
+            GlCommand::DepthMask(true),
+        ]
+    );
+}
+
+#[test]
+fn invalidation_applies_every_part_again() {
+    let gl = WebGLRenderingContext::new_mock();
+    gl.apply_pipeline(&OPAQUE);
+    let first = gl.take_commands();
+
+    gl.invalidate_state();
+    gl.apply_pipeline(&OPAQUE);
+    assert_eq!(gl.take_commands(), first);
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "mock")]
+extern crate uni_gl;
+
+use uni_gl::mock::GlCommand;
+use uni_gl::*;
+
+#[test]
+fn redundant_calls_are_skipped() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.create_buffer().unwrap();
+    let program = gl.create_program().unwrap();
+    gl.set_state_cache(true);
+    gl.clear_commands();
+
+    for _ in 0..2 {
+        gl.bind_buffer(BufferKind::Array, &buffer);
+        gl.use_program(&program);
+        gl.enable(Flag::Blend as i32);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.viewport(0, 0, 640, 480);
+    }
+    gl.viewport(0, 0, 320, 240);
+
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::BindBuffer {
+                kind: BufferKind::Array,
+                buffer: buffer.0,
+            },
+            GlCommand::UseProgram(program.0),
+            GlCommand::Enable(Flag::Blend as i32),
+            GlCommand::ClearColor(0.0, 0.0, 0.0, 1.0),
+            GlCommand::Viewport {
+                x: 0,
+                y: 0,
+                width: 640,
+                height: 480,
+            },
+            GlCommand::Viewport {
+                x: 0,
+                y: 0,
+                width: 320,
+                height: 240,
+            },
+        ]
+    );
+}
+
+#[test]
+fn disabled_cache_forwards_every_call() {
+    let gl = WebGLRenderingContext::new_mock();
+    let program = gl.create_program().unwrap();
+    gl.clear_commands();
+    assert!(!gl.state_cache());
+
+    gl.use_program(&program);
+    gl.use_program(&program);
+    assert_eq!(gl.take_commands().len(), 2);
+}
+
+#[test]
+fn texture_bindings_are_per_unit() {
+    let gl = WebGLRenderingContext::new_mock();
+    let texture = gl.create_texture().unwrap();
+    gl.set_state_cache(true);
+    gl.clear_commands();
+
+    gl.active_texture(0);
+    gl.bind_texture(&texture);
+    gl.active_texture(1);
+    gl.bind_texture(&texture);
+    gl.active_texture(0);
+    gl.bind_texture(&texture);
+
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::ActiveTexture(0),
+            GlCommand::BindTexture(texture.0),
+            GlCommand::ActiveTexture(1),
+            GlCommand::BindTexture(texture.0),
+            GlCommand::ActiveTexture(0),
+        ]
+    );
+}
+
+#[test]
+fn invalidation_resets_the_cache() {
+    let gl = WebGLRenderingContext::new_mock();
+    let program = gl.create_program().unwrap();
+    gl.set_state_cache(true);
+    gl.use_program(&program);
+    gl.depth_mask(false);
+    gl.clear_commands();
+
+    gl.invalidate_state();
+    gl.use_program(&program);
+    gl.depth_mask(false);
+    assert_eq!(
+        gl.take_commands(),
+        vec![
+            GlCommand::UseProgram(program.0),
+            GlCommand::DepthMask(false),
+        ]
+    );
+
+    // turning the cache back on starts from an unknown state too
+    gl.set_state_cache(true);
+    gl.use_program(&program);
+    assert_eq!(gl.take_commands(), vec![GlCommand::UseProgram(program.0)]);
+}
+
+#[test]
+fn deleted_objects_are_unbound() {
+    let gl = WebGLRenderingContext::new_mock();
+    let buffer = gl.create_buffer().unwrap();
+    gl.set_state_cache(true);
+    gl.bind_buffer(BufferKind::Array, &buffer);
+    gl.delete_buffer(&buffer);
+    gl.clear_commands();
+
+    // GL unbound the buffer, so binding the same name again is not redundant
+    gl.bind_buffer(BufferKind::Array, &buffer);
+    assert_eq!(gl.take_commands().len(), 1);
+}
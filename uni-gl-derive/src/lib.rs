@@ -0,0 +1,141 @@
+//! `#[derive(Vertex)]` for uni-gl, enabled in `uni-gl` with the `derive` feature.
+//!
+//! See `uni_gl::Vertex` for usage.
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// implement `uni_gl::Vertex` and `uni_gl::Pod` for a `#[repr(C)]` struct with named fields.
+///
+/// Field attributes :
+/// * `#[vertex(name = "a_position")]` : name of the shader attribute, the field name by default
+/// * `#[vertex(normalized)]` : map integer values to [0, 1] or [-1, 1]
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match vertex(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => compile_error(&e).into(),
+    }
+}
+
+/// like `Error::to_compile_error`, without the `::core` path unavailable to 2015 edition crates
+fn compile_error(e: &Error) -> TokenStream {
+    let message = e.to_string();
+    quote_spanned! { e.span() => compile_error!(#message); }
+}
+
+/// options of a field given with `#[vertex(...)]`
+struct FieldOptions {
+    name: Option<String>,
+    normalized: bool,
+}
+
+fn field_options(field: &syn::Field) -> Result<FieldOptions, Error> {
+    let mut options = FieldOptions {
+        name: None,
+        normalized: false,
+    };
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("normalized") {
+                options.normalized = true;
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                let name: LitStr = meta.value()?.parse()?;
+                options.name = Some(name.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"` or `normalized`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn is_repr_c(input: &DeriveInput) -> bool {
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            }
+            Ok(())
+        });
+    }
+    repr_c
+}
+
+fn vertex(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "#[derive(Vertex)] does not support generic structs",
+        ));
+    }
+    if !is_repr_c(input) {
+        return Err(Error::new(
+            ident.span(),
+            "#[derive(Vertex)] requires #[repr(C)], the field order would be undefined otherwise",
+        ));
+    }
+    let fields = match input.data {
+        Data::Struct(ref s) => match s.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    ident.span(),
+                    "#[derive(Vertex)] requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                ident.span(),
+                "#[derive(Vertex)] only supports structs",
+            ))
+        }
+    };
+
+    let mut attributes = Vec::new();
+    let mut sizes = Vec::new();
+    for field in fields {
+        let options = field_options(field)?;
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let name = options.name.unwrap_or_else(|| field_ident.to_string());
+        let normalized = options.normalized;
+        attributes.push(quote! {
+            .attribute_at(
+                #name,
+                <#ty as ::uni_gl::AttributeType>::SIZE,
+                <#ty as ::uni_gl::AttributeType>::KIND,
+                #normalized,
+                ::std::mem::offset_of!(#ident, #field_ident) as u32,
+            )
+        });
+        sizes.push(quote! { ::std::mem::size_of::<#ty>() });
+    }
+
+    let padding_error = format!("#[derive(Vertex)] {} has padding bytes", ident);
+    Ok(quote! {
+        unsafe impl ::uni_gl::Pod for #ident {}
+
+        const _: () = assert!(
+            ::std::mem::size_of::<#ident>() == 0 #(+ #sizes)*,
+            #padding_error
+        );
+
+        impl ::uni_gl::Vertex for #ident {
+            fn layout() -> ::uni_gl::VertexLayout {
+                ::uni_gl::VertexLayout::new()
+                    #(#attributes)*
+                    .with_stride(::std::mem::size_of::<#ident>() as u32)
+            }
+        }
+    })
+}